@@ -27,13 +27,14 @@ impl<'a> System<'a> for AdjacentAI {
                 let height = map.height;
                 // Add possible reactions to adjacents for each direction
                 if pos.x > 0 {
-                    evaluate(idx - 1, &factions, &my_faction.name, &mut reactions);
+                    evaluate(&map, idx - 1, &factions, &my_faction.name, &mut reactions);
                 }
                 if pos.x < width - 1 {
-                    evaluate(idx + 1, &factions, &my_faction.name, &mut reactions);
+                    evaluate(&map, idx + 1, &factions, &my_faction.name, &mut reactions);
                 }
                 if pos.y > 0 {
                     evaluate(
+                        &map,
                         idx - width as usize,
                         &factions,
                         &my_faction.name,
@@ -42,6 +43,7 @@ impl<'a> System<'a> for AdjacentAI {
                 }
                 if pos.y < height - 1 {
                     evaluate(
+                        &map,
                         idx + width as usize,
                         &factions,
                         &my_faction.name,
@@ -50,6 +52,7 @@ impl<'a> System<'a> for AdjacentAI {
                 }
                 if pos.y > 0 && pos.x > 0 {
                     evaluate(
+                        &map,
                         (idx - width as usize) - 1,
                         &factions,
                         &my_faction.name,
@@ -58,6 +61,7 @@ impl<'a> System<'a> for AdjacentAI {
                 }
                 if pos.y > 0 && pos.x < width - 1 {
                     evaluate(
+                        &map,
                         (idx - width as usize) + 1,
                         &factions,
                         &my_faction.name,
@@ -66,6 +70,7 @@ impl<'a> System<'a> for AdjacentAI {
                 }
                 if pos.y < height - 1 && pos.x > 0 {
                     evaluate(
+                        &map,
                         (idx + width as usize) - 1,
                         &factions,
                         &my_faction.name,
@@ -74,6 +79,7 @@ impl<'a> System<'a> for AdjacentAI {
                 }
                 if pos.y < height - 1 && pos.x < width - 1 {
                     evaluate(
+                        &map,
                         (idx + width as usize) + 1,
                         &factions,
                         &my_faction.name,
@@ -105,12 +111,13 @@ impl<'a> System<'a> for AdjacentAI {
 }
 
 fn evaluate(
+    map: &Map,
     idx: usize,
     factions: &ReadStorage<Faction>,
     my_faction: &str,
     reactions: &mut Vec<(Entity, Reaction)>,
 ) {
-    crate::spatial::for_each_tile_content(idx, |other_entity| {
+    map.spatial.for_each_tile_content(idx, |other_entity| {
         if let Some(faction) = factions.get(other_entity) {
             reactions.push((
                 other_entity,
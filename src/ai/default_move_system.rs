@@ -9,7 +9,7 @@ impl<'a> System<'a> for DefaultMoveAI {
         WriteStorage<'a, MyTurn>,
         WriteStorage<'a, MoveMode>,
         WriteStorage<'a, Position>,
-        ReadExpect<'a, Map>,
+        WriteExpect<'a, Map>,
         WriteStorage<'a, Viewshed>,
         WriteStorage<'a, EntityMoved>,
         WriteExpect<'a, rltk::RandomNumberGenerator>,
@@ -21,7 +21,7 @@ impl<'a> System<'a> for DefaultMoveAI {
             mut turns,
             mut move_mode,
             mut positions,
-            map,
+            mut map,
             mut viewsheds,
             mut entity_moved,
             mut rng,
@@ -55,14 +55,14 @@ impl<'a> System<'a> for DefaultMoveAI {
 
                     if x > 0 && x < map.width - 1 && y > 0 && y < map.height - 1 {
                         let destination_idx = map.xy_idx(x, y);
-                        if !crate::spatial::is_blocked(destination_idx) {
+                        if !map.spatial.is_blocked(destination_idx) {
                             let idx = map.xy_idx(pos.x, pos.y);
                             pos.x = x;
                             pos.y = y;
                             entity_moved
                                 .insert(entity, EntityMoved {})
                                 .expect("Unable to insert marker");
-                            crate::spatial::move_entity(entity, idx, destination_idx);
+                            map.spatial.move_entity(entity, idx, destination_idx);
                             viewshed.dirty = true;
                         }
                     }
@@ -72,14 +72,14 @@ impl<'a> System<'a> for DefaultMoveAI {
                         // We have a target - go there
                         let idx = map.xy_idx(pos.x, pos.y);
                         if path.len() > 1 {
-                            if !crate::spatial::is_blocked(path[1]) {
+                            if !map.spatial.is_blocked(path[1]) {
                                 pos.x = path[1] as i32 % map.width;
                                 pos.y = path[1] as i32 / map.width;
                                 entity_moved
                                     .insert(entity, EntityMoved {})
                                     .expect("Unable to insert marker");
                                 let new_idx = map.xy_idx(pos.x, pos.y);
-                                crate::spatial::move_entity(entity, idx, new_idx);
+                                map.spatial.move_entity(entity, idx, new_idx);
                                 viewshed.dirty = true;
                                 path.remove(0); // Remove the first step in the path
                             }
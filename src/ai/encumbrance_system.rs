@@ -1,4 +1,8 @@
-use crate::{gamelog::GameLog, Attributes, EquipmentChanged, Equipped, InBackpack, Item, Pools};
+use crate::{
+    attribute_bonus, gamelog::GameLog, mana_at_level, player_hp_at_level, Attribute,
+    AttributeBonus, Attributes, EquipmentChanged, Equipped, InBackpack, Item, Pools, Skill,
+    SkillBonus, Skills, Stackable, StatusEffects, StatusKind,
+};
 use specs::prelude::*;
 use std::collections::HashMap;
 
@@ -13,9 +17,14 @@ impl<'a> System<'a> for EncumbranceSystem {
         ReadStorage<'a, InBackpack>,
         ReadStorage<'a, Equipped>,
         WriteStorage<'a, Pools>,
-        ReadStorage<'a, Attributes>,
+        WriteStorage<'a, Attributes>,
+        ReadStorage<'a, StatusEffects>,
         ReadExpect<'a, Entity>,
         WriteExpect<'a, GameLog>,
+        ReadStorage<'a, AttributeBonus>,
+        ReadStorage<'a, SkillBonus>,
+        WriteStorage<'a, Skills>,
+        ReadStorage<'a, Stackable>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -26,9 +35,14 @@ impl<'a> System<'a> for EncumbranceSystem {
             backpacks,
             wielded,
             mut pools,
-            attributes,
+            mut attributes,
+            statuses,
             player,
             mut gamelog,
+            attribute_bonuses,
+            skill_bonuses,
+            mut skills,
+            stacks,
         ) = data;
 
         if equip_dirty.is_empty() {
@@ -53,12 +67,53 @@ impl<'a> System<'a> for EncumbranceSystem {
             }
         }
 
-        // Total up carried items
-        for (item, carried) in (&items, &backpacks).join() {
+        // Total up carried items, counting everything in a stack
+        for (item, carried, stack) in (&items, &backpacks, stacks.maybe()).join() {
             if to_update.contains_key(&carried.owner) {
+                let quantity = stack.map_or(1, |stack| stack.quantity) as f32;
                 let totals = to_update.get_mut(&carried.owner).unwrap();
-                totals.0 += item.weight_lbs;
-                totals.1 += item.initiative_penalty;
+                totals.0 += item.weight_lbs * quantity;
+                totals.1 += item.initiative_penalty * quantity;
+            }
+        }
+
+        // Equipment raises attributes and skills, and weakness saps might
+        for entity in to_update.keys() {
+            let worn = |owner: &Equipped| owner.owner == *entity;
+            if let Some(attr) = attributes.get_mut(*entity) {
+                let mut totals = AttributeBonus {
+                    might: -statuses
+                        .get(*entity)
+                        .map_or(0, |s| s.magnitude(StatusKind::Weakness)),
+                    fitness: 0,
+                    quickness: 0,
+                    intelligence: 0,
+                };
+                for (bonus, _) in (&attribute_bonuses, &wielded)
+                    .join()
+                    .filter(|(_, equipped)| worn(equipped))
+                {
+                    totals.might += bonus.might;
+                    totals.fitness += bonus.fitness;
+                    totals.quickness += bonus.quickness;
+                    totals.intelligence += bonus.intelligence;
+                }
+                set_modifiers(&mut attr.might, totals.might);
+                set_modifiers(&mut attr.fitness, totals.fitness);
+                set_modifiers(&mut attr.quickness, totals.quickness);
+                set_modifiers(&mut attr.intelligence, totals.intelligence);
+            }
+            if let Some(entity_skills) = skills.get_mut(*entity) {
+                let mut modifiers: HashMap<Skill, i32> = HashMap::new();
+                for (bonus, _) in (&skill_bonuses, &wielded)
+                    .join()
+                    .filter(|(_, equipped)| worn(equipped))
+                {
+                    for (skill, amount) in bonus.skills.iter() {
+                        *modifiers.entry(*skill).or_insert(0) += amount;
+                    }
+                }
+                entity_skills.modifiers = modifiers;
             }
         }
 
@@ -69,6 +124,20 @@ impl<'a> System<'a> for EncumbranceSystem {
                 pool.total_initiative_penalty = *initiative;
 
                 if let Some(attr) = attributes.get(*entity) {
+                    // The player's pools follow their Fitness and Intelligence
+                    if *entity == *player {
+                        pool.hit_points.max = player_hp_at_level(
+                            attr.fitness.base + attr.fitness.modifiers,
+                            pool.level,
+                        );
+                        pool.hit_points.current = pool.hit_points.current.min(pool.hit_points.max);
+                        pool.mana.max = mana_at_level(
+                            attr.intelligence.base + attr.intelligence.modifiers,
+                            pool.level,
+                        );
+                        pool.mana.current = pool.mana.current.min(pool.mana.max);
+                    }
+
                     let carry_capacity_lbs = (attr.might.base + attr.might.modifiers) * 15;
                     if pool.total_weight as i32 > carry_capacity_lbs {
                         // Overburdened
@@ -84,3 +153,8 @@ impl<'a> System<'a> for EncumbranceSystem {
         }
     }
 }
+
+fn set_modifiers(attribute: &mut Attribute, modifiers: i32) {
+    attribute.modifiers = modifiers;
+    attribute.bonus = attribute_bonus(attribute.base + attribute.modifiers);
+}
@@ -1,4 +1,4 @@
-use crate::{Attributes, Initiative, MyTurn, Pools, Position, RunState};
+use crate::{Attributes, Initiative, MyTurn, Pools, Position, RunState, StatusEffects};
 use specs::prelude::*;
 
 pub struct InitiativeSystem {}
@@ -16,6 +16,7 @@ impl<'a> System<'a> for InitiativeSystem {
         ReadExpect<'a, Entity>,
         ReadExpect<'a, rltk::Point>,
         ReadStorage<'a, Pools>,
+        ReadStorage<'a, StatusEffects>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -30,6 +31,7 @@ impl<'a> System<'a> for InitiativeSystem {
             player,
             player_pos,
             pools,
+            statuses,
         ) = data;
 
         if *runstate != RunState::Ticking {
@@ -57,7 +59,11 @@ impl<'a> System<'a> for InitiativeSystem {
                 if let Some(pools) = pools.get(entity) {
                     initiative.current += pools.total_initiative_penalty.floor() as i32;
                 }
-                // TODO: More initiative granting boosts
+
+                // Haste and slow
+                if let Some(statuses) = statuses.get(entity) {
+                    initiative.current += statuses.initiative_modifier();
+                }
 
                 // If it's the player, we want to go to an AwaitingInput state
                 if entity == *player {
@@ -1,33 +1,103 @@
-use crate::{Confusion, MyTurn, RunState};
+use crate::{
+    effects::{EffectQueue, EffectType, Targets},
+    gamelog::GameLog,
+    EquipmentChanged, MyTurn, RunState, StatusEffects, StatusKind,
+};
 use specs::prelude::*;
 
+/// Counts down the statuses of whoever's turn it is. Poison and regeneration take effect,
+/// and the confused or paralyzed lose their turn.
 pub struct TurnStatusSystem {}
 
 impl<'a> System<'a> for TurnStatusSystem {
     #[allow(clippy::type_complexity)]
     type SystemData = (
         WriteStorage<'a, MyTurn>,
-        WriteStorage<'a, Confusion>,
+        WriteStorage<'a, StatusEffects>,
+        WriteStorage<'a, EquipmentChanged>,
         Entities<'a>,
-        ReadExpect<'a, RunState>,
+        WriteExpect<'a, RunState>,
+        ReadExpect<'a, Entity>,
+        WriteExpect<'a, GameLog>,
+        WriteExpect<'a, EffectQueue>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut turns, mut confusion, entities, run_state) = data;
+        let (
+            mut turns,
+            mut statuses,
+            mut equipment_changed,
+            entities,
+            mut run_state,
+            player,
+            mut log,
+            mut effect_queue,
+        ) = data;
 
-        if *run_state != RunState::Ticking {
+        // The initiative system has just handed out turns, and waits for the player if one
+        // of them is theirs
+        if *run_state != RunState::Ticking && *run_state != RunState::AwaitingInput {
             return;
         }
 
         let mut not_my_turn: Vec<Entity> = Vec::new();
-        let mut not_confused: Vec<Entity> = Vec::new();
+        let mut recovered: Vec<Entity> = Vec::new();
 
-        for (entity, _turn, confused) in (&entities, &mut turns, &mut confusion).join() {
-            confused.turns -= 1;
-            if confused.turns < 1 {
-                not_confused.push(entity);
-            } else {
+        for (entity, _turn, effects) in (&entities, &turns, &mut statuses).join() {
+            let mut skip_turn = None;
+            for status in effects.effects.iter_mut() {
+                match status.kind {
+                    StatusKind::Poison => effect_queue.add_effect(
+                        None,
+                        EffectType::Damage {
+                            amount: status.magnitude,
+                        },
+                        Targets::Single { target: entity },
+                    ),
+                    StatusKind::Regeneration => effect_queue.add_effect(
+                        None,
+                        EffectType::Healing {
+                            amount: status.magnitude,
+                        },
+                        Targets::Single { target: entity },
+                    ),
+                    kind if kind.skips_turn() => skip_turn = Some(kind),
+                    _ => {}
+                }
+                status.turns -= 1;
+            }
+
+            if let Some(kind) = skip_turn {
                 not_my_turn.push(entity);
+                if entity == *player {
+                    log.entries.push(format!(
+                        "You are {}, and lose your turn.",
+                        kind.name().to_lowercase()
+                    ));
+                    *run_state = RunState::Ticking;
+                }
+            }
+
+            let mut worn_off: Vec<StatusKind> = Vec::new();
+            for status in effects.effects.iter().filter(|s| s.turns < 1) {
+                if !worn_off.contains(&status.kind) {
+                    worn_off.push(status.kind);
+                }
+            }
+            effects.effects.retain(|status| status.turns > 0);
+            for kind in worn_off.into_iter().filter(|kind| !effects.has(*kind)) {
+                if kind == StatusKind::Weakness {
+                    equipment_changed
+                        .insert(entity, EquipmentChanged {})
+                        .expect("Unable to insert");
+                }
+                if entity == *player {
+                    log.entries
+                        .push(format!("You are no longer {}.", kind.name().to_lowercase()));
+                }
+            }
+            if effects.effects.is_empty() {
+                recovered.push(entity);
             }
         }
 
@@ -35,8 +105,8 @@ impl<'a> System<'a> for TurnStatusSystem {
             turns.remove(entity);
         }
 
-        for entity in not_confused {
-            confusion.remove(entity);
+        for entity in recovered {
+            statuses.remove(entity);
         }
     }
 }
@@ -1,5 +1,8 @@
 use crate::{
-    raws::Reaction, Chasing, Faction, Map, MyTurn, Position, Viewshed, WantsToApproach, WantsToFlee,
+    equipped_ranged_weapon, find_ammo,
+    raws::{spell_info, Reaction, RAWS},
+    Ammunition, Chasing, Equipped, Faction, InBackpack, KnownSpells, Map, MyTurn, Pools, Position,
+    RangedWeapon, Viewshed, WantsToApproach, WantsToCastSpell, WantsToFlee, WantsToShoot,
 };
 use specs::prelude::*;
 
@@ -18,11 +21,19 @@ impl<'a> System<'a> for VisibleAI {
         ReadExpect<'a, Entity>,
         ReadStorage<'a, Viewshed>,
         WriteStorage<'a, Chasing>,
+        ReadStorage<'a, Equipped>,
+        ReadStorage<'a, RangedWeapon>,
+        ReadStorage<'a, InBackpack>,
+        ReadStorage<'a, Ammunition>,
+        WriteStorage<'a, WantsToShoot>,
+        ReadStorage<'a, KnownSpells>,
+        ReadStorage<'a, Pools>,
+        WriteStorage<'a, WantsToCastSpell>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
         let (
-            turns,
+            mut turns,
             factions,
             positions,
             map,
@@ -32,8 +43,17 @@ impl<'a> System<'a> for VisibleAI {
             player,
             viewsheds,
             mut chasing,
+            equipped,
+            ranged_weapons,
+            backpack,
+            ammunition,
+            mut want_shoot,
+            known_spells,
+            pools,
+            mut want_cast,
         ) = data;
 
+        let mut turn_done: Vec<Entity> = Vec::new();
         for (entity, _turn, my_faction, pos, viewshed) in
             (&entities, &turns, &factions, &positions, &viewsheds).join()
         {
@@ -44,10 +64,65 @@ impl<'a> System<'a> for VisibleAI {
                 for visible_tile in viewshed.visible_tiles.iter() {
                     let idx = map.xy_idx(visible_tile.x, visible_tile.y);
                     if my_idx != idx {
-                        evaluate(idx, &factions, &my_faction.name, &mut reactions);
+                        evaluate(&map, idx, &factions, &my_faction.name, &mut reactions);
                     }
                 }
 
+                let in_range = |reaction: &&(usize, Reaction, Entity), range: i32| {
+                    let target = rltk::Point::new(
+                        reaction.0 as i32 % map.width,
+                        reaction.0 as i32 / map.width,
+                    );
+                    reaction.1 == Reaction::Attack
+                        && rltk::DistanceAlg::Pythagoras
+                            .distance2d(rltk::Point::new(pos.x, pos.y), target)
+                            <= range as f32
+                };
+
+                // Casters with the mana for an aimed spell cast it at anything in range
+                let mana = pools.get(entity).map_or(0, |pools| pools.mana.current);
+                let cast = known_spells.get(entity).and_then(|known| {
+                    let raws = RAWS.lock().unwrap();
+                    known.spells.iter().find_map(|spell| {
+                        let info = spell_info(&raws, spell)?;
+                        if info.mana_cost > mana {
+                            return None;
+                        }
+                        let range = info.range?;
+                        let reaction = reactions.iter().find(|r| in_range(r, range))?;
+                        Some(WantsToCastSpell {
+                            spell: spell.clone(),
+                            target: Some(rltk::Point::new(
+                                reaction.0 as i32 % map.width,
+                                reaction.0 as i32 / map.width,
+                            )),
+                        })
+                    })
+                });
+                if let Some(cast) = cast {
+                    want_cast.insert(entity, cast).expect("Unable to insert");
+                    turn_done.push(entity);
+                    continue;
+                }
+
+                // Archers with something to shoot stand and shoot at anything in range
+                let shot = equipped_ranged_weapon(entity, &equipped, &ranged_weapons)
+                    .filter(|weapon| {
+                        find_ammo(entity, &weapon.ammo, &entities, &backpack, &ammunition).is_some()
+                    })
+                    .and_then(|weapon| {
+                        reactions
+                            .iter()
+                            .find(|reaction| in_range(reaction, weapon.range))
+                    });
+                if let Some(reaction) = shot {
+                    want_shoot
+                        .insert(entity, WantsToShoot { target: reaction.2 })
+                        .expect("Unable to insert");
+                    turn_done.push(entity);
+                    continue;
+                }
+
                 let mut done = false;
                 for reaction in reactions.iter() {
                     match reaction.1 {
@@ -79,16 +154,22 @@ impl<'a> System<'a> for VisibleAI {
                 }
             }
         }
+
+        // remove turn marker for those that are done
+        for done in turn_done.iter() {
+            turns.remove(*done);
+        }
     }
 }
 
 fn evaluate(
+    map: &Map,
     idx: usize,
     factions: &ReadStorage<Faction>,
     my_faction: &str,
     reactions: &mut Vec<(usize, Reaction, Entity)>,
 ) {
-    crate::spatial::for_each_tile_content(idx, |other_entity| {
+    map.spatial.for_each_tile_content(idx, |other_entity| {
         if let Some(faction) = factions.get(other_entity) {
             reactions.push((
                 idx,
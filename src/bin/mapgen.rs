@@ -0,0 +1,409 @@
+//! Builds a single level without the game and prints it, so builder chains can be tried
+//! out quickly. Run with `--help` for the options.
+
+use rltk::RandomNumberGenerator;
+use roguelike_rust::map_builders::*;
+use roguelike_rust::{load_raws, Map, MasterDungeonMap, TileType};
+
+const USAGE: &str = "\
+Usage: mapgen [options]
+
+  --builder NAME   level (default), random, or a starting builder (see --list)
+  --with NAME      add a meta builder; repeat to build a chain (see --list)
+  --depth N        dungeon depth, default 1
+  --seed N         run seed, as shown in game; a level matches the game's for the same
+                   seed and depth. Random if omitted
+  --width N        map width, default 80
+  --height N       map height, default 50
+  --format FORMAT  ascii (default), json, or history to print every build step
+  --list           list the builder names";
+
+const STARTING_BUILDERS: &[&str] = &[
+    "simple",
+    "bsp-dungeon",
+    "bsp-interior",
+    "cellular-automata",
+    "drunkard-open-area",
+    "drunkard-open-halls",
+    "drunkard-winding-passages",
+    "drunkard-fat-passages",
+    "drunkard-fearful-symmetry",
+    "maze",
+    "dla-walk-inwards",
+    "dla-walk-outwards",
+    "dla-central-attractor",
+    "dla-insectoid",
+    "dla-heavy-erosion",
+    "voronoi-pythagoras",
+    "voronoi-manhattan",
+    "prefab-wfc-populated",
+];
+
+const META_BUILDERS: &[&str] = &[
+    "room-drawer",
+    "sort-leftmost",
+    "sort-rightmost",
+    "sort-topmost",
+    "sort-bottommost",
+    "sort-central",
+    "corridors-dogleg",
+    "corridors-nearest",
+    "corridors-lines",
+    "corridors-bsp",
+    "corridor-spawner",
+    "room-exploder",
+    "room-corner-rounder",
+    "room-start",
+    "room-stairs",
+    "room-spawner",
+    "center-start",
+    "cull-unreachable",
+    "voronoi-spawning",
+    "distant-exit",
+    "up-stairs",
+    "wfc",
+    "underground-fort",
+    "doors",
+    "vaults",
+];
+
+#[derive(PartialEq)]
+enum Format {
+    Ascii,
+    Json,
+    History,
+}
+
+struct Options {
+    builder: String,
+    meta_builders: Vec<String>,
+    depth: i32,
+    seed: Option<u64>,
+    width: i32,
+    height: i32,
+    format: Format,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        builder: "level".to_string(),
+        meta_builders: Vec::new(),
+        depth: 1,
+        seed: None,
+        width: 80,
+        height: 50,
+        format: Format::Ascii,
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            "--list" => {
+                println!("Starting builders:\n  level\n  random");
+                for name in STARTING_BUILDERS.iter() {
+                    println!("  {}", name);
+                }
+                println!("Meta builders:");
+                for name in META_BUILDERS.iter() {
+                    println!("  {}", name);
+                }
+                std::process::exit(0);
+            }
+            _ => {}
+        }
+
+        let value = args
+            .next()
+            .ok_or_else(|| format!("{} needs a value", arg))?;
+        match arg.as_str() {
+            "--builder" => options.builder = value,
+            "--with" => options.meta_builders.push(value),
+            "--depth" => options.depth = parse_number(&arg, &value)?,
+            "--seed" => options.seed = Some(parse_number(&arg, &value)?),
+            "--width" => options.width = parse_number(&arg, &value)?,
+            "--height" => options.height = parse_number(&arg, &value)?,
+            "--format" => {
+                options.format = match value.as_str() {
+                    "ascii" => Format::Ascii,
+                    "json" => Format::Json,
+                    "history" => Format::History,
+                    _ => return Err(format!("Unknown format {}", value)),
+                }
+            }
+            _ => return Err(format!("Unknown option {}", arg)),
+        }
+    }
+
+    if options.width < 3 || options.height < 3 {
+        return Err("The map must be at least 3x3".to_string());
+    }
+
+    Ok(options)
+}
+
+fn parse_number<T: std::str::FromStr>(arg: &str, value: &str) -> Result<T, String> {
+    value
+        .parse::<T>()
+        .map_err(|_| format!("{} expects a number, not {}", arg, value))
+}
+
+fn starting_builder(name: &str) -> Option<Box<dyn InitialMapBuilder>> {
+    let builder: Box<dyn InitialMapBuilder> = match name {
+        "simple" => SimpleMapBuilder::new(),
+        "bsp-dungeon" => BspDungeonBuilder::new(),
+        "bsp-interior" => BspInteriorBuilder::new(),
+        "cellular-automata" => CellularAutomataBuilder::new(),
+        "drunkard-open-area" => DrunkardsWalkBuilder::open_area(),
+        "drunkard-open-halls" => DrunkardsWalkBuilder::open_halls(),
+        "drunkard-winding-passages" => DrunkardsWalkBuilder::winding_passages(),
+        "drunkard-fat-passages" => DrunkardsWalkBuilder::fat_passages(),
+        "drunkard-fearful-symmetry" => DrunkardsWalkBuilder::fearful_symmetry(),
+        "maze" => MazeBuilder::new(),
+        "dla-walk-inwards" => DLABuilder::walk_inwards(),
+        "dla-walk-outwards" => DLABuilder::walk_outwards(),
+        "dla-central-attractor" => DLABuilder::central_attractor(),
+        "dla-insectoid" => DLABuilder::insectoid(),
+        "dla-heavy-erosion" => DLABuilder::heavy_erosion(),
+        "voronoi-pythagoras" => VoronoiCellBuilder::pythagoras(),
+        "voronoi-manhattan" => VoronoiCellBuilder::manhattan(),
+        "prefab-wfc-populated" => PrefabBuilder::constant(prefab_levels::WFC_POPULATED),
+        _ => return None,
+    };
+    Some(builder)
+}
+
+fn meta_builder(name: &str) -> Option<Box<dyn MetaMapBuilder>> {
+    let builder: Box<dyn MetaMapBuilder> = match name {
+        "room-drawer" => RoomDrawer::new(),
+        "sort-leftmost" => RoomSorter::new(RoomSort::LeftMost),
+        "sort-rightmost" => RoomSorter::new(RoomSort::RightMost),
+        "sort-topmost" => RoomSorter::new(RoomSort::TopMost),
+        "sort-bottommost" => RoomSorter::new(RoomSort::BottomMost),
+        "sort-central" => RoomSorter::new(RoomSort::Central),
+        "corridors-dogleg" => DoglegCorridors::new(),
+        "corridors-nearest" => NearestCorridors::new(),
+        "corridors-lines" => StraightLineCorridors::new(),
+        "corridors-bsp" => BspCorridors::new(),
+        "corridor-spawner" => CorridorSpawner::new(),
+        "room-exploder" => RoomExploder::new(),
+        "room-corner-rounder" => RoomCornerRounder::new(),
+        "room-start" => RoomBasedStartingPosition::new(),
+        "room-stairs" => RoomBasedStairs::new(),
+        "room-spawner" => RoomBasedSpawner::new(),
+        "center-start" => AreaStartingPosition::new(XStart::Center, YStart::Center),
+        "cull-unreachable" => CullUnreachable::new(),
+        "voronoi-spawning" => VoronoiSpawning::new(),
+        "distant-exit" => DistantExit::new(),
+        "up-stairs" => UpStairsPlacement::new(),
+        "wfc" => WaveformCollapseBuilder::new(),
+        "underground-fort" => PrefabBuilder::sectional(prefab_sections::UNDERGROUND_FORT),
+        "doors" => DoorPlacement::new(),
+        "vaults" => PrefabBuilder::vaults(),
+        _ => return None,
+    };
+    Some(builder)
+}
+
+/// The meta builders a starting builder gets when none are given, enough to give it a
+/// start, an exit and some spawns.
+fn default_meta_builders(starter: &str) -> Vec<&'static str> {
+    match starter {
+        "simple" | "bsp-dungeon" => vec![
+            "room-drawer",
+            "corridors-dogleg",
+            "room-start",
+            "room-stairs",
+            "room-spawner",
+        ],
+        "bsp-interior" => vec!["room-start", "room-stairs", "room-spawner"],
+        _ => vec![
+            "center-start",
+            "cull-unreachable",
+            "voronoi-spawning",
+            "distant-exit",
+        ],
+    }
+}
+
+fn build_chain(options: &Options, rng: &mut RandomNumberGenerator) -> Result<BuilderChain, String> {
+    let (width, height) = (options.width, options.height);
+    let mut chain = match options.builder.as_str() {
+        "level" => level_builder(options.depth, rng, width, height),
+        "random" => random_builder(options.depth, rng, width, height),
+        name => {
+            let starter = starting_builder(name)
+                .ok_or_else(|| format!("Unknown builder {}; try --list", name))?;
+            let mut chain = BuilderChain::new(options.depth, width, height, name);
+            chain.start_with(starter);
+            if options.meta_builders.is_empty() {
+                for meta in default_meta_builders(name) {
+                    chain.with(meta_builder(meta).unwrap());
+                }
+            }
+            chain
+        }
+    };
+
+    for name in options.meta_builders.iter() {
+        let meta = meta_builder(name)
+            .ok_or_else(|| format!("Unknown meta builder {}; try --list", name))?;
+        chain.with(meta);
+    }
+
+    Ok(chain)
+}
+
+/// Runs the chain, turning a builder's panic (most often from a map too small for it) into
+/// an error rather than a crash.
+fn build_map(chain: &mut BuilderChain, rng: &mut RandomNumberGenerator) -> Result<(), String> {
+    std::panic::set_hook(Box::new(|_| {}));
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| chain.build_map(rng)));
+    let _ = std::panic::take_hook();
+    result.map_err(|panic| {
+        panic
+            .downcast_ref::<&str>()
+            .map(|msg| msg.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "a builder panicked".to_string())
+    })
+}
+
+fn tile_char(tile: TileType) -> char {
+    match tile {
+        TileType::Wall => '#',
+        TileType::Stalactite => '|',
+        TileType::Stalagmite => '!',
+        TileType::Floor => '.',
+        TileType::DownStairs => '>',
+        TileType::UpStairs => '<',
+        TileType::Road => '=',
+        TileType::Grass => '"',
+        TileType::ShallowWater => '~',
+        TileType::DeepWater => '≈',
+        TileType::WoodFloor => ',',
+        TileType::Bridge => ':',
+        TileType::Gravel => ';',
+    }
+}
+
+fn ascii_rows(map: &Map, start: Option<(i32, i32)>) -> Vec<String> {
+    (0..map.height)
+        .map(|y| {
+            (0..map.width)
+                .map(|x| {
+                    if start == Some((x, y)) {
+                        '@'
+                    } else {
+                        tile_char(map.tiles[map.xy_idx(x, y)])
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn main() {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    for problem in load_raws() {
+        eprintln!("Skipped a mod: {}", problem);
+    }
+
+    // Seed the builder the same way the game seeds this depth
+    let seed = options
+        .seed
+        .unwrap_or_else(|| RandomNumberGenerator::new().next_u64());
+    let mut rng =
+        RandomNumberGenerator::seeded(MasterDungeonMap::new(seed).depth_seed(options.depth));
+
+    let mut chain = match build_chain(&options, &mut rng) {
+        Ok(chain) => chain,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    chain.build_data.record_history = options.format == Format::History;
+    if let Err(e) = build_map(&mut chain, &mut rng) {
+        eprintln!("The build failed: {}", e);
+        eprintln!("Some builders need a bigger map, or a chain that gives them rooms to use.");
+        std::process::exit(1);
+    }
+
+    let build_data = &chain.build_data;
+    let map = &build_data.map;
+    let start = build_data
+        .starting_position
+        .as_ref()
+        .map(|pos| (pos.x, pos.y));
+    // A roll of "None" on a spawn table spawns nothing in the game, so it isn't listed
+    let spawns: Vec<(i32, i32, &str)> = build_data
+        .spawn_list
+        .iter()
+        .filter(|(_, name)| name != "None")
+        .map(|(idx, name)| {
+            (
+                *idx as i32 % map.width,
+                *idx as i32 / map.width,
+                name.as_str(),
+            )
+        })
+        .collect();
+
+    match options.format {
+        Format::Json => {
+            let output = serde_json::json!({
+                "seed": seed,
+                "depth": options.depth,
+                "builder": options.builder,
+                "name": map.name,
+                "width": map.width,
+                "height": map.height,
+                "outdoors": map.outdoors,
+                "tiles": map.tiles,
+                "rows": ascii_rows(map, None),
+                "starting_position": start.map(|(x, y)| serde_json::json!({ "x": x, "y": y })),
+                "spawn_list": spawns
+                    .iter()
+                    .map(|(x, y, name)| serde_json::json!({ "x": x, "y": y, "name": name }))
+                    .collect::<Vec<_>>(),
+            });
+            println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        }
+        Format::Ascii | Format::History => {
+            if options.format == Format::History {
+                let frames = build_data.history.len();
+                for (i, frame) in build_data.history.iter().enumerate() {
+                    println!("--- Step {} of {} ---", i + 1, frames);
+                    for row in ascii_rows(frame, None) {
+                        println!("{}", row);
+                    }
+                }
+                println!("--- Result ---");
+            }
+
+            println!("{} (depth {}, seed {})", map.name, options.depth, seed);
+            for row in ascii_rows(map, start) {
+                println!("{}", row);
+            }
+            match start {
+                Some((x, y)) => println!("Start: {},{}", x, y),
+                None => println!("Start: none"),
+            }
+            println!("Spawns: {}", spawns.len());
+            for (x, y, name) in spawns.iter() {
+                println!("  {},{} {}", x, y, name);
+            }
+        }
+    }
+}
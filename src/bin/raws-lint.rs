@@ -0,0 +1,59 @@
+//! Checks raws files and lists every problem in them, without starting the game. Exits
+//! with status 1 if there are any.
+
+use roguelike_rust::{check_raw_files, read_raw_dir, RawFile, MOD_DIR};
+use std::path::Path;
+
+const USAGE: &str = "\
+Usage: raws-lint [PATH...]
+
+  PATH  a raws file, or a directory of them, merged in the order given as the game
+        merges its raws and mods. Default: raws, then mods if there is one";
+
+fn main() {
+    let mut paths: Vec<String> = std::env::args().skip(1).collect();
+    if paths.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", USAGE);
+        return;
+    }
+    if paths.is_empty() {
+        paths.push("raws".to_string());
+        if Path::new(MOD_DIR).is_dir() {
+            paths.push(MOD_DIR.to_string());
+        }
+    }
+
+    let mut files = Vec::new();
+    for path in paths.iter().map(Path::new) {
+        let read = if path.is_dir() {
+            read_raw_dir(path)
+        } else {
+            RawFile::read(path).map(|file| vec![file])
+        };
+        match read {
+            Ok(read) => files.extend(read),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    match check_raw_files(&files) {
+        Ok(raws) => println!(
+            "{} file(s) ok: {} items, {} mobs, {} props, {} spells",
+            files.len(),
+            raws.items.len(),
+            raws.mobs.len(),
+            raws.props.len(),
+            raws.spells.len()
+        ),
+        Err(errors) => {
+            for error in errors.iter() {
+                println!("{}", error);
+            }
+            eprintln!("{} problem(s) found", errors.len());
+            std::process::exit(1);
+        }
+    }
+}
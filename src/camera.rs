@@ -38,7 +38,7 @@ pub fn render_camera(ecs: &World, ctx: &mut Rltk) {
     let mut data = (&position, &rendarables, !&hidden)
         .join()
         .collect::<Vec<_>>();
-    data.sort_by(|&a, &b| b.1.render_order.cmp(&a.1.render_order));
+    data.sort_by_key(|a| std::cmp::Reverse(a.1.render_order));
 
     for (pos, render, _hidden) in data.iter() {
         let idx = map.xy_idx(pos.x, pos.y);
@@ -47,6 +47,11 @@ pub struct WantsToMelee {
     pub target: Entity,
 }
 
+#[derive(Component, Debug, Clone, ConvertSaveload)]
+pub struct WantsToShoot {
+    pub target: Entity,
+}
+
 #[derive(Component, Debug, Clone, ConvertSaveload)]
 pub struct SufferDamage {
     pub amount: Vec<(i32, bool)>,
@@ -114,34 +119,28 @@ pub struct Ranged {
 
 #[derive(Component, Debug, Clone, ConvertSaveload)]
 pub struct InflictsDamage {
-    pub damage: i32,
+    pub n_dice: i32,
+    pub die_type: i32,
+    pub bonus: i32,
 }
 
-#[derive(Component, Debug, Clone, ConvertSaveload)]
-pub struct AreaOfEffect {
-    pub radius: i32,
+impl InflictsDamage {
+    pub fn roll(&self, rng: &mut rltk::RandomNumberGenerator) -> i32 {
+        rng.roll_dice(self.n_dice, self.die_type) + self.bonus
+    }
 }
 
 #[derive(Component, Debug, Clone, ConvertSaveload)]
-pub struct Confusion {
-    pub turns: i32,
+pub struct AreaOfEffect {
+    pub radius: i32,
 }
 
 pub struct SerializeMe;
 
-#[derive(Component, Serialize, Deserialize, Clone)]
-pub struct SerializationHelper {
-    pub map: super::map::Map,
-}
-
-#[derive(Component, Serialize, Deserialize, Clone)]
-pub struct DMSerializationHelper {
-    pub map: super::map::MasterDungeonMap,
-}
-
 #[derive(PartialEq, Copy, Clone, Serialize, Deserialize)]
 pub enum EquipmentSlot {
     Melee,
+    Ranged,
     Shield,
     Head,
     Torso,
@@ -167,6 +166,22 @@ pub enum WeaponAttribute {
     Quickness,
 }
 
+#[derive(Component, ConvertSaveload, Clone)]
+pub struct RangedWeapon {
+    pub range: i32,
+    pub ammo: String,
+    pub damage_n_dice: i32,
+    pub damage_die_type: i32,
+    pub damage_bonus: i32,
+    pub hit_bonus: i32,
+}
+
+/// Fired from a ranged weapon whose `ammo` is the same `kind`.
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct Ammunition {
+    pub kind: String,
+}
+
 #[derive(Component, ConvertSaveload, Clone)]
 pub struct MeleeWeapon {
     pub attribute: WeaponAttribute,
@@ -266,16 +281,42 @@ pub struct Attributes {
     pub intelligence: Attribute,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, Hash)]
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum AttributeName {
+    Might,
+    Fitness,
+    Quickness,
+    Intelligence,
+}
+
+impl Attributes {
+    pub fn attribute_mut(&mut self, name: AttributeName) -> &mut Attribute {
+        match name {
+            AttributeName::Might => &mut self.might,
+            AttributeName::Fitness => &mut self.fitness,
+            AttributeName::Quickness => &mut self.quickness,
+            AttributeName::Intelligence => &mut self.intelligence,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Eq, PartialEq, Hash)]
 pub enum Skill {
     Melee,
     Defense,
     Magic,
+    Ranged,
 }
 
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Skills {
     pub skills: HashMap<Skill, i32>,
+    /// Uses of each skill since it last improved
+    #[serde(default)]
+    pub practice: HashMap<Skill, i32>,
+    /// Added to each skill by equipment
+    #[serde(default)]
+    pub modifiers: HashMap<Skill, i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -294,6 +335,12 @@ pub struct Pools {
     pub total_initiative_penalty: f32,
     pub gold: f32,
     pub god_mode: bool,
+    /// Tenths of a hit point regained towards the next whole one
+    #[serde(default)]
+    pub hp_regen_progress: i32,
+    /// Tenths of a point of mana regained towards the next whole one
+    #[serde(default)]
+    pub mana_regen_progress: i32,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -421,3 +468,222 @@ pub struct ObfuscatedName {
 pub struct IdentifiedItem {
     pub name: String,
 }
+
+/// The spells an entity can cast, by their name in the raws.
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+pub struct KnownSpells {
+    pub spells: Vec<String>,
+}
+
+/// Marks the short-lived entity a spell's effects are built on while it is cast.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct SpellTemplate {
+    pub mana_cost: i32,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct WantsToCastSpell {
+    pub spell: String,
+    pub target: Option<rltk::Point>,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct TeachesSpell {
+    pub spell: String,
+}
+
+/// A kind of timed status effect. In the raws and saves they're written in snake_case.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Eq, PartialEq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusKind {
+    /// Loses turns
+    Confusion,
+    /// Loses `magnitude` hp a turn
+    Poison,
+    /// Heals `magnitude` hp a turn
+    Regeneration,
+    /// Waits `magnitude` fewer ticks between turns
+    Haste,
+    /// Waits `magnitude` more ticks between turns
+    Slow,
+    /// Might is lowered by `magnitude`
+    Weakness,
+    /// Loses turns
+    Paralysis,
+}
+
+impl StatusKind {
+    /// What the status is called next to the health bar.
+    pub fn name(self) -> &'static str {
+        match self {
+            StatusKind::Confusion => "Confused",
+            StatusKind::Poison => "Poisoned",
+            StatusKind::Regeneration => "Regenerating",
+            StatusKind::Haste => "Hasted",
+            StatusKind::Slow => "Slowed",
+            StatusKind::Weakness => "Weakened",
+            StatusKind::Paralysis => "Paralyzed",
+        }
+    }
+
+    /// Doses of poison add up; any other status is only refreshed by another of its kind.
+    pub fn stacks(self) -> bool {
+        self == StatusKind::Poison
+    }
+
+    /// Whether the status does anything with its magnitude.
+    pub fn has_magnitude(self) -> bool {
+        !matches!(self, StatusKind::Confusion | StatusKind::Paralysis)
+    }
+
+    /// Whether whoever has it loses their turns.
+    pub fn skips_turn(self) -> bool {
+        matches!(self, StatusKind::Confusion | StatusKind::Paralysis)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Status {
+    pub kind: StatusKind,
+    pub turns: i32,
+    #[serde(default)]
+    pub magnitude: i32,
+}
+
+/// The statuses an entity is under, each counted down on its own turns.
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+pub struct StatusEffects {
+    pub effects: Vec<Status>,
+}
+
+impl StatusEffects {
+    /// Adds a status, or refreshes the one of its kind already there to whichever lasts
+    /// longer and is stronger.
+    pub fn add(&mut self, status: Status) {
+        if !status.kind.stacks() {
+            if let Some(existing) = self.effects.iter_mut().find(|s| s.kind == status.kind) {
+                existing.turns = existing.turns.max(status.turns);
+                existing.magnitude = existing.magnitude.max(status.magnitude);
+                return;
+            }
+        }
+        self.effects.push(status);
+    }
+
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.effects.iter().any(|s| s.kind == kind)
+    }
+
+    /// The summed magnitude of every status of a kind.
+    pub fn magnitude(&self, kind: StatusKind) -> i32 {
+        self.effects
+            .iter()
+            .filter(|s| s.kind == kind)
+            .map(|s| s.magnitude)
+            .sum()
+    }
+
+    /// How many more ticks to wait between turns; negative when hasted.
+    pub fn initiative_modifier(&self) -> i32 {
+        self.magnitude(StatusKind::Slow) - self.magnitude(StatusKind::Haste)
+    }
+}
+
+/// Puts its statuses on whatever it hits, like a poisoned dart or a scroll of confusion.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct InflictsStatus {
+    pub statuses: Vec<Status>,
+}
+
+/// Equipment that speeds up (or slows down) its wearer's natural regeneration, in tenths of
+/// a point a turn.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct RegenerationBonus {
+    pub hp: i32,
+    pub mana: i32,
+}
+
+/// Equipment that raises (or lowers) its wearer's attributes.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct AttributeBonus {
+    pub might: i32,
+    pub fitness: i32,
+    pub quickness: i32,
+    pub intelligence: i32,
+}
+
+/// Equipment that raises (or lowers) its wearer's skills.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct SkillBonus {
+    pub skills: HashMap<Skill, i32>,
+}
+
+/// Equipment that can't be taken off once it's on, until the curse is lifted.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct CursedItem {}
+
+/// Lifts the curses on whatever the user has equipped.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct ProvidesRemoveCurse {}
+
+/// Identical items carried as one, e.g. a stack of potions. Picking up or buying another
+/// adds it to the stack.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Stackable {
+    pub quantity: i32,
+}
+
+/// An item that can be used a number of times before it's spent. Spent items are kept, so
+/// they can be recharged.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Charges {
+    pub remaining: i32,
+    pub max: i32,
+    /// Each recharge makes the next more likely to blow it up
+    pub times_recharged: i32,
+}
+
+/// Reading it lets the player pick a charged item to recharge.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct ProvidesRecharge {}
+
+/// A vendor who recharges items for gold.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Recharger {
+    pub price: f32,
+}
+
+/// Reading it lets the player pick an unidentified item to identify.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct ProvidesIdentification {}
+
+/// A prop that holds items, such as a chest. What's in it is carried by it, as if in a
+/// backpack, so it stays with the container when the level is left.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Container {}
+
+/// A container that has to be picked open first.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Locked {
+    pub difficulty: i32,
+}
+
+/// A container with a trap that goes off on whoever opens it first. The trap is an entity of
+/// its own, holding the effects, so they go with it once it has gone off.
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct Trapped {
+    pub trap: Entity,
+}
+
+/// Someone in town who lifts curses for gold.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Temple {
+    pub curse_price: f32,
+}
+
+/// Points from levelling up that the player hasn't spent yet.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct LevelUpPoints {
+    pub attributes: i32,
+    pub skills: i32,
+}
@@ -1,6 +1,6 @@
 use super::*;
-use crate::components::{Attributes, Confusion, Player, Pools};
-use crate::game_system::{mana_at_level, player_hp_at_level};
+use crate::components::{Attributes, LevelUpPoints, Player, Pools};
+use crate::game_system::level_up;
 use crate::gamelog::GameLog;
 use crate::map::Map;
 
@@ -10,8 +10,14 @@ pub fn inflict_damage(ecs: &mut World, damage: &EffectSpawner, target: Entity) {
         if !pool.god_mode {
             if let EffectType::Damage { amount } = damage.effect_type {
                 pool.hit_points.current -= amount;
-                add_effect(None, EffectType::Bloodstain, Targets::Single { target });
                 add_effect(
+                    ecs,
+                    None,
+                    EffectType::Bloodstain,
+                    Targets::Single { target },
+                );
+                add_effect(
+                    ecs,
                     None,
                     EffectType::Particle {
                         glyph: rltk::to_cp437('‼'),
@@ -24,6 +30,7 @@ pub fn inflict_damage(ecs: &mut World, damage: &EffectSpawner, target: Entity) {
 
                 if pool.hit_points.current < 1 {
                     add_effect(
+                        ecs,
                         damage.creator,
                         EffectType::EntityDeath,
                         Targets::Single { target },
@@ -47,7 +54,9 @@ pub fn death(ecs: &mut World, effect: &EffectSpawner, target: Entity) {
     let attributes = ecs.read_storage::<Attributes>();
 
     if let Some(pos) = entity_position(ecs, target) {
-        crate::spatial::remove_entity(target, pos as usize);
+        ecs.fetch_mut::<Map>()
+            .spatial
+            .remove_entity(target, pos as usize);
     }
 
     if let Some(source) = effect.creator {
@@ -63,29 +72,19 @@ pub fn death(ecs: &mut World, effect: &EffectSpawner, target: Entity) {
                 let player_attributes = attributes.get(source).unwrap();
                 player_stats.xp += xp_gain;
                 player_stats.gold += gold_gain;
-                if player_stats.xp >= player_stats.level * 1000 {
-                    player_stats.level += 1;
-                    log.entries.push(format!(
-                        "Congratulations, you are now level {}",
-                        player_stats.level
-                    ));
-                    player_stats.hit_points.max = player_hp_at_level(
-                        player_attributes.fitness.base + player_attributes.fitness.modifiers,
-                        player_stats.level,
-                    );
-                    player_stats.hit_points.current = player_stats.hit_points.max;
-                    player_stats.mana.max = mana_at_level(
-                        player_attributes.intelligence.base
-                            + player_attributes.intelligence.modifiers,
-                        player_stats.level,
-                    );
-                    player_stats.mana.current = player_stats.mana.max;
-
+                if level_up(
+                    source,
+                    player_stats,
+                    player_attributes,
+                    &mut ecs.write_storage::<LevelUpPoints>(),
+                    &mut log,
+                ) {
                     let player_pos = ecs.fetch::<rltk::Point>();
                     let map = ecs.fetch::<Map>();
                     for i in 0..10 {
                         if player_pos.y - i > 1 {
                             add_effect(
+                                ecs,
                                 None,
                                 EffectType::Particle {
                                     glyph: rltk::to_cp437('░'),
@@ -112,9 +111,10 @@ pub fn heal_damage(ecs: &mut World, heal: &EffectSpawner, target: Entity) {
             pool.hit_points.current =
                 i32::min(pool.hit_points.max, pool.hit_points.current + amount);
             add_effect(
+                ecs,
                 None,
                 EffectType::Particle {
-                    glyph: rltk::to_cp437('‼'),
+                    glyph: rltk::to_cp437('♥'),
                     fg: rltk::RGB::named(rltk::GREEN),
                     bg: rltk::RGB::named(rltk::BLACK),
                     lifespan: 200.0,
@@ -124,11 +124,3 @@ pub fn heal_damage(ecs: &mut World, heal: &EffectSpawner, target: Entity) {
         }
     }
 }
-
-pub fn add_confusion(ecs: &mut World, effect: &EffectSpawner, target: Entity) {
-    if let EffectType::Confusion { turns } = &effect.effect_type {
-        ecs.write_storage::<Confusion>()
-            .insert(target, Confusion { turns: *turns })
-            .expect("Unable to insert status");
-    }
-}
@@ -1,17 +1,35 @@
 use crate::map::Map;
 use specs::prelude::*;
 use std::collections::VecDeque;
-use std::sync::Mutex;
 mod damage;
 mod targeting;
 pub use targeting::*;
 mod hunger;
 mod movement;
 mod particles;
+mod status;
 mod triggers;
 
-lazy_static! {
-    pub static ref EFFECT_QUEUE: Mutex<VecDeque<EffectSpawner>> = Mutex::new(VecDeque::new());
+/// Effects waiting to be applied, oldest first. It's a `World` resource, so each game has
+/// its own queue.
+#[derive(Default)]
+pub struct EffectQueue {
+    queue: VecDeque<EffectSpawner>,
+}
+
+impl EffectQueue {
+    pub fn add_effect(
+        &mut self,
+        creator: Option<Entity>,
+        effect_type: EffectType,
+        targets: Targets,
+    ) {
+        self.queue.push_back(EffectSpawner {
+            creator,
+            effect_type,
+            targets,
+        });
+    }
 }
 
 #[derive(Debug)]
@@ -30,12 +48,16 @@ pub enum EffectType {
     ItemUse {
         item: Entity,
     },
+    SpellUse {
+        spell: String,
+        power: i32,
+    },
     WellFed,
     Healing {
         amount: i32,
     },
-    Confusion {
-        turns: i32,
+    Status {
+        status: crate::components::Status,
     },
     TriggerFire {
         trigger: Entity,
@@ -63,17 +85,14 @@ pub struct EffectSpawner {
     pub targets: Targets,
 }
 
-pub fn add_effect(creator: Option<Entity>, effect_type: EffectType, targets: Targets) {
-    EFFECT_QUEUE.lock().unwrap().push_back(EffectSpawner {
-        creator,
-        effect_type,
-        targets,
-    });
+pub fn add_effect(ecs: &World, creator: Option<Entity>, effect_type: EffectType, targets: Targets) {
+    ecs.fetch_mut::<EffectQueue>()
+        .add_effect(creator, effect_type, targets);
 }
 
 pub fn run_effects_queue(ecs: &mut World) {
     loop {
-        let effect: Option<EffectSpawner> = EFFECT_QUEUE.lock().unwrap().pop_front();
+        let effect: Option<EffectSpawner> = ecs.fetch_mut::<EffectQueue>().queue.pop_front();
         if let Some(effect) = effect {
             target_applicator(ecs, &effect);
         } else {
@@ -85,6 +104,8 @@ pub fn run_effects_queue(ecs: &mut World) {
 fn target_applicator(ecs: &mut World, effect: &EffectSpawner) {
     if let EffectType::ItemUse { item } = effect.effect_type {
         triggers::item_trigger(effect.creator, item, &effect.targets, ecs);
+    } else if let EffectType::SpellUse { spell, power } = &effect.effect_type {
+        triggers::spell_trigger(effect.creator, spell, *power, &effect.targets, ecs);
     } else if let EffectType::TriggerFire { trigger } = effect.effect_type {
         triggers::trigger(effect.creator, trigger, &effect.targets, ecs);
     } else {
@@ -102,19 +123,22 @@ fn target_applicator(ecs: &mut World, effect: &EffectSpawner) {
 }
 
 fn tile_effect_hits_entities(effect: &EffectType) -> bool {
-    match effect {
-        EffectType::Damage { .. } => true,
-        EffectType::WellFed => true,
-        EffectType::Healing { .. } => true,
-        EffectType::Confusion { .. } => true,
-        EffectType::TeleportTo { .. } => true,
-        _ => false,
-    }
+    matches!(
+        effect,
+        EffectType::Damage { .. }
+            | EffectType::WellFed
+            | EffectType::Healing { .. }
+            | EffectType::Status { .. }
+            | EffectType::TeleportTo { .. }
+    )
 }
 
 fn affect_tile(ecs: &mut World, effect: &EffectSpawner, tile_idx: i32) {
     if tile_effect_hits_entities(&effect.effect_type) {
-        let content = crate::spatial::get_tile_content_clone(tile_idx as usize);
+        let content = ecs
+            .fetch::<Map>()
+            .spatial
+            .get_tile_content_clone(tile_idx as usize);
         content
             .iter()
             .for_each(|entity| affect_entity(ecs, effect, *entity));
@@ -122,7 +146,7 @@ fn affect_tile(ecs: &mut World, effect: &EffectSpawner, tile_idx: i32) {
 
     match &effect.effect_type {
         EffectType::Bloodstain => damage::bloodstain(ecs, tile_idx),
-        EffectType::Particle { .. } => particles::particle_to_tile(ecs, tile_idx, &effect),
+        EffectType::Particle { .. } => particles::particle_to_tile(ecs, tile_idx, effect),
         _ => {}
     }
 }
@@ -131,19 +155,19 @@ fn affect_entity(ecs: &mut World, effect: &EffectSpawner, target: Entity) {
     match &effect.effect_type {
         EffectType::Damage { .. } => damage::inflict_damage(ecs, effect, target),
         EffectType::EntityDeath => damage::death(ecs, effect, target),
-        EffectType::Bloodstain { .. } => {
+        EffectType::Bloodstain => {
             if let Some(pos) = entity_position(ecs, target) {
                 damage::bloodstain(ecs, pos)
             }
         }
         EffectType::Particle { .. } => {
             if let Some(pos) = entity_position(ecs, target) {
-                particles::particle_to_tile(ecs, pos, &effect)
+                particles::particle_to_tile(ecs, pos, effect)
             }
         }
         EffectType::WellFed => hunger::well_fed(ecs, effect, target),
         EffectType::Healing { .. } => damage::heal_damage(ecs, effect, target),
-        EffectType::Confusion { .. } => damage::add_confusion(ecs, effect, target),
+        EffectType::Status { .. } => status::add_status(ecs, effect, target),
         EffectType::TeleportTo { .. } => movement::apply_teleport(ecs, effect, target),
         _ => {}
     }
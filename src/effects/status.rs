@@ -0,0 +1,43 @@
+use super::*;
+use crate::components::{EquipmentChanged, Name, Pools, StatusEffects, StatusKind};
+use crate::gamelog::GameLog;
+
+/// Puts a status on anything with hit points. The player hears about it when it's them, or
+/// when they were the one who inflicted it.
+pub fn add_status(ecs: &mut World, effect: &EffectSpawner, target: Entity) {
+    let EffectType::Status { status } = &effect.effect_type else {
+        return;
+    };
+    if ecs.read_storage::<Pools>().get(target).is_none() {
+        return;
+    }
+
+    let mut statuses = ecs.write_storage::<StatusEffects>();
+    if statuses.get(target).is_none() {
+        statuses
+            .insert(target, StatusEffects::default())
+            .expect("Unable to insert status");
+    }
+    statuses.get_mut(target).unwrap().add(status.clone());
+
+    // Might is worked out again with the penalty
+    if status.kind == StatusKind::Weakness {
+        ecs.write_storage::<EquipmentChanged>()
+            .insert(target, EquipmentChanged {})
+            .expect("Unable to insert");
+    }
+
+    let player = *ecs.fetch::<Entity>();
+    let condition = status.kind.name().to_lowercase();
+    if target == player {
+        ecs.fetch_mut::<GameLog>()
+            .entries
+            .push(format!("You are {}.", condition));
+    } else if effect.creator == Some(player) {
+        if let Some(name) = ecs.read_storage::<Name>().get(target) {
+            ecs.fetch_mut::<GameLog>()
+                .entries
+                .push(format!("{} is {}.", name.name, condition));
+        }
+    }
+}
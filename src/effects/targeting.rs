@@ -11,7 +11,7 @@ pub fn entity_position(ecs: &World, target: Entity) -> Option<i32> {
 }
 
 pub fn aoe_tiles(map: &Map, target: rltk::Point, radius: i32) -> Vec<i32> {
-    let mut blast_tiles = rltk::field_of_view(target, radius, &*map);
+    let mut blast_tiles = rltk::field_of_view(target, radius, map);
     blast_tiles.retain(|p| p.x > 0 && p.x < map.width - 1 && p.y > 0 && p.y < map.height - 1);
     let mut result = Vec::new();
     for t in blast_tiles.iter() {
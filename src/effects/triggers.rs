@@ -1,27 +1,108 @@
 use super::*;
 use crate::components::*;
 use crate::gamelog::GameLog;
+use crate::map::MasterDungeonMap;
 use crate::RunState;
 
+/// What using something came to.
+struct Outcome {
+    /// Anything happened at all; nothing is used up otherwise
+    did_something: bool,
+    /// What happened gave away what it was
+    obvious: bool,
+}
+
 pub fn item_trigger(creator: Option<Entity>, item: Entity, targets: &Targets, ecs: &mut World) {
+    // A spent item does nothing until it's recharged
+    if ecs
+        .read_storage::<Charges>()
+        .get(item)
+        .is_some_and(|charges| charges.remaining < 1)
+    {
+        return;
+    }
+
     // Use the item via the generic system
-    let did_something = event_trigger(creator, item, targets, ecs);
+    let outcome = event_trigger(creator, item, targets, ecs);
+
+    // The player learns what an item is from what it does (equipment is identified by
+    // putting it on instead)
+    if let Some(user) = creator.filter(|creator| *creator == *ecs.fetch::<Entity>()) {
+        if ecs.read_storage::<Equippable>().get(item).is_none() {
+            learn_from_use(ecs, user, item, outcome.obvious);
+        }
+    }
+
+    // If it was a consumable, then it gets deleted - or uses up a charge, or one of a stack
+    if outcome.did_something && ecs.read_storage::<Consumable>().get(item).is_some() {
+        if let Some(charges) = ecs.write_storage::<Charges>().get_mut(item) {
+            charges.remaining -= 1;
+        } else {
+            crate::consume_one(ecs, item);
+        }
+    }
+}
+
+/// Identifies an item the player used if its effect gave it away. If not, its kind is
+/// noted as tried, so they know not to expect anything showy from the next one.
+fn learn_from_use(ecs: &mut World, player: Entity, item: Entity, obvious: bool) {
+    let Some(name) = ecs
+        .read_storage::<Name>()
+        .get(item)
+        .map(|name| name.name.clone())
+    else {
+        return;
+    };
+    if obvious {
+        ecs.write_storage::<IdentifiedItem>()
+            .insert(player, IdentifiedItem { name })
+            .expect("Unable to insert");
+    } else if ecs.read_storage::<MagicItem>().get(item).is_some() {
+        let mut dungeon_master = ecs.fetch_mut::<MasterDungeonMap>();
+        if !dungeon_master.identified_items.contains(&name) {
+            dungeon_master.note_mut(&name).tried = true;
+        }
+    }
+}
 
-    // If it was a consumable, then it gets deleted
-    if did_something && ecs.read_storage::<Consumable>().get(item).is_some() {
-        ecs.entities().delete(item).expect("Delete Failed");
+/// Casts a spell: its effects are built on a short-lived entity and fired like an item's.
+/// `power` is added to the damage or healing it does.
+pub fn spell_trigger(
+    creator: Option<Entity>,
+    spell: &str,
+    power: i32,
+    targets: &Targets,
+    ecs: &mut World,
+) {
+    let Some(template) =
+        crate::raws::spawn_named_spell(&crate::raws::RAWS.lock().unwrap(), ecs, spell)
+    else {
+        return;
+    };
+    if let Some(damage) = ecs.write_storage::<InflictsDamage>().get_mut(template) {
+        damage.bonus += power;
+    }
+    if let Some(heal) = ecs.write_storage::<ProvidesHealing>().get_mut(template) {
+        heal.heal_amount += power;
     }
+    event_trigger(creator, template, targets, ecs);
+    ecs.entities().delete(template).expect("Delete Failed");
 }
 
 pub fn trigger(creator: Option<Entity>, trigger: Entity, targets: &Targets, ecs: &mut World) {
     // The triggering item is no longer hidden
     ecs.write_storage::<Hidden>().remove(trigger);
+    if let Some(name) = ecs.read_storage::<Name>().get(trigger) {
+        ecs.fetch_mut::<GameLog>()
+            .entries
+            .push(format!("{} triggers!", name.name));
+    }
 
     // Use the item via the generic system
-    let did_something = event_trigger(creator, trigger, targets, ecs);
+    let outcome = event_trigger(creator, trigger, targets, ecs);
 
     // If it was a single activation, then it gets deleted
-    if did_something
+    if outcome.did_something
         && ecs
             .read_storage::<SingleActivation>()
             .get(trigger)
@@ -36,13 +117,24 @@ fn event_trigger(
     entity: Entity,
     targets: &Targets,
     ecs: &mut World,
-) -> bool {
+) -> Outcome {
     let mut did_something = false;
+    // Set by effects that can go off without the user noticing anything
+    let mut unnoticed = false;
     let mut gamelog = ecs.fetch_mut::<GameLog>();
+    let by_player = creator.is_some_and(|creator| creator == *ecs.fetch::<Entity>());
+    let names = ecs.read_storage::<Name>();
+    let name = names.get(entity).map_or("", |name| name.name.as_str());
+    let used = if ecs.read_storage::<SpellTemplate>().get(entity).is_some() {
+        format!("You cast {}", name)
+    } else {
+        format!("You use the {}", name)
+    };
 
     // Simple particle spawn
     if let Some(part) = ecs.read_storage::<SpawnParticleBurst>().get(entity) {
         add_effect(
+            ecs,
             creator,
             EffectType::Particle {
                 glyph: part.glyph,
@@ -56,7 +148,10 @@ fn event_trigger(
 
     // Line particle spawn
     if let Some(part) = ecs.read_storage::<SpawnParticleLine>().get(entity) {
-        if let Some(start_pos) = targeting::find_item_position(ecs, entity) {
+        // A spell flies from whoever cast it
+        let start_pos = targeting::find_item_position(ecs, entity)
+            .or_else(|| creator.and_then(|creator| entity_position(ecs, creator)));
+        if let Some(start_pos) = start_pos {
             match targets {
                 Targets::Tile { tile_idx } => spawn_line_particles(ecs, start_pos, *tile_idx, part),
                 Targets::Tiles { tiles } => tiles
@@ -80,11 +175,8 @@ fn event_trigger(
 
     // Providing food
     if ecs.read_storage::<ProvidesFood>().get(entity).is_some() {
-        add_effect(creator, EffectType::WellFed, targets.clone());
-        let names = ecs.read_storage::<Name>();
-        gamelog
-            .entries
-            .push(format!("You eat the {}.", names.get(entity).unwrap().name));
+        add_effect(ecs, creator, EffectType::WellFed, targets.clone());
+        gamelog.entries.push(format!("You eat the {}.", name));
         did_something = true;
     }
 
@@ -108,52 +200,121 @@ fn event_trigger(
         } else {
             gamelog
                 .entries
-                .push("You are telported back to town!".to_string());
+                .push("You are teleported back to town!".to_string());
             let mut runstate = ecs.fetch_mut::<RunState>();
             *runstate = RunState::TownPortal;
             did_something = true;
         }
     }
 
+    // Lifting the curses on what the user is wearing
+    if let (Some(_), Some(user)) = (
+        ecs.read_storage::<ProvidesRemoveCurse>().get(entity),
+        creator,
+    ) {
+        let mut cursed = ecs.write_storage::<CursedItem>();
+        let lifted: Vec<Entity> = (&ecs.entities(), &ecs.read_storage::<Equipped>(), &cursed)
+            .join()
+            .filter(|(_, worn, _)| worn.owner == user)
+            .map(|(item, _, _)| item)
+            .collect();
+        for item in lifted.iter() {
+            cursed.remove(*item);
+            if let (true, Some(item_name)) = (by_player, names.get(*item)) {
+                gamelog
+                    .entries
+                    .push(format!("The curse on your {} lifts.", item_name.name));
+            }
+        }
+        if lifted.is_empty() {
+            if by_player {
+                gamelog
+                    .entries
+                    .push("You feel as if someone is watching over you.".to_string());
+            }
+            unnoticed = true;
+        }
+        did_something = true;
+    }
+
     // Healing
     if let Some(heal) = ecs.read_storage::<ProvidesHealing>().get(entity) {
         add_effect(
+            ecs,
             creator,
             EffectType::Healing {
                 amount: heal.heal_amount,
             },
             targets.clone(),
         );
+        if by_player {
+            gamelog
+                .entries
+                .push(format!("{}, healing {} hp.", used, heal.heal_amount));
+        }
         did_something = true;
     }
 
     // Damage
     if let Some(damage) = ecs.read_storage::<InflictsDamage>().get(entity) {
-        add_effect(
-            creator,
-            EffectType::Damage {
-                amount: damage.damage,
-            },
-            targets.clone(),
-        );
+        let amount = damage.roll(&mut ecs.write_resource::<rltk::RandomNumberGenerator>());
+        add_effect(ecs, creator, EffectType::Damage { amount }, targets.clone());
+        if by_player {
+            gamelog
+                .entries
+                .push(format!("{}, inflicting {} hp.", used, amount));
+        }
         did_something = true;
     }
 
-    // Confusion
-    if let Some(confusion) = ecs.read_storage::<Confusion>().get(entity) {
-        add_effect(
-            creator,
-            EffectType::Confusion {
-                turns: confusion.turns,
-            },
-            targets.clone(),
-        );
+    // Statuses, which say what they did as they take hold
+    if let Some(inflicts) = ecs.read_storage::<InflictsStatus>().get(entity) {
+        for status in inflicts.statuses.iter() {
+            add_effect(
+                ecs,
+                creator,
+                EffectType::Status {
+                    status: status.clone(),
+                },
+                targets.clone(),
+            );
+        }
         did_something = true;
     }
 
+    // Learning a spell
+    if let (Some(teaches), Some(learner)) =
+        (ecs.read_storage::<TeachesSpell>().get(entity), creator)
+    {
+        let mut known_spells = ecs.write_storage::<KnownSpells>();
+        if known_spells.get(learner).is_none() {
+            known_spells
+                .insert(learner, KnownSpells::default())
+                .expect("Unable to insert");
+        }
+        let known = known_spells.get_mut(learner).unwrap();
+        if known.spells.contains(&teaches.spell) {
+            if by_player {
+                gamelog
+                    .entries
+                    .push(format!("You already know how to cast {}.", teaches.spell));
+            }
+        } else {
+            known.spells.push(teaches.spell.clone());
+            if by_player {
+                gamelog.entries.push(format!(
+                    "You study the {} and learn to cast {}.",
+                    name, teaches.spell
+                ));
+            }
+            did_something = true;
+        }
+    }
+
     // Teleport
     if let Some(teleport) = ecs.read_storage::<TeleportTo>().get(entity) {
         add_effect(
+            ecs,
             creator,
             EffectType::TeleportTo {
                 x: teleport.x,
@@ -166,16 +327,20 @@ fn event_trigger(
         did_something = true;
     }
 
-    did_something
+    Outcome {
+        did_something,
+        obvious: did_something && !unnoticed,
+    }
 }
 
 fn spawn_line_particles(ecs: &World, start: i32, end: i32, part: &SpawnParticleLine) {
     let map = ecs.fetch::<Map>();
-    let start_pt = rltk::Point::new(start % map.width, end / map.width);
+    let start_pt = rltk::Point::new(start % map.width, start / map.width);
     let end_pt = rltk::Point::new(end % map.width, end / map.width);
     let line = rltk::line2d(rltk::LineAlg::Bresenham, start_pt, end_pt);
     for pt in line.iter() {
         add_effect(
+            ecs,
             None,
             EffectType::Particle {
                 glyph: part.glyph,
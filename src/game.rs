@@ -0,0 +1,94 @@
+use super::{
+    apply_command, damage_system, map::MasterDungeonMap, particle_system, random_seed,
+    PlayerCommand, RunState, State,
+};
+use specs::prelude::*;
+
+/// A game session without a window. It owns the world and advances it one player command
+/// at a time, running the simulation until the player is asked for input again, so that
+/// tests, bots and balancing tools can play without a screen or keyboard.
+pub struct Game {
+    state: State,
+}
+
+impl Game {
+    /// Starts a new game on the first level, ready for the player's first command.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Game {
+        Game::with_seed(random_seed())
+    }
+
+    /// Starts a new game from a run seed; the same seed and commands replay the same game.
+    pub fn with_seed(seed: u64) -> Game {
+        let mut state = State::with_seed(seed);
+        state.mapgen_next_state = Some(RunState::PreRun);
+        let mut game = Game { state };
+        game.run_until_input();
+        game
+    }
+
+    pub fn world(&self) -> &World {
+        &self.state.ecs
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.state.ecs
+    }
+
+    pub fn player(&self) -> Entity {
+        *self.state.ecs.fetch::<Entity>()
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.state.ecs.fetch::<MasterDungeonMap>().seed
+    }
+
+    pub fn run_state(&self) -> RunState {
+        *self.state.ecs.fetch::<RunState>()
+    }
+
+    /// Carries out a command and runs the simulation until the player is next asked for
+    /// input, returning the state the game is then waiting in: `AwaitingInput`, a menu such
+    /// as `LevelUp`, `ShowTargeting` or `ShowContainer`, or `GameOver`. A command given while
+    /// a menu is open is the choice made in it; any other command leaves the menu, as
+    /// closing it on screen would.
+    pub fn apply(&mut self, command: PlayerCommand) -> RunState {
+        if !self.run_state().awaits_player() {
+            return self.run_state();
+        }
+
+        let newrunstate = apply_command(&mut self.state.ecs, command);
+        self.set_run_state(newrunstate);
+        self.run_until_input()
+    }
+
+    fn run_until_input(&mut self) -> RunState {
+        loop {
+            let runstate = self.run_state();
+            let newrunstate = match runstate {
+                RunState::GameOver => return runstate,
+                runstate if runstate.awaits_player() => return runstate,
+                RunState::MapGeneration => self.state.mapgen_next_state.unwrap(),
+                RunState::PreRun
+                | RunState::Ticking
+                | RunState::NextLevel
+                | RunState::PreviousLevel
+                | RunState::MagicMapReveal { .. }
+                | RunState::TownPortal
+                | RunState::TeleportingToOtherLevel { .. } => self.state.advance(runstate),
+                // Screens that only show things, or belong to the main menu, have nothing to
+                // wait for without a window
+                _ => RunState::AwaitingInput,
+            };
+            self.set_run_state(newrunstate);
+            damage_system::delete_the_dead(&mut self.state.ecs);
+            // Nothing is ever drawn, so particles can go as soon as they are spawned
+            particle_system::cull_dead_particles(&mut self.state.ecs, f32::MAX);
+        }
+    }
+
+    fn set_run_state(&mut self, runstate: RunState) {
+        let mut runwriter = self.state.ecs.write_resource::<RunState>();
+        *runwriter = runstate;
+    }
+}
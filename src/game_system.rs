@@ -1,4 +1,5 @@
-use super::{Skill, Skills};
+use super::{gamelog::GameLog, Attributes, LevelUpPoints, Pools, Skill, Skills};
+use specs::{prelude::*, storage::GenericReadStorage};
 
 pub fn attribute_bonus(value: i32) -> i32 {
     (value - 10) / 2 // See: https://roll20.net/compendium/dnd5e/Ability%20Scores#content
@@ -12,6 +13,67 @@ pub fn player_hp_at_level(fitness: i32, level: i32) -> i32 {
     10 + player_hp_per_level(fitness) * level
 }
 
+/// Points to spend on attributes for each level gained.
+pub const ATTRIBUTE_POINTS_PER_LEVEL: i32 = 1;
+
+/// Points to spend on skills for each level gained.
+pub const SKILL_POINTS_PER_LEVEL: i32 = 2;
+
+/// The xp needed to leave a level.
+pub fn xp_for_next_level(level: i32) -> i32 {
+    level * 1000
+}
+
+/// Raises the player a level for each threshold their xp has passed, restoring them to
+/// full health and mana and banking points for the level-up screen. Returns whether they
+/// gained any levels.
+pub fn level_up(
+    player: Entity,
+    pools: &mut Pools,
+    attributes: &Attributes,
+    points: &mut WriteStorage<LevelUpPoints>,
+    log: &mut GameLog,
+) -> bool {
+    let mut levels = 0;
+    while pools.xp >= xp_for_next_level(pools.level) {
+        pools.level += 1;
+        levels += 1;
+    }
+    if levels == 0 {
+        return false;
+    }
+
+    pools.hit_points.max = player_hp_at_level(
+        attributes.fitness.base + attributes.fitness.modifiers,
+        pools.level,
+    );
+    pools.hit_points.current = pools.hit_points.max;
+    pools.mana.max = mana_at_level(
+        attributes.intelligence.base + attributes.intelligence.modifiers,
+        pools.level,
+    );
+    pools.mana.current = pools.mana.max;
+
+    let (attribute_points, skill_points) = points
+        .get(player)
+        .map_or((0, 0), |points| (points.attributes, points.skills));
+    points
+        .insert(
+            player,
+            LevelUpPoints {
+                attributes: attribute_points + levels * ATTRIBUTE_POINTS_PER_LEVEL,
+                skills: skill_points + levels * SKILL_POINTS_PER_LEVEL,
+            },
+        )
+        .expect("Unable to insert");
+
+    log.entries.push(format!(
+        "Congratulations, you are now level {}",
+        pools.level
+    ));
+    true
+}
+
 pub fn npc_hp(fitness: i32, level: i32) -> i32 {
     let mut total = 1;
     for _ in 0..level {
@@ -28,10 +90,92 @@ pub fn mana_at_level(intelligence: i32, level: i32) -> i32 {
     mana_per_level(intelligence) * level
 }
 
+/// Tenths of a hit point regained each turn out of danger, before hunger.
+pub fn hp_regen_rate(fitness: i32, level: i32) -> i32 {
+    0.max(3 + attribute_bonus(fitness) + level)
+}
+
+/// Tenths of a point of mana regained each turn out of danger, before hunger.
+pub fn mana_regen_rate(intelligence: i32, level: i32) -> i32 {
+    0.max(3 + attribute_bonus(intelligence) + level)
+}
+
 pub fn skill_bonus(skill: Skill, skills: &Skills) -> i32 {
-    if skills.skills.contains_key(&skill) {
-        skills.skills[&skill]
-    } else {
-        -4
+    let modifier = skills.modifiers.get(&skill).copied().unwrap_or(0);
+    skill_level(skill, skills) + modifier
+}
+
+/// The level of a skill nobody has trained.
+pub const UNTRAINED_SKILL: i32 = -4;
+
+/// The trained level of a skill, before any modifiers from equipment or statuses.
+pub fn skill_level(skill: Skill, skills: &Skills) -> i32 {
+    skills
+        .skills
+        .get(&skill)
+        .copied()
+        .unwrap_or(UNTRAINED_SKILL)
+}
+
+/// The percentage chance that recharging an item blows it up instead. The first recharge
+/// is safe; after four, nothing survives another.
+pub fn recharge_explosion_chance(times_recharged: i32) -> i32 {
+    (25 * times_recharged).clamp(0, 100)
+}
+
+/// Every skill, in the order the character sheet and level-up screen list them.
+pub const TRAINABLE_SKILLS: [Skill; 4] =
+    [Skill::Melee, Skill::Defense, Skill::Ranged, Skill::Magic];
+
+/// How many uses it takes to raise a skill from `level`; each level takes longer than the
+/// last.
+pub fn skill_practice_needed(level: i32) -> i32 {
+    let next = 1.max(level + 1);
+    5 * next * next
+}
+
+/// The highest a skill can be trained to by use at a character level.
+pub fn skill_cap(character_level: i32) -> i32 {
+    character_level + 1
+}
+
+/// Counts one use of a skill, returning its new level if that raised it. An untrained skill
+/// climbs up from `UNTRAINED_SKILL`; a skill at the cap for the character's level learns no
+/// more.
+pub fn practice_skill(skills: &mut Skills, skill: Skill, character_level: i32) -> Option<i32> {
+    let level = skill_level(skill, skills);
+    if level >= skill_cap(character_level) {
+        return None;
+    }
+    let practice = skills.practice.entry(skill).or_insert(0);
+    *practice += 1;
+    if *practice < skill_practice_needed(level) {
+        return None;
+    }
+    *practice = 0;
+    skills.skills.insert(skill, level + 1);
+    Some(level + 1)
+}
+
+/// Counts a use of a skill for each entity in `practiced`, telling the player when theirs
+/// improve.
+pub fn practice_skills(
+    practiced: &[(Entity, Skill)],
+    skills: &mut WriteStorage<Skills>,
+    pools: &impl GenericReadStorage<Component = Pools>,
+    player: Entity,
+    log: &mut GameLog,
+) {
+    for (entity, skill) in practiced.iter() {
+        let character_level = pools.get(*entity).map_or(1, |pools| pools.level);
+        let Some(skills) = skills.get_mut(*entity) else {
+            continue;
+        };
+        if let Some(level) = practice_skill(skills, *skill, character_level) {
+            if *entity == player {
+                log.entries
+                    .push(format!("Your {:?} skill improves to {}.", skill, level));
+            }
+        }
     }
 }
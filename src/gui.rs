@@ -1,11 +1,16 @@
 use crate::{MagicItem, MagicItemClass, ObfuscatedName};
 
 use super::{
-    camera, gamelog::GameLog, rex_assets::RexAssets, Attribute, Attributes, Consumable, Equipped,
-    Hidden, HungerClock, HungerState, InBackpack, Item, Map, Name, Point, Pools, Position,
-    RunState, State, Vendor, VendorMode, Viewshed,
+    backpack_items, camera, charged_items, container_items, gamelog::GameLog, known_spells,
+    rex_assets::RexAssets, skill_cap, skill_level, skill_practice_needed, unidentified_items,
+    xp_for_next_level, Attribute, AttributeName, Attributes, Charges, Consumable, ContainerMode,
+    CursedItem, Equipped, Hidden, HungerClock, HungerState, InBackpack, Item, ItemNote,
+    LevelUpPoints, Map, MasterDungeonMap, Name, PlayerCommand, Point, Pools, Position, Recharger,
+    RunState, SaveEncoding, Skills, Stackable, State, StatusEffects, StatusKind, Temple, Vendor,
+    VendorMode, Viewshed, TRAINABLE_SKILLS,
 };
 use rltk::{Rltk, VirtualKeyCode, RGB};
+use serde::{Deserialize, Serialize};
 use specs::prelude::*;
 use std::cmp::Ordering;
 
@@ -34,6 +39,28 @@ pub fn draw_hollow_box(
     }
 }
 
+/// The player's statuses and the turns left on them, along the top of the panel with the
+/// health bar.
+fn draw_statuses(ecs: &World, player: Entity, ctx: &mut Rltk) {
+    let statuses = ecs.read_storage::<StatusEffects>();
+    let Some(statuses) = statuses.get(player) else {
+        return;
+    };
+    let mut x = 51;
+    for status in statuses.effects.iter() {
+        let color = match status.kind {
+            StatusKind::Regeneration | StatusKind::Haste => RGB::named(rltk::GREEN),
+            _ => RGB::named(rltk::ORANGE),
+        };
+        let label = format!("{} {}", status.kind.name(), status.turns);
+        if x + label.len() as i32 > 78 {
+            break;
+        }
+        ctx.print_color(x, 0, color, RGB::named(rltk::BLACK), &label);
+        x += label.len() as i32 + 1;
+    }
+}
+
 pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
     use rltk::to_cp437;
 
@@ -85,7 +112,8 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
     ctx.print_color(50, 1, white, black, &health);
     ctx.print_color(50, 2, white, black, &mana);
     ctx.print_color(50, 3, white, black, &xp);
-    let xp_level_start = (player_pools.level - 1) * 1000;
+    draw_statuses(ecs, *player_entity, ctx);
+    let xp_level_start = xp_for_next_level(player_pools.level - 1);
     ctx.draw_bar_horizontal(
         64,
         1,
@@ -109,7 +137,7 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
         3,
         14,
         player_pools.xp - xp_level_start,
-        1000,
+        xp_for_next_level(player_pools.level) - xp_level_start,
         RGB::named(rltk::GOLD),
         black,
     );
@@ -128,7 +156,7 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
         9,
         white,
         black,
-        &format!(
+        format!(
             "{:.0} lbs ({} lbs max)",
             player_pools.total_weight,
             (attr.might.base + attr.might.modifiers) * 15
@@ -139,7 +167,7 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
         10,
         white,
         black,
-        &format!(
+        format!(
             "Initiative Penalty: {:.0}",
             player_pools.total_initiative_penalty
         ),
@@ -149,7 +177,7 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
         11,
         rltk::RGB::named(rltk::GOLD),
         black,
-        &format!("Gold: {:.1}", player_pools.gold),
+        format!("Gold: {:.1}", player_pools.gold),
     );
 
     // Equipped
@@ -163,7 +191,7 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
                 y,
                 get_item_color(ecs, entity),
                 black,
-                &get_item_display_name(ecs, entity),
+                get_item_display_name(ecs, entity),
             );
             y += 1;
         }
@@ -177,19 +205,50 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
     let mut index = 1;
     for (entity, carried_by, _consumable) in (&entities, &backpack, &consumables).join() {
         if carried_by.owner == *player_entity && index < 10 {
-            ctx.print_color(50, y, yellow, black, &format!("↑{}", index));
+            ctx.print_color(50, y, yellow, black, format!("↑{}", index));
             ctx.print_color(
                 53,
                 y,
                 get_item_color(ecs, entity),
                 black,
-                &get_item_display_name(ecs, entity),
+                get_item_display_name(ecs, entity),
             );
             y += 1;
             index += 1;
         }
     }
 
+    // Spells
+    y += 1;
+    let raws = crate::raws::RAWS.lock().unwrap();
+    for (index, spell) in known_spells(ecs).iter().take(9).enumerate() {
+        let cost = crate::raws::spell_info(&raws, spell).map_or(0, |info| info.mana_cost);
+        ctx.print_color(
+            50,
+            y,
+            RGB::named(rltk::CYAN),
+            black,
+            format!("^{}", index + 1),
+        );
+        ctx.print_color(
+            53,
+            y,
+            RGB::named(rltk::CYAN),
+            black,
+            format!("{} ({})", spell, cost),
+        );
+        y += 1;
+    }
+    drop(raws);
+
+    ctx.print_color(
+        50,
+        43,
+        RGB::named(rltk::GRAY),
+        black,
+        format!("Seed: {}", ecs.fetch::<MasterDungeonMap>().seed),
+    );
+
     // Status
     let hunger = ecs.read_storage::<HungerClock>();
     let hc = hunger.get(*player_entity).unwrap();
@@ -208,12 +267,8 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
 
     // Draw the log
     let log = ecs.fetch::<GameLog>();
-    let mut y = 46;
-    for s in log.entries.iter().rev() {
-        if y < 59 {
-            ctx.print(2, y, s);
-        }
-        y += 1;
+    for (y, s) in (46..59).zip(log.entries.iter().rev()) {
+        ctx.print(2, y, s);
     }
 
     draw_tooltips(ecs, ctx);
@@ -235,9 +290,9 @@ fn draw_attributes(name: &str, attribute: &Attribute, y: i32, ctx: &mut Rltk) {
         y,
         color,
         black,
-        &format!("{}", attribute.base + attribute.modifiers),
+        format!("{}", attribute.base + attribute.modifiers),
     );
-    ctx.print_color(73, y, color, black, &format!("{}", attribute.bonus));
+    ctx.print_color(73, y, color, black, format!("{}", attribute.bonus));
     if attribute.bonus > 0 {
         ctx.set(72, y, color, black, rltk::to_cp437('+'));
     }
@@ -422,7 +477,7 @@ pub fn show_inventory(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option
         .filter(|item| item.0.owner == *player_entity);
     let count = inventory.count();
 
-    let mut y = (25 - (count / 2)) as i32;
+    let y = (25 - (count / 2)) as i32;
     ctx.draw_box(
         15,
         y - 2,
@@ -452,6 +507,7 @@ pub fn show_inventory(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option
         .filter(|item| item.1.owner == *player_entity)
         .enumerate()
     {
+        let y = y + j as i32;
         ctx.set(
             17,
             y,
@@ -479,10 +535,9 @@ pub fn show_inventory(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option
             y,
             get_item_color(&gs.ecs, entity),
             RGB::from_f32(0.0, 0.0, 0.0),
-            &get_item_display_name(&gs.ecs, entity),
+            get_item_display_name(&gs.ecs, entity),
         );
         equippable.push(entity);
-        y += 1;
     }
 
     match ctx.key {
@@ -503,6 +558,95 @@ pub fn show_inventory(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option
     }
 }
 
+/// Lists the spells the player knows with what they cost; picking one returns its index.
+pub fn show_spells(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<usize>) {
+    let spells = known_spells(&gs.ecs);
+    let count = spells.len();
+    let raws = crate::raws::RAWS.lock().unwrap();
+    let mana = gs
+        .ecs
+        .read_storage::<Pools>()
+        .get(*gs.ecs.fetch::<Entity>())
+        .map_or(0, |pools| pools.mana.current);
+
+    let y = (25 - (count / 2)) as i32;
+    ctx.draw_box(
+        15,
+        y - 2,
+        31,
+        (count + 3) as i32,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        18,
+        y - 2,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "Cast which spell?",
+    );
+    ctx.print_color(
+        18,
+        y + count as i32 + 1,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "ESCAPE to cancel",
+    );
+
+    for (j, spell) in spells.iter().enumerate() {
+        let y = y + j as i32;
+        ctx.set(
+            17,
+            y,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::BLACK),
+            rltk::to_cp437('('),
+        );
+        ctx.set(
+            18,
+            y,
+            RGB::named(rltk::YELLOW),
+            RGB::named(rltk::BLACK),
+            97 + j as rltk::FontCharType,
+        );
+        ctx.set(
+            19,
+            y,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::BLACK),
+            rltk::to_cp437(')'),
+        );
+
+        let cost = crate::raws::spell_info(&raws, spell).map_or(0, |info| info.mana_cost);
+        let color = if cost <= mana {
+            RGB::named(rltk::CYAN)
+        } else {
+            RGB::named(rltk::GRAY)
+        };
+        ctx.print_color(
+            21,
+            y,
+            color,
+            RGB::named(rltk::BLACK),
+            format!("{} ({} mana)", spell, cost),
+        );
+    }
+
+    match ctx.key {
+        None => (ItemMenuResult::NoResponse, None),
+        Some(key) => match key {
+            VirtualKeyCode::Escape => (ItemMenuResult::Cancel, None),
+            _ => {
+                let selection = rltk::letter_to_option(key);
+                if selection > -1 && selection < count as i32 {
+                    return (ItemMenuResult::Selected, Some(selection as usize));
+                }
+                (ItemMenuResult::NoResponse, None)
+            }
+        },
+    }
+}
+
 pub fn drop_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<Entity>) {
     let player_entity = gs.ecs.fetch::<Entity>();
     let names = gs.ecs.read_storage::<Name>();
@@ -514,7 +658,7 @@ pub fn drop_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option
         .filter(|item| item.0.owner == *player_entity);
     let count = inventory.count();
 
-    let mut y = (25 - (count / 2)) as i32;
+    let y = (25 - (count / 2)) as i32;
     ctx.draw_box(
         15,
         y - 2,
@@ -544,6 +688,7 @@ pub fn drop_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option
         .filter(|item| item.1.owner == *player_entity)
         .enumerate()
     {
+        let y = y + j as i32;
         ctx.set(
             17,
             y,
@@ -571,10 +716,9 @@ pub fn drop_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option
             y,
             get_item_color(&gs.ecs, entity),
             RGB::from_f32(0.0, 0.0, 0.0),
-            &get_item_display_name(&gs.ecs, entity),
+            get_item_display_name(&gs.ecs, entity),
         );
         equippable.push(entity);
-        y += 1;
     }
 
     match ctx.key {
@@ -665,7 +809,130 @@ pub fn ranged_target(
     (ItemMenuResult::NoResponse, None)
 }
 
-#[derive(PartialEq, Copy, Clone)]
+#[derive(PartialEq, Clone)]
+pub enum LoadMenuResult {
+    NoResponse,
+    Cancel,
+    Selected(String),
+}
+
+pub fn load_menu(ctx: &mut Rltk) -> LoadMenuResult {
+    let slots = super::saveload_system::list_saves();
+    let count = slots.len() as i32;
+
+    let y = 25 - (count / 2);
+    ctx.draw_box(
+        3,
+        y - 2,
+        73,
+        count + 3,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        6,
+        y - 2,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "Load which game?",
+    );
+    ctx.print_color(
+        6,
+        y + count + 1,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "ESCAPE to cancel",
+    );
+
+    for (j, slot) in slots.iter().enumerate() {
+        let y = y + j as i32;
+        let color = if slot.can_load() {
+            RGB::named(rltk::WHITE)
+        } else {
+            RGB::named(rltk::GRAY)
+        };
+        ctx.set(
+            5,
+            y,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::BLACK),
+            rltk::to_cp437('('),
+        );
+        ctx.set(
+            6,
+            y,
+            RGB::named(rltk::YELLOW),
+            RGB::named(rltk::BLACK),
+            97 + j as rltk::FontCharType,
+        );
+        ctx.set(
+            7,
+            y,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::BLACK),
+            rltk::to_cp437(')'),
+        );
+        let description = match &slot.metadata {
+            Some(m) if m.consumed => format!("{} - already loaded", slot.name),
+            Some(m) => format!(
+                "{} - Level {}, {} ({}), played {}, saved {}",
+                slot.name,
+                m.character_level,
+                m.map_name,
+                m.depth,
+                format_play_time(m.play_time_ms),
+                format_timestamp(m.timestamp)
+            ),
+            None => format!("{} - an older save", slot.name),
+        };
+        ctx.print_color(9, y, color, RGB::named(rltk::BLACK), description);
+    }
+
+    match ctx.key {
+        None => LoadMenuResult::NoResponse,
+        Some(key) => match key {
+            VirtualKeyCode::Escape => LoadMenuResult::Cancel,
+            _ => {
+                let selection = rltk::letter_to_option(key);
+                if selection > -1 && selection < count && slots[selection as usize].can_load() {
+                    return LoadMenuResult::Selected(slots[selection as usize].name.clone());
+                }
+                LoadMenuResult::NoResponse
+            }
+        },
+    }
+}
+
+fn format_play_time(ms: u64) -> String {
+    let minutes = ms / 60_000;
+    format!("{}h{:02}m", minutes / 60, minutes % 60)
+}
+
+/// A Unix timestamp as a UTC date and time.
+fn format_timestamp(secs: u64) -> String {
+    // Civil-from-days, see http://howardhinnant.github.io/date_algorithms.html
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    let time = secs % 86_400;
+    format!(
+        "{}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        time / 3_600,
+        time % 3_600 / 60
+    )
+}
+
+#[derive(PartialEq, Copy, Clone, Debug)]
 pub enum MainMenuSelection {
     NewGame,
     LoadGame,
@@ -765,6 +1032,54 @@ pub fn main_menu(gs: &mut State, ctx: &mut Rltk) -> MainMenuResult {
             ctx.print_color_centered(y, RGB::named(rltk::WHITE), RGB::named(rltk::BLACK), "Quit");
         }
 
+        ctx.draw_box(
+            25,
+            29,
+            31,
+            3,
+            RGB::named(rltk::WHEAT),
+            RGB::named(rltk::BLACK),
+        );
+        ctx.print_color_centered(
+            30,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::BLACK),
+            format!("Seed: {}_", gs.seed_entry),
+        );
+        ctx.print_color_centered(
+            31,
+            RGB::named(rltk::GRAY),
+            RGB::named(rltk::BLACK),
+            "Type digits to set, empty for random",
+        );
+
+        if let Some(error) = &gs.load_error {
+            let mut line = String::new();
+            let mut error_y = 34;
+            for word in error.split_whitespace() {
+                if !line.is_empty() && line.len() + word.len() >= 76 {
+                    ctx.print_color_centered(
+                        error_y,
+                        RGB::named(rltk::RED),
+                        RGB::named(rltk::BLACK),
+                        &line,
+                    );
+                    error_y += 1;
+                    line.clear();
+                }
+                if !line.is_empty() {
+                    line.push(' ');
+                }
+                line.push_str(word);
+            }
+            ctx.print_color_centered(
+                error_y,
+                RGB::named(rltk::RED),
+                RGB::named(rltk::BLACK),
+                &line,
+            );
+        }
+
         match ctx.key {
             None => {
                 return MainMenuResult::NoSelection {
@@ -810,10 +1125,22 @@ pub fn main_menu(gs: &mut State, ctx: &mut Rltk) -> MainMenuResult {
                         selected: selection,
                     }
                 }
-                _ => {
+                VirtualKeyCode::Back => {
+                    gs.seed_entry.pop();
                     return MainMenuResult::NoSelection {
                         selected: selection,
+                    };
+                }
+                _ => {
+                    if let Some(digit) = seed_digit(key) {
+                        // Anything longer can't be a u64
+                        if gs.seed_entry.len() < 20 {
+                            gs.seed_entry.push(digit);
+                        }
                     }
+                    return MainMenuResult::NoSelection {
+                        selected: selection,
+                    };
                 }
             },
         }
@@ -823,6 +1150,22 @@ pub fn main_menu(gs: &mut State, ctx: &mut Rltk) -> MainMenuResult {
     }
 }
 
+fn seed_digit(key: VirtualKeyCode) -> Option<char> {
+    match key {
+        VirtualKeyCode::Key0 | VirtualKeyCode::Numpad0 => Some('0'),
+        VirtualKeyCode::Key1 | VirtualKeyCode::Numpad1 => Some('1'),
+        VirtualKeyCode::Key2 | VirtualKeyCode::Numpad2 => Some('2'),
+        VirtualKeyCode::Key3 | VirtualKeyCode::Numpad3 => Some('3'),
+        VirtualKeyCode::Key4 | VirtualKeyCode::Numpad4 => Some('4'),
+        VirtualKeyCode::Key5 | VirtualKeyCode::Numpad5 => Some('5'),
+        VirtualKeyCode::Key6 | VirtualKeyCode::Numpad6 => Some('6'),
+        VirtualKeyCode::Key7 | VirtualKeyCode::Numpad7 => Some('7'),
+        VirtualKeyCode::Key8 | VirtualKeyCode::Numpad8 => Some('8'),
+        VirtualKeyCode::Key9 | VirtualKeyCode::Numpad9 => Some('9'),
+        _ => None,
+    }
+}
+
 pub fn remove_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<Entity>) {
     let player_entity = gs.ecs.fetch::<Entity>();
     let names = gs.ecs.read_storage::<Name>();
@@ -834,7 +1177,7 @@ pub fn remove_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Opti
         .filter(|item| item.0.owner == *player_entity);
     let count = inventory.count();
 
-    let mut y = 25 - (count / 2) as i32;
+    let y = 25 - (count / 2) as i32;
     ctx.draw_box(
         15,
         y - 2,
@@ -864,6 +1207,7 @@ pub fn remove_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Opti
         .filter(|item| item.1.owner == *player_entity)
         .enumerate()
     {
+        let y = y + j as i32;
         ctx.set(
             17,
             y,
@@ -891,10 +1235,9 @@ pub fn remove_item_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Opti
             y,
             get_item_color(&gs.ecs, entity),
             RGB::from_f32(0.0, 0.0, 0.0),
-            &get_item_display_name(&gs.ecs, entity),
+            get_item_display_name(&gs.ecs, entity),
         );
         equippable.push(entity);
-        y += 1;
     }
 
     match ctx.key {
@@ -921,7 +1264,7 @@ pub enum GameOverResult {
     QuitToMenu,
 }
 
-pub fn game_over(ctx: &mut Rltk) -> GameOverResult {
+pub fn game_over(ecs: &World, ctx: &mut Rltk) -> GameOverResult {
     ctx.print_color_centered(
         15,
         RGB::named(rltk::YELLOW),
@@ -929,6 +1272,13 @@ pub fn game_over(ctx: &mut Rltk) -> GameOverResult {
         "Your journey has ended!",
     );
 
+    ctx.print_color_centered(
+        17,
+        RGB::named(rltk::GRAY),
+        RGB::named(rltk::BLACK),
+        format!("Seed: {}", ecs.fetch::<MasterDungeonMap>().seed),
+    );
+
     ctx.print_color_centered(
         20,
         RGB::named(rltk::MAGENTA),
@@ -942,7 +1292,7 @@ pub fn game_over(ctx: &mut Rltk) -> GameOverResult {
     }
 }
 
-#[derive(PartialEq, Copy, Clone)]
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
 pub enum CheatMenuResult {
     NoResponse,
     Cancel,
@@ -950,10 +1300,11 @@ pub enum CheatMenuResult {
     Heal,
     Reveal,
     GodMode,
+    ReloadRaws,
 }
 
 pub fn show_cheat_mode(_gs: &mut State, ctx: &mut Rltk) -> CheatMenuResult {
-    let count = 2;
+    let count = 5;
     let mut y = 25 - (count / 2);
     ctx.draw_box(
         15,
@@ -1073,6 +1424,30 @@ pub fn show_cheat_mode(_gs: &mut State, ctx: &mut Rltk) -> CheatMenuResult {
     );
     ctx.print(21, y, "God Mode (No Death)");
 
+    y += 1;
+    ctx.set(
+        17,
+        y,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+        rltk::to_cp437('('),
+    );
+    ctx.set(
+        18,
+        y,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        rltk::to_cp437('L'),
+    );
+    ctx.set(
+        19,
+        y,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+        rltk::to_cp437(')'),
+    );
+    ctx.print(21, y, "Reload the raws");
+
     match ctx.key {
         None => CheatMenuResult::NoResponse,
         Some(key) => match key {
@@ -1080,6 +1455,7 @@ pub fn show_cheat_mode(_gs: &mut State, ctx: &mut Rltk) -> CheatMenuResult {
             VirtualKeyCode::H => CheatMenuResult::Heal,
             VirtualKeyCode::R => CheatMenuResult::Reveal,
             VirtualKeyCode::G => CheatMenuResult::GodMode,
+            VirtualKeyCode::L => CheatMenuResult::ReloadRaws,
             VirtualKeyCode::Escape => CheatMenuResult::Cancel,
             _ => CheatMenuResult::NoResponse,
         },
@@ -1087,38 +1463,30 @@ pub fn show_cheat_mode(_gs: &mut State, ctx: &mut Rltk) -> CheatMenuResult {
 }
 
 #[derive(PartialEq, Copy, Clone)]
-pub enum VendorResult {
+pub enum SaveMenuResult {
     NoResponse,
     Cancel,
-    Sell,
-    BuyMode,
-    SellMode,
-    Buy,
+    Save(SaveEncoding),
 }
 
-fn vendor_sell_menu(
-    gs: &mut State,
-    ctx: &mut Rltk,
-    _vendor: Entity,
-    _mode: VendorMode,
-) -> (VendorResult, Option<Entity>, Option<String>, Option<f32>) {
-    let player_entity = gs.ecs.fetch::<Entity>();
-    let names = gs.ecs.read_storage::<Name>();
-    let backpack = gs.ecs.read_storage::<InBackpack>();
-    let items = gs.ecs.read_storage::<Item>();
-    let entities = gs.ecs.entities();
-
-    let inventory = (&backpack, &names)
-        .join()
-        .filter(|item| item.0.owner == *player_entity);
-    let count = inventory.count();
-
-    let mut y = (25 - (count / 2)) as i32;
+/// Asks how to write the save before quitting. The encoding saved with last is marked, and
+/// Enter picks it again.
+pub fn show_save_menu(gs: &mut State, ctx: &mut Rltk) -> SaveMenuResult {
+    let current = gs
+        .ecs
+        .try_fetch::<SaveEncoding>()
+        .map_or_else(Default::default, |e| *e);
+    let options = [
+        ('J', SaveEncoding::Json, "Save as JSON (readable)"),
+        ('B', SaveEncoding::Binary, "Save as binary (compact)"),
+    ];
+    let count = options.len() as i32;
+    let y = 25 - (count / 2);
     ctx.draw_box(
         15,
         y - 2,
-        51,
-        (count + 3) as i32,
+        37,
+        count + 3,
         RGB::named(rltk::WHITE),
         RGB::named(rltk::BLACK),
     );
@@ -1127,22 +1495,18 @@ fn vendor_sell_menu(
         y - 2,
         RGB::named(rltk::YELLOW),
         RGB::named(rltk::BLACK),
-        "Sell Which Item? (space to switch to buy mode)",
+        "Save and Quit",
     );
     ctx.print_color(
         18,
-        y + count as i32 + 1,
+        y + count + 1,
         RGB::named(rltk::YELLOW),
         RGB::named(rltk::BLACK),
         "ESCAPE to cancel",
     );
 
-    let mut equippable: Vec<Entity> = Vec::new();
-    for (j, (entity, _pack, item)) in (&entities, &backpack, &items)
-        .join()
-        .filter(|item| item.1.owner == *player_entity)
-        .enumerate()
-    {
+    for (j, (key, encoding, label)) in options.iter().enumerate() {
+        let y = y + j as i32;
         ctx.set(
             17,
             y,
@@ -1155,7 +1519,7 @@ fn vendor_sell_menu(
             y,
             RGB::named(rltk::YELLOW),
             RGB::named(rltk::BLACK),
-            97 + j as rltk::FontCharType,
+            rltk::to_cp437(*key),
         );
         ctx.set(
             19,
@@ -1164,22 +1528,136 @@ fn vendor_sell_menu(
             RGB::named(rltk::BLACK),
             rltk::to_cp437(')'),
         );
+        let fg = if *encoding == current {
+            RGB::named(rltk::MAGENTA)
+        } else {
+            RGB::named(rltk::WHITE)
+        };
+        ctx.print_color(21, y, fg, RGB::named(rltk::BLACK), label);
+    }
 
-        ctx.print_color(
-            21,
-            y,
-            get_item_color(&gs.ecs, entity),
-            RGB::from_f32(0.0, 0.0, 0.0),
-            &get_item_display_name(&gs.ecs, entity),
-        );
-        ctx.print(50, y, &format!("{:.1} gp", item.base_value * 0.8));
-        equippable.push(entity);
-        y += 1;
+    match ctx.key {
+        None => SaveMenuResult::NoResponse,
+        Some(key) => match key {
+            VirtualKeyCode::J => SaveMenuResult::Save(SaveEncoding::Json),
+            VirtualKeyCode::B => SaveMenuResult::Save(SaveEncoding::Binary),
+            VirtualKeyCode::Return => SaveMenuResult::Save(current),
+            VirtualKeyCode::Escape => SaveMenuResult::Cancel,
+            _ => SaveMenuResult::NoResponse,
+        },
+    }
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum VendorResult {
+    NoResponse,
+    Cancel,
+    Sell,
+    BuyMode,
+    SellMode,
+    RechargeMode,
+    Buy,
+    Recharge,
+}
+
+fn vendor_sell_menu(
+    gs: &mut State,
+    ctx: &mut Rltk,
+    vendor: Entity,
+    _mode: VendorMode,
+) -> (VendorResult, Option<Entity>, Option<String>, Option<f32>) {
+    // Vendors who recharge items have a third page
+    let recharges = gs.ecs.read_storage::<Recharger>().get(vendor).is_some();
+    let player_entity = gs.ecs.fetch::<Entity>();
+    let names = gs.ecs.read_storage::<Name>();
+    let backpack = gs.ecs.read_storage::<InBackpack>();
+    let items = gs.ecs.read_storage::<Item>();
+    let stacks = gs.ecs.read_storage::<Stackable>();
+    let entities = gs.ecs.entities();
+
+    let inventory = (&backpack, &names)
+        .join()
+        .filter(|item| item.0.owner == *player_entity);
+    let count = inventory.count();
+
+    let y = (25 - (count / 2)) as i32;
+    ctx.draw_box(
+        15,
+        y - 2,
+        51,
+        (count + 3) as i32,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        18,
+        y - 2,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        if recharges {
+            "Sell Which Item? (space to switch to recharge mode)"
+        } else {
+            "Sell Which Item? (space to switch to buy mode)"
+        },
+    );
+    ctx.print_color(
+        18,
+        y + count as i32 + 1,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "ESCAPE to cancel",
+    );
+
+    let mut equippable: Vec<Entity> = Vec::new();
+    for (j, (entity, _pack, item)) in (&entities, &backpack, &items)
+        .join()
+        .filter(|item| item.1.owner == *player_entity)
+        .enumerate()
+    {
+        let y = y + j as i32;
+        ctx.set(
+            17,
+            y,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::BLACK),
+            rltk::to_cp437('('),
+        );
+        ctx.set(
+            18,
+            y,
+            RGB::named(rltk::YELLOW),
+            RGB::named(rltk::BLACK),
+            97 + j as rltk::FontCharType,
+        );
+        ctx.set(
+            19,
+            y,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::BLACK),
+            rltk::to_cp437(')'),
+        );
+
+        ctx.print_color(
+            21,
+            y,
+            get_item_color(&gs.ecs, entity),
+            RGB::from_f32(0.0, 0.0, 0.0),
+            get_item_display_name(&gs.ecs, entity),
+        );
+        // A stack sells one at a time
+        let each = if stacks.get(entity).is_some_and(|stack| stack.quantity > 1) {
+            " each"
+        } else {
+            ""
+        };
+        ctx.print(50, y, format!("{:.1} gp{}", item.base_value * 0.8, each));
+        equippable.push(entity);
     }
 
     match ctx.key {
         None => (VendorResult::NoResponse, None, None, None),
         Some(key) => match key {
+            VirtualKeyCode::Space if recharges => (VendorResult::RechargeMode, None, None, None),
             VirtualKeyCode::Space => (VendorResult::BuyMode, None, None, None),
             VirtualKeyCode::Escape => (VendorResult::Cancel, None, None, None),
             _ => {
@@ -1214,7 +1692,7 @@ fn vendor_buy_menu(
     );
     let count = inventory.len();
 
-    let mut y = (25 - (count / 2)) as i32;
+    let y = (25 - (count / 2)) as i32;
     ctx.draw_box(
         15,
         y - 2,
@@ -1239,6 +1717,7 @@ fn vendor_buy_menu(
     );
 
     for (j, sale) in inventory.iter().enumerate() {
+        let y = y + j as i32;
         ctx.set(
             17,
             y,
@@ -1262,8 +1741,7 @@ fn vendor_buy_menu(
         );
 
         ctx.print(21, y, &sale.0);
-        ctx.print(50, y, &format!("{:.1} gp", sale.1 * 1.2));
-        y += 1;
+        ctx.print(50, y, format!("{:.1} gp", sale.1 * 1.2));
     }
 
     match ctx.key {
@@ -1287,6 +1765,101 @@ fn vendor_buy_menu(
     }
 }
 
+fn vendor_recharge_menu(
+    gs: &mut State,
+    ctx: &mut Rltk,
+    vendor: Entity,
+    _mode: VendorMode,
+) -> (VendorResult, Option<Entity>, Option<String>, Option<f32>) {
+    let price = gs
+        .ecs
+        .read_storage::<Recharger>()
+        .get(vendor)
+        .map_or(0.0, |recharger| recharger.price);
+    let charged = charged_items(&gs.ecs);
+    let count = charged.len().max(1);
+
+    let y = (25 - (count / 2)) as i32;
+    ctx.draw_box(
+        15,
+        y - 2,
+        51,
+        (count + 3) as i32,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        18,
+        y - 2,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "Recharge Which Item? (space to switch to buy mode)",
+    );
+    ctx.print_color(
+        18,
+        y + count as i32 + 1,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "ESCAPE to cancel",
+    );
+
+    if charged.is_empty() {
+        ctx.print(17, y, "You carry nothing with charges.");
+    }
+    for (j, item) in charged.iter().enumerate() {
+        let y = y + j as i32;
+        ctx.set(
+            17,
+            y,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::BLACK),
+            rltk::to_cp437('('),
+        );
+        ctx.set(
+            18,
+            y,
+            RGB::named(rltk::YELLOW),
+            RGB::named(rltk::BLACK),
+            97 + j as rltk::FontCharType,
+        );
+        ctx.set(
+            19,
+            y,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::BLACK),
+            rltk::to_cp437(')'),
+        );
+        ctx.print_color(
+            21,
+            y,
+            get_item_color(&gs.ecs, *item),
+            RGB::from_f32(0.0, 0.0, 0.0),
+            get_item_display_name(&gs.ecs, *item),
+        );
+        ctx.print(50, y, format!("{:.1} gp", price));
+    }
+
+    match ctx.key {
+        None => (VendorResult::NoResponse, None, None, None),
+        Some(key) => match key {
+            VirtualKeyCode::Space => (VendorResult::BuyMode, None, None, None),
+            VirtualKeyCode::Escape => (VendorResult::Cancel, None, None, None),
+            _ => {
+                let selection = rltk::letter_to_option(key);
+                if selection > -1 && (selection as usize) < charged.len() {
+                    return (
+                        VendorResult::Recharge,
+                        Some(charged[selection as usize]),
+                        None,
+                        Some(price),
+                    );
+                }
+                (VendorResult::NoResponse, None, None, None)
+            }
+        },
+    }
+}
+
 pub fn show_vendor_menu(
     gs: &mut State,
     ctx: &mut Rltk,
@@ -1296,6 +1869,7 @@ pub fn show_vendor_menu(
     match mode {
         VendorMode::Buy => vendor_buy_menu(gs, ctx, vendor, mode),
         VendorMode::Sell => vendor_sell_menu(gs, ctx, vendor, mode),
+        VendorMode::Recharge => vendor_recharge_menu(gs, ctx, vendor, mode),
     }
 }
 
@@ -1310,21 +1884,627 @@ pub fn get_item_color(ecs: &World, item: Entity) -> RGB {
     RGB::from_f32(1.0, 1.0, 1.0)
 }
 
+/// The plural of an item's name, e.g. "Health Potions" or "Scrolls of abeku".
+fn plural(name: &str) -> String {
+    if let Some((noun, rest)) = name.split_once(" of ") {
+        format!("{} of {}", plural(noun), rest)
+    } else if name.ends_with('s') {
+        name.to_string()
+    } else {
+        format!("{}s", name)
+    }
+}
+
 pub fn get_item_display_name(ecs: &World, item: Entity) -> String {
+    // A stack is counted, e.g. "3 Health Potions"
+    let quantity = ecs
+        .read_storage::<Stackable>()
+        .get(item)
+        .map_or(1, |stack| stack.quantity);
+    let counted = |name: &str| {
+        if quantity > 1 {
+            format!("{} {}", quantity, plural(name))
+        } else {
+            name.to_string()
+        }
+    };
+    // Curses and charges show once the item is known for what it is
+    let cursed = ecs.read_storage::<CursedItem>().get(item).is_some();
+    let charges = ecs
+        .read_storage::<Charges>()
+        .get(item)
+        .map(|charges| charges.remaining);
+    let known = |name: &str| {
+        let mut shown = counted(name);
+        match charges {
+            Some(1) => shown.push_str(" (1 charge)"),
+            Some(remaining) => shown.push_str(&format!(" ({} charges)", remaining)),
+            None => {}
+        }
+        if cursed {
+            shown.push_str(" (cursed)");
+        }
+        shown
+    };
     if let Some(name) = ecs.read_storage::<Name>().get(item) {
         if ecs.read_storage::<MagicItem>().get(item).is_some() {
             let dm = ecs.fetch::<crate::map::MasterDungeonMap>();
             if dm.identified_items.contains(&name.name) {
-                name.name.clone()
+                known(&name.name)
             } else if let Some(obfuscated) = ecs.read_storage::<ObfuscatedName>().get(item) {
-                obfuscated.name.clone()
+                match dm.item_notes.get(&name.name).and_then(ItemNote::describe) {
+                    Some(hints) => format!("{} {}", counted(&obfuscated.name), hints),
+                    None => counted(&obfuscated.name),
+                }
             } else {
                 "Unidentified magic item".to_string()
             }
         } else {
-            name.name.clone()
+            known(&name.name)
         }
     } else {
         "Nameless item (bug)".to_string()
     }
 }
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum CharacterSheetResult {
+    NoResponse,
+    Close,
+}
+
+pub fn show_character_sheet(ecs: &World, ctx: &mut Rltk) -> CharacterSheetResult {
+    let white = RGB::named(rltk::WHITE);
+    let black = RGB::named(rltk::BLACK);
+    let yellow = RGB::named(rltk::YELLOW);
+
+    let player_entity = ecs.fetch::<Entity>();
+    let pools = ecs.read_storage::<Pools>();
+    let attributes = ecs.read_storage::<Attributes>();
+    let skills = ecs.read_storage::<Skills>();
+    let (Some(player_pools), Some(attr), Some(player_skills)) = (
+        pools.get(*player_entity),
+        attributes.get(*player_entity),
+        skills.get(*player_entity),
+    ) else {
+        return CharacterSheetResult::Close;
+    };
+
+    ctx.draw_box(10, 8, 40, 17, white, black);
+    ctx.print_color(13, 8, yellow, black, "Character");
+    ctx.print_color(13, 25, yellow, black, "ESCAPE to close");
+
+    ctx.print_color(
+        12,
+        10,
+        white,
+        black,
+        format!("Level: {}", player_pools.level),
+    );
+    let mut y = 12;
+    for (label, attribute) in [
+        ("Might:", &attr.might),
+        ("Quickness:", &attr.quickness),
+        ("Fitness:", &attr.fitness),
+        ("Intelligence:", &attr.intelligence),
+    ] {
+        ctx.print_color(12, y, white, black, label);
+        ctx.print_color(
+            27,
+            y,
+            white,
+            black,
+            format!(
+                "{} ({:+})",
+                attribute.base + attribute.modifiers,
+                attribute.bonus
+            ),
+        );
+        y += 1;
+    }
+
+    // Skills, with how far each is towards its next level
+    y += 1;
+    let cap = skill_cap(player_pools.level);
+    ctx.print_color(12, y, yellow, black, format!("Skills (max {})", cap));
+    y += 1;
+    for skill in TRAINABLE_SKILLS {
+        let level = skill_level(skill, player_skills);
+        let progress = if level >= cap {
+            "capped".to_string()
+        } else {
+            format!(
+                "{} / {}",
+                player_skills.practice.get(&skill).copied().unwrap_or(0),
+                skill_practice_needed(level)
+            )
+        };
+        ctx.print_color(12, y, white, black, format!("{:?}:", skill));
+        let modifier = player_skills.modifiers.get(&skill).copied().unwrap_or(0);
+        let shown = if modifier == 0 {
+            format!("{}", level)
+        } else {
+            format!("{} ({:+})", level, modifier)
+        };
+        ctx.print_color(27, y, white, black, shown);
+        ctx.print_color(36, y, RGB::named(rltk::GRAY), black, progress);
+        y += 1;
+    }
+
+    match ctx.key {
+        Some(VirtualKeyCode::Escape) | Some(VirtualKeyCode::P) => CharacterSheetResult::Close,
+        _ => CharacterSheetResult::NoResponse,
+    }
+}
+
+pub enum LevelUpResult {
+    NoResponse,
+    Selected(PlayerCommand),
+}
+
+/// Spends the points from levelling up; the screen stays until they're gone.
+pub fn show_level_up(gs: &mut State, ctx: &mut Rltk) -> LevelUpResult {
+    let white = RGB::named(rltk::WHITE);
+    let black = RGB::named(rltk::BLACK);
+    let yellow = RGB::named(rltk::YELLOW);
+    let gray = RGB::named(rltk::GRAY);
+
+    let player_entity = gs.ecs.fetch::<Entity>();
+    let pools = gs.ecs.read_storage::<Pools>();
+    let attributes = gs.ecs.read_storage::<Attributes>();
+    let skills = gs.ecs.read_storage::<Skills>();
+    let points = gs.ecs.read_storage::<LevelUpPoints>();
+    let (Some(player_pools), Some(attr), Some(player_skills), Some(player_points)) = (
+        pools.get(*player_entity),
+        attributes.get(*player_entity),
+        skills.get(*player_entity),
+        points.get(*player_entity),
+    ) else {
+        return LevelUpResult::NoResponse;
+    };
+
+    ctx.draw_box(10, 8, 40, 17, white, black);
+    ctx.print_color(13, 8, yellow, black, "Level Up!");
+    ctx.print_color(
+        12,
+        10,
+        white,
+        black,
+        format!("You are now level {}.", player_pools.level),
+    );
+
+    let mut choices: Vec<PlayerCommand> = Vec::new();
+    let mut y = 12;
+    ctx.print_color(
+        12,
+        y,
+        yellow,
+        black,
+        format!("Attribute points: {}", player_points.attributes),
+    );
+    y += 1;
+    for (name, attribute) in [
+        (AttributeName::Might, &attr.might),
+        (AttributeName::Fitness, &attr.fitness),
+        (AttributeName::Quickness, &attr.quickness),
+        (AttributeName::Intelligence, &attr.intelligence),
+    ] {
+        let fg = if player_points.attributes > 0 {
+            white
+        } else {
+            gray
+        };
+        ctx.print_color(
+            12,
+            y,
+            fg,
+            black,
+            format!("({}) {:?}", (97 + choices.len() as u8) as char, name),
+        );
+        ctx.print_color(32, y, fg, black, format!("{}", attribute.base));
+        choices.push(PlayerCommand::RaiseAttribute { attribute: name });
+        y += 1;
+    }
+
+    y += 1;
+    let cap = skill_cap(player_pools.level);
+    ctx.print_color(
+        12,
+        y,
+        yellow,
+        black,
+        format!("Skill points: {} (max {})", player_points.skills, cap),
+    );
+    y += 1;
+    for skill in TRAINABLE_SKILLS {
+        let level = skill_level(skill, player_skills);
+        let fg = if player_points.skills > 0 && level < cap {
+            white
+        } else {
+            gray
+        };
+        ctx.print_color(
+            12,
+            y,
+            fg,
+            black,
+            format!("({}) {:?}", (97 + choices.len() as u8) as char, skill),
+        );
+        ctx.print_color(32, y, fg, black, format!("{}", level));
+        choices.push(PlayerCommand::RaiseSkill { skill });
+        y += 1;
+    }
+
+    match ctx.key {
+        None => LevelUpResult::NoResponse,
+        Some(key) => {
+            let selection = rltk::letter_to_option(key);
+            if selection > -1 && (selection as usize) < choices.len() {
+                LevelUpResult::Selected(choices[selection as usize])
+            } else {
+                LevelUpResult::NoResponse
+            }
+        }
+    }
+}
+
+/// The temple: pick a cursed item to have its curse lifted for gold.
+pub fn show_temple(
+    gs: &mut State,
+    ctx: &mut Rltk,
+    priest: Entity,
+) -> (ItemMenuResult, Option<Entity>) {
+    let player_entity = gs.ecs.fetch::<Entity>();
+    let equipped = gs.ecs.read_storage::<Equipped>();
+    let cursed = gs.ecs.read_storage::<CursedItem>();
+    let entities = gs.ecs.entities();
+    let price = gs
+        .ecs
+        .read_storage::<Temple>()
+        .get(priest)
+        .map_or(0.0, |temple| temple.curse_price);
+
+    let afflicted: Vec<Entity> = (&entities, &equipped, &cursed)
+        .join()
+        .filter(|(_, worn, _)| worn.owner == *player_entity)
+        .map(|(item, _, _)| item)
+        .collect();
+    let count = afflicted.len().max(1);
+
+    let y = (25 - (count / 2)) as i32;
+    ctx.draw_box(
+        15,
+        y - 2,
+        51,
+        (count + 3) as i32,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        18,
+        y - 2,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "Lift the curse on which item?",
+    );
+    ctx.print_color(
+        18,
+        y + count as i32 + 1,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "ESCAPE to cancel",
+    );
+
+    if afflicted.is_empty() {
+        ctx.print(17, y, "The priest finds no curse upon you.");
+    }
+    for (j, item) in afflicted.iter().enumerate() {
+        let y = y + j as i32;
+        ctx.set(
+            17,
+            y,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::BLACK),
+            rltk::to_cp437('('),
+        );
+        ctx.set(
+            18,
+            y,
+            RGB::named(rltk::YELLOW),
+            RGB::named(rltk::BLACK),
+            97 + j as rltk::FontCharType,
+        );
+        ctx.set(
+            19,
+            y,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::BLACK),
+            rltk::to_cp437(')'),
+        );
+        ctx.print_color(
+            21,
+            y,
+            get_item_color(&gs.ecs, *item),
+            RGB::from_f32(0.0, 0.0, 0.0),
+            get_item_display_name(&gs.ecs, *item),
+        );
+        ctx.print(50, y, format!("{:.1} gp", price));
+    }
+
+    match ctx.key {
+        None => (ItemMenuResult::NoResponse, None),
+        Some(VirtualKeyCode::Escape) => (ItemMenuResult::Cancel, None),
+        Some(key) => {
+            let selection = rltk::letter_to_option(key);
+            if selection > -1 && (selection as usize) < afflicted.len() {
+                (
+                    ItemMenuResult::Selected,
+                    Some(afflicted[selection as usize]),
+                )
+            } else {
+                (ItemMenuResult::NoResponse, None)
+            }
+        }
+    }
+}
+
+/// What the player has noted about an item's kind so far.
+pub fn get_item_note(ecs: &World, item: Entity) -> ItemNote {
+    let dm = ecs.fetch::<MasterDungeonMap>();
+    ecs.read_storage::<Name>()
+        .get(item)
+        .and_then(|name| dm.item_notes.get(&name.name))
+        .cloned()
+        .unwrap_or_default()
+}
+
+pub fn show_identify(
+    gs: &mut State,
+    ctx: &mut Rltk,
+    scroll: Entity,
+) -> (ItemMenuResult, Option<Entity>) {
+    let unknown: Vec<Entity> = unidentified_items(&gs.ecs)
+        .into_iter()
+        .filter(|item| *item != scroll)
+        .collect();
+    item_choice_menu(
+        gs,
+        ctx,
+        "Identify which item?",
+        &unknown,
+        "You know everything you carry.",
+    )
+}
+
+pub fn show_annotate_menu(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<Entity>) {
+    let unknown = unidentified_items(&gs.ecs);
+    item_choice_menu(
+        gs,
+        ctx,
+        "Write a note on which item?",
+        &unknown,
+        "You know everything you carry.",
+    )
+}
+
+pub fn show_recharge(gs: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<Entity>) {
+    let charged = charged_items(&gs.ecs);
+    item_choice_menu(
+        gs,
+        ctx,
+        "Recharge which item?",
+        &charged,
+        "You carry nothing with charges.",
+    )
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum ContainerResult {
+    NoResponse,
+    Cancel,
+    SwitchMode,
+    Take,
+    Put,
+}
+
+/// The transfer menu of an open container: taking things out of it, or putting backpack
+/// items in. Space switches between the two.
+pub fn show_container(
+    gs: &mut State,
+    ctx: &mut Rltk,
+    container: Entity,
+    mode: ContainerMode,
+) -> (ContainerResult, Option<Entity>) {
+    let name = gs
+        .ecs
+        .read_storage::<Name>()
+        .get(container)
+        .map_or(String::new(), |name| name.name.clone());
+    let (items, title, empty) = match mode {
+        ContainerMode::Take => (
+            container_items(&gs.ecs, container),
+            "Take which item? (space to put items in)",
+            format!("The {} is empty.", name),
+        ),
+        ContainerMode::Put => (
+            backpack_items(&gs.ecs),
+            "Put in which item? (space to take items out)",
+            "You carry nothing.".to_string(),
+        ),
+    };
+    let result = item_choice_menu(gs, ctx, title, &items, &empty);
+
+    if ctx.key == Some(VirtualKeyCode::Space) {
+        return (ContainerResult::SwitchMode, None);
+    }
+    match result.0 {
+        ItemMenuResult::NoResponse => (ContainerResult::NoResponse, None),
+        ItemMenuResult::Cancel => (ContainerResult::Cancel, None),
+        ItemMenuResult::Selected => match mode {
+            ContainerMode::Take => (ContainerResult::Take, result.1),
+            ContainerMode::Put => (ContainerResult::Put, result.1),
+        },
+    }
+}
+
+/// A letter menu to pick one of `items`, with `empty` shown if there are none.
+fn item_choice_menu(
+    gs: &mut State,
+    ctx: &mut Rltk,
+    title: &str,
+    items: &[Entity],
+    empty: &str,
+) -> (ItemMenuResult, Option<Entity>) {
+    let count = items.len().max(1);
+
+    let y = (25 - (count / 2)) as i32;
+    ctx.draw_box(
+        15,
+        y - 2,
+        51,
+        (count + 3) as i32,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        18,
+        y - 2,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        title,
+    );
+    ctx.print_color(
+        18,
+        y + count as i32 + 1,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "ESCAPE to cancel",
+    );
+
+    if items.is_empty() {
+        ctx.print(17, y, empty);
+    }
+    for (j, item) in items.iter().enumerate() {
+        let y = y + j as i32;
+        ctx.set(
+            17,
+            y,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::BLACK),
+            rltk::to_cp437('('),
+        );
+        ctx.set(
+            18,
+            y,
+            RGB::named(rltk::YELLOW),
+            RGB::named(rltk::BLACK),
+            97 + j as rltk::FontCharType,
+        );
+        ctx.set(
+            19,
+            y,
+            RGB::named(rltk::WHITE),
+            RGB::named(rltk::BLACK),
+            rltk::to_cp437(')'),
+        );
+        ctx.print_color(
+            21,
+            y,
+            get_item_color(&gs.ecs, *item),
+            RGB::from_f32(0.0, 0.0, 0.0),
+            get_item_display_name(&gs.ecs, *item),
+        );
+    }
+
+    match ctx.key {
+        None => (ItemMenuResult::NoResponse, None),
+        Some(VirtualKeyCode::Escape) => (ItemMenuResult::Cancel, None),
+        Some(key) => {
+            let selection = rltk::letter_to_option(key);
+            if selection > -1 && (selection as usize) < items.len() {
+                (ItemMenuResult::Selected, Some(items[selection as usize]))
+            } else {
+                (ItemMenuResult::NoResponse, None)
+            }
+        }
+    }
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum AnnotateResult {
+    NoResponse,
+    Cancel,
+    Done,
+}
+
+/// Lets the player type a note on an unidentified item into `gs.note_entry`.
+pub fn annotate_item(gs: &mut State, ctx: &mut Rltk, item: Entity) -> AnnotateResult {
+    let name = get_item_display_name(&gs.ecs, item);
+    ctx.draw_box(
+        15,
+        22,
+        51,
+        5,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        18,
+        22,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "Write a note",
+    );
+    ctx.print_color(
+        17,
+        24,
+        get_item_color(&gs.ecs, item),
+        RGB::named(rltk::BLACK),
+        &name,
+    );
+    ctx.print(17, 25, format!("Note: {}_", gs.note_entry));
+    ctx.print_color(
+        18,
+        27,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "ENTER to save, ESCAPE to cancel",
+    );
+
+    match ctx.key {
+        None => AnnotateResult::NoResponse,
+        Some(VirtualKeyCode::Escape) => {
+            gs.note_entry.clear();
+            AnnotateResult::Cancel
+        }
+        Some(VirtualKeyCode::Return) => AnnotateResult::Done,
+        Some(VirtualKeyCode::Back) => {
+            gs.note_entry.pop();
+            AnnotateResult::NoResponse
+        }
+        Some(key) => {
+            if let Some(c) = note_char(key, ctx.shift) {
+                // Anything longer won't fit beside the item's name
+                if gs.note_entry.len() < 24 {
+                    gs.note_entry.push(c);
+                }
+            }
+            AnnotateResult::NoResponse
+        }
+    }
+}
+
+fn note_char(key: VirtualKeyCode, shift: bool) -> Option<char> {
+    let letter = rltk::letter_to_option(key);
+    if letter > -1 {
+        let c = (b'a' + letter as u8) as char;
+        return Some(if shift { c.to_ascii_uppercase() } else { c });
+    }
+    match key {
+        VirtualKeyCode::Space => Some(' '),
+        VirtualKeyCode::Minus => Some('-'),
+        VirtualKeyCode::Period => Some('.'),
+        VirtualKeyCode::Slash if shift => Some('?'),
+        _ => seed_digit(key),
+    }
+}
@@ -0,0 +1,1142 @@
+extern crate serde;
+mod ai;
+pub mod camera;
+mod components;
+pub mod effects;
+pub mod game;
+pub mod game_system;
+mod gamelog;
+mod gui;
+mod map;
+pub mod map_builders;
+mod player;
+pub mod random_table;
+mod raws;
+mod rect;
+mod replay;
+mod rex_assets;
+mod spatial;
+mod spawner;
+mod systems;
+
+pub use components::*;
+pub use game::Game;
+pub use game_system::*;
+pub use gamelog::GameLog;
+pub use map::*;
+pub use player::*;
+pub use raws::{
+    check_raw_files, check_raws_with_mods, load_raws, read_raw_dir, reload_raws, Dice, Effect,
+    RawError, RawFile, MOD_DIR, RAW_DIR,
+};
+pub use rect::Rect;
+pub use systems::*;
+
+use rltk::{GameState, Point, Rltk};
+use specs::prelude::*;
+use specs::saveload::{SimpleMarker, SimpleMarkerAllocator};
+#[macro_use]
+extern crate lazy_static;
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum VendorMode {
+    Buy,
+    Sell,
+    Recharge,
+}
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum ContainerMode {
+    Take,
+    Put,
+}
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum RunState {
+    AwaitingInput,
+    PreRun,
+    Ticking,
+    ShowInventory,
+    ShowDropItem,
+    ShowTargeting {
+        range: i32,
+        item: Entity,
+    },
+    ShowFireTargeting {
+        range: i32,
+    },
+    ShowCastMenu,
+    ShowSpellTargeting {
+        range: i32,
+        spell: usize,
+    },
+    MainMenu {
+        menu_selection: gui::MainMenuSelection,
+    },
+    SaveGame,
+    ShowSaveMenu,
+    ShowLoadMenu,
+    NextLevel,
+    PreviousLevel,
+    TownPortal,
+    ShowRemoveItem,
+    GameOver,
+    MagicMapReveal {
+        row: i32,
+    },
+    MapGeneration,
+    ShowCheatMenu,
+    ShowCharacterSheet,
+    LevelUp,
+    ShowVendor {
+        vendor: Entity,
+        mode: VendorMode,
+    },
+    ShowTemple {
+        priest: Entity,
+    },
+    ShowIdentify {
+        scroll: Entity,
+    },
+    ShowRecharge {
+        scroll: Entity,
+    },
+    ShowContainer {
+        container: Entity,
+        mode: ContainerMode,
+    },
+    ShowAnnotateMenu,
+    AnnotateItem {
+        item: Entity,
+    },
+    TeleportingToOtherLevel {
+        x: i32,
+        y: i32,
+        depth: i32,
+    },
+}
+
+impl RunState {
+    /// Whether the game is waiting on the player: either for a command, or for a choice in
+    /// a menu that leads to one. These are the states a replay feeds its inputs to.
+    pub fn awaits_player(&self) -> bool {
+        matches!(
+            self,
+            RunState::AwaitingInput
+                | RunState::ShowInventory
+                | RunState::ShowDropItem
+                | RunState::ShowRemoveItem
+                | RunState::ShowTargeting { .. }
+                | RunState::ShowFireTargeting { .. }
+                | RunState::ShowCastMenu
+                | RunState::ShowSpellTargeting { .. }
+                | RunState::ShowVendor { .. }
+                | RunState::ShowTemple { .. }
+                | RunState::ShowIdentify { .. }
+                | RunState::ShowRecharge { .. }
+                | RunState::ShowContainer { .. }
+                | RunState::ShowCheatMenu
+                | RunState::LevelUp
+        )
+    }
+}
+
+pub struct State {
+    ecs: World,
+    mapgen_next_state: Option<RunState>,
+    mapgen_history: Vec<Map>,
+    mapgen_index: usize,
+    mapgen_timer: f32,
+    /// The seed typed into the main menu, used for the next new game
+    seed_entry: String,
+    /// The note being written on an unidentified item
+    note_entry: String,
+    /// Why the last attempt to load a save failed, shown on the main menu
+    load_error: Option<String>,
+    /// The save slot the current run saves to, if it came from the main menu
+    save_slot: Option<String>,
+    recorder: Option<replay::Recorder>,
+    playback: Option<replay::Replay>,
+}
+
+impl GameState for State {
+    fn tick(&mut self, ctx: &mut Rltk) {
+        let mut newrunstate;
+        {
+            let runstate = self.ecs.fetch::<RunState>();
+            newrunstate = *runstate;
+        }
+
+        ctx.cls();
+        particle_system::cull_dead_particles(&mut self.ecs, ctx.frame_time_ms);
+
+        match newrunstate {
+            RunState::MainMenu { .. } => {}
+            RunState::ShowLoadMenu => {}
+            RunState::GameOver => {}
+            _ => {
+                camera::render_camera(&self.ecs, ctx);
+                gui::draw_ui(&self.ecs, ctx);
+                self.ecs.write_resource::<saveload_system::PlayTime>().ms +=
+                    ctx.frame_time_ms as u64;
+            }
+        }
+
+        match newrunstate {
+            RunState::MapGeneration => {
+                #[cfg(not(debug_assertions))]
+                {
+                    newrunstate = self.mapgen_next_state.unwrap();
+                }
+                #[cfg(debug_assertions)]
+                {
+                    ctx.cls();
+                    if self.mapgen_index < self.mapgen_history.len() {
+                        camera::render_debug_map(&self.mapgen_history[self.mapgen_index], ctx);
+                    }
+
+                    self.mapgen_timer += ctx.frame_time_ms;
+                    if self.mapgen_timer > 200.0 {
+                        self.mapgen_timer = 0.0;
+                        self.mapgen_index += 1;
+                        if self.mapgen_index >= self.mapgen_history.len() {
+                            //self.mapgen_index -= 1;
+                            newrunstate = self.mapgen_next_state.unwrap();
+                        }
+                    }
+                }
+            }
+            RunState::PreRun
+            | RunState::Ticking
+            | RunState::NextLevel
+            | RunState::PreviousLevel
+            | RunState::MagicMapReveal { .. }
+            | RunState::TownPortal
+            | RunState::TeleportingToOtherLevel { .. } => {
+                newrunstate = self.advance(newrunstate);
+            }
+            runstate if runstate.awaits_player() && self.playback.is_some() => {
+                newrunstate = self.play_back(newrunstate);
+            }
+            RunState::AwaitingInput => {
+                newrunstate = player_input(self, ctx);
+            }
+            RunState::ShowInventory => {
+                let result = gui::show_inventory(self, ctx);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        let item = backpack_index(&self.ecs, result.1.unwrap());
+                        let command = PlayerCommand::UseItem { item, target: None };
+                        newrunstate =
+                            self.perform(replay::ReplayInput::Command(command), newrunstate);
+                    }
+                }
+            }
+            RunState::ShowDropItem => {
+                let result = gui::drop_item_menu(self, ctx);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        let item = backpack_index(&self.ecs, result.1.unwrap());
+                        let command = PlayerCommand::DropItem { item };
+                        newrunstate =
+                            self.perform(replay::ReplayInput::Command(command), newrunstate);
+                    }
+                }
+            }
+            RunState::ShowRemoveItem => {
+                let result = gui::remove_item_menu(self, ctx);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        let item = equipped_index(&self.ecs, result.1.unwrap());
+                        let command = PlayerCommand::RemoveItem { item };
+                        newrunstate =
+                            self.perform(replay::ReplayInput::Command(command), newrunstate);
+                    }
+                }
+            }
+            RunState::ShowTargeting { range, item } => {
+                let result = gui::ranged_target(self, ctx, range);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        let item = backpack_index(&self.ecs, item);
+                        let command = PlayerCommand::UseItem {
+                            item,
+                            target: result.1,
+                        };
+                        newrunstate =
+                            self.perform(replay::ReplayInput::Command(command), newrunstate);
+                    }
+                }
+            }
+            RunState::ShowFireTargeting { range } => {
+                let result = gui::ranged_target(self, ctx, range);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        let command = PlayerCommand::Fire { target: result.1 };
+                        newrunstate =
+                            self.perform(replay::ReplayInput::Command(command), newrunstate);
+                    }
+                }
+            }
+            RunState::ShowCastMenu => {
+                let result = gui::show_spells(self, ctx);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        let command = PlayerCommand::CastSpell {
+                            spell: result.1.unwrap(),
+                            target: None,
+                        };
+                        newrunstate =
+                            self.perform(replay::ReplayInput::Command(command), newrunstate);
+                    }
+                }
+            }
+            RunState::ShowSpellTargeting { range, spell } => {
+                let result = gui::ranged_target(self, ctx, range);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        let command = PlayerCommand::CastSpell {
+                            spell,
+                            target: result.1,
+                        };
+                        newrunstate =
+                            self.perform(replay::ReplayInput::Command(command), newrunstate);
+                    }
+                }
+            }
+            RunState::ShowVendor { vendor, mode } => {
+                let result = gui::show_vendor_menu(self, ctx, vendor, mode);
+                match result.0 {
+                    gui::VendorResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::VendorResult::NoResponse => {}
+                    gui::VendorResult::Sell => {
+                        let item = backpack_index(&self.ecs, result.1.unwrap());
+                        newrunstate = self.perform(replay::ReplayInput::Sell { item }, newrunstate);
+                    }
+                    gui::VendorResult::Buy => {
+                        let tag = result.2.unwrap();
+                        let price = result.3.unwrap();
+                        newrunstate =
+                            self.perform(replay::ReplayInput::Buy { tag, price }, newrunstate);
+                    }
+                    gui::VendorResult::BuyMode => {
+                        newrunstate = RunState::ShowVendor {
+                            vendor,
+                            mode: VendorMode::Buy,
+                        }
+                    }
+                    gui::VendorResult::SellMode => {
+                        newrunstate = RunState::ShowVendor {
+                            vendor,
+                            mode: VendorMode::Sell,
+                        }
+                    }
+                    gui::VendorResult::RechargeMode => {
+                        newrunstate = RunState::ShowVendor {
+                            vendor,
+                            mode: VendorMode::Recharge,
+                        }
+                    }
+                    gui::VendorResult::Recharge => {
+                        let item = backpack_index(&self.ecs, result.1.unwrap());
+                        let price = result.3.unwrap();
+                        newrunstate = self
+                            .perform(replay::ReplayInput::Recharge { item, price }, newrunstate);
+                    }
+                }
+            }
+            RunState::MainMenu { .. } => {
+                let result = gui::main_menu(self, ctx);
+                match result {
+                    gui::MainMenuResult::NoSelection { selected } => {
+                        newrunstate = RunState::MainMenu {
+                            menu_selection: selected,
+                        }
+                    }
+                    gui::MainMenuResult::Selected { selected } => match selected {
+                        gui::MainMenuSelection::NewGame => {
+                            self.load_error = None;
+                            self.save_slot = Some(saveload_system::new_slot_name());
+                            self.ecs.insert(saveload_system::PlayTime::default());
+                            let seed = self.seed_entry.parse::<u64>().ok();
+                            if seed == Some(self.ecs.fetch::<map::MasterDungeonMap>().seed) {
+                                newrunstate = RunState::PreRun;
+                            } else {
+                                // The waiting world was built from another seed
+                                self.new_run(seed.unwrap_or_else(random_seed));
+                                newrunstate = RunState::MapGeneration;
+                                self.mapgen_next_state = Some(RunState::PreRun);
+                            }
+                            let seed = self.ecs.fetch::<map::MasterDungeonMap>().seed;
+                            self.recorder = replay::Recorder::start(seed);
+                        }
+                        gui::MainMenuSelection::LoadGame => {
+                            newrunstate = RunState::ShowLoadMenu;
+                        }
+                        gui::MainMenuSelection::Quit => {
+                            ::std::process::exit(0);
+                        }
+                    },
+                }
+            }
+            RunState::ShowLoadMenu => match gui::load_menu(ctx) {
+                gui::LoadMenuResult::NoResponse => {}
+                gui::LoadMenuResult::Cancel => {
+                    newrunstate = RunState::MainMenu {
+                        menu_selection: gui::MainMenuSelection::LoadGame,
+                    };
+                }
+                gui::LoadMenuResult::Selected(slot) => {
+                    newrunstate = self.load_slot(slot);
+                }
+            },
+            RunState::GameOver => {
+                let result = gui::game_over(&self.ecs, ctx);
+                match result {
+                    gui::GameOverResult::NoSelection => {}
+                    gui::GameOverResult::QuitToMenu => {
+                        self.game_over_cleanup();
+                        newrunstate = RunState::MapGeneration;
+                        self.mapgen_next_state = Some(RunState::MainMenu {
+                            menu_selection: gui::MainMenuSelection::NewGame,
+                        });
+                    }
+                }
+            }
+            RunState::ShowSaveMenu => match gui::show_save_menu(self, ctx) {
+                gui::SaveMenuResult::NoResponse => {}
+                gui::SaveMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                gui::SaveMenuResult::Save(encoding) => {
+                    self.set_save_encoding(encoding);
+                    newrunstate = RunState::SaveGame;
+                }
+            },
+            RunState::SaveGame => {
+                self.recorder = None;
+                let slot = self
+                    .save_slot
+                    .get_or_insert_with(saveload_system::new_slot_name);
+                saveload_system::save_game(&mut self.ecs, slot);
+
+                // The saved run lives on in its slot; a new game shouldn't pick it up
+                self.save_slot = None;
+                self.new_run(random_seed());
+                newrunstate = RunState::MapGeneration;
+                self.mapgen_next_state = Some(RunState::MainMenu {
+                    menu_selection: gui::MainMenuSelection::LoadGame,
+                });
+            }
+            RunState::LevelUp => {
+                if let gui::LevelUpResult::Selected(command) = gui::show_level_up(self, ctx) {
+                    newrunstate = self.perform(replay::ReplayInput::Command(command), newrunstate);
+                }
+            }
+            RunState::ShowTemple { priest } => {
+                let result = gui::show_temple(self, ctx, priest);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        let item = equipped_index(&self.ecs, result.1.unwrap());
+                        let price = self
+                            .ecs
+                            .read_storage::<Temple>()
+                            .get(priest)
+                            .map_or(0.0, |temple| temple.curse_price);
+                        newrunstate = self
+                            .perform(replay::ReplayInput::LiftCurse { item, price }, newrunstate);
+                    }
+                }
+            }
+            RunState::ShowIdentify { scroll } => {
+                let result = gui::show_identify(self, ctx, scroll);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        let command = PlayerCommand::Identify {
+                            scroll: backpack_index(&self.ecs, scroll),
+                            item: backpack_index(&self.ecs, result.1.unwrap()),
+                        };
+                        newrunstate =
+                            self.perform(replay::ReplayInput::Command(command), newrunstate);
+                    }
+                }
+            }
+            RunState::ShowRecharge { scroll } => {
+                let result = gui::show_recharge(self, ctx);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        let command = PlayerCommand::Recharge {
+                            scroll: backpack_index(&self.ecs, scroll),
+                            item: backpack_index(&self.ecs, result.1.unwrap()),
+                        };
+                        newrunstate =
+                            self.perform(replay::ReplayInput::Command(command), newrunstate);
+                    }
+                }
+            }
+            RunState::ShowContainer { container, mode } => {
+                let result = gui::show_container(self, ctx, container, mode);
+                match result.0 {
+                    gui::ContainerResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::ContainerResult::NoResponse => {}
+                    gui::ContainerResult::SwitchMode => {
+                        newrunstate = RunState::ShowContainer {
+                            container,
+                            mode: match mode {
+                                ContainerMode::Take => ContainerMode::Put,
+                                ContainerMode::Put => ContainerMode::Take,
+                            },
+                        }
+                    }
+                    gui::ContainerResult::Take => {
+                        let item = container_items(&self.ecs, container)
+                            .iter()
+                            .position(|item| *item == result.1.unwrap())
+                            .unwrap();
+                        newrunstate = match container_pos(&self.ecs, container) {
+                            Some(pos) => {
+                                let command = PlayerCommand::TakeItem {
+                                    container: pos,
+                                    item,
+                                };
+                                self.perform(replay::ReplayInput::Command(command), newrunstate)
+                            }
+                            None => RunState::AwaitingInput,
+                        };
+                    }
+                    gui::ContainerResult::Put => {
+                        let item = backpack_index(&self.ecs, result.1.unwrap());
+                        newrunstate = match container_pos(&self.ecs, container) {
+                            Some(pos) => {
+                                let command = PlayerCommand::PutItem {
+                                    container: pos,
+                                    item,
+                                };
+                                self.perform(replay::ReplayInput::Command(command), newrunstate)
+                            }
+                            None => RunState::AwaitingInput,
+                        };
+                    }
+                }
+            }
+            // Notes don't change the game, so they aren't recorded
+            RunState::ShowAnnotateMenu => {
+                let result = gui::show_annotate_menu(self, ctx);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        let item = result.1.unwrap();
+                        self.note_entry = gui::get_item_note(&self.ecs, item).text;
+                        newrunstate = RunState::AnnotateItem { item };
+                    }
+                }
+            }
+            RunState::AnnotateItem { item } => match gui::annotate_item(self, ctx, item) {
+                gui::AnnotateResult::NoResponse => {}
+                gui::AnnotateResult::Cancel => newrunstate = RunState::AwaitingInput,
+                gui::AnnotateResult::Done => {
+                    let text = std::mem::take(&mut self.note_entry);
+                    annotate_item(&mut self.ecs, item, &text);
+                    newrunstate = RunState::AwaitingInput;
+                }
+            },
+            RunState::ShowCharacterSheet => {
+                if gui::show_character_sheet(&self.ecs, ctx) == gui::CharacterSheetResult::Close {
+                    newrunstate = RunState::AwaitingInput;
+                }
+            }
+            RunState::ShowCheatMenu => {
+                let result = gui::show_cheat_mode(self, ctx);
+                match result {
+                    gui::CheatMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::CheatMenuResult::NoResponse => {}
+                    cheat => {
+                        newrunstate = self.perform(replay::ReplayInput::Cheat(cheat), newrunstate)
+                    }
+                }
+            }
+        }
+
+        {
+            let mut runwriter = self.ecs.write_resource::<RunState>();
+            *runwriter = newrunstate;
+        }
+        damage_system::delete_the_dead(&mut self.ecs);
+    }
+}
+
+impl State {
+    fn run_systems(&mut self) {
+        let mut vis = VisibilitySystem {};
+        vis.run_now(&self.ecs);
+
+        let mut mapindex = MapIndexingSystem {};
+        mapindex.run_now(&self.ecs);
+
+        let mut encumbrance = ai::EncumbranceSystem {};
+        encumbrance.run_now(&self.ecs);
+
+        let mut initiative = ai::InitiativeSystem {};
+        initiative.run_now(&self.ecs);
+
+        let mut turn_status = ai::TurnStatusSystem {};
+        turn_status.run_now(&self.ecs);
+
+        let mut quipper = ai::QuipSystem {};
+        quipper.run_now(&self.ecs);
+
+        let mut adjacency = ai::AdjacentAI {};
+        adjacency.run_now(&self.ecs);
+
+        let mut visible = ai::VisibleAI {};
+        visible.run_now(&self.ecs);
+
+        let mut approach = ai::ApproachAI {};
+        approach.run_now(&self.ecs);
+
+        let mut flee = ai::FleeAI {};
+        flee.run_now(&self.ecs);
+
+        let mut default_move = ai::DefaultMoveAI {};
+        default_move.run_now(&self.ecs);
+
+        let mut chase = ai::ChaseAI {};
+        chase.run_now(&self.ecs);
+
+        let mut melee = MeleeCombatSystem {};
+        melee.run_now(&self.ecs);
+        let mut ranged = RangedCombatSystem {};
+        ranged.run_now(&self.ecs);
+        let mut spells = SpellCastSystem {};
+        spells.run_now(&self.ecs);
+        let mut damage = DamageSystem {};
+        damage.run_now(&self.ecs);
+
+        let mut moving = movement_system::MovementSystem {};
+        moving.run_now(&self.ecs);
+
+        let mut triggers = TriggerSystem {};
+        triggers.run_now(&self.ecs);
+
+        let mut pickup = ItemCollectionSystem {};
+        pickup.run_now(&self.ecs);
+
+        let mut item_id = inventory_system::ItemIdentificationSystem {};
+        item_id.run_now(&self.ecs);
+
+        let mut potions = ItemUseSystem {};
+        potions.run_now(&self.ecs);
+
+        let mut drop_items = ItemDropSystem {};
+        drop_items.run_now(&self.ecs);
+
+        let mut item_remove = ItemRemoveSystem {};
+        item_remove.run_now(&self.ecs);
+
+        let mut hunger = HungerSystem {};
+        hunger.run_now(&self.ecs);
+
+        let mut regeneration = RegenerationSystem {};
+        regeneration.run_now(&self.ecs);
+
+        effects::run_effects_queue(&mut self.ecs);
+
+        let mut particles = particle_system::ParticleSpawnSystem {};
+        particles.run_now(&self.ecs);
+
+        let mut lighting = LightingSystem {};
+        lighting.run_now(&self.ecs);
+
+        self.ecs.maintain();
+    }
+}
+
+impl State {
+    /// Advances any state that needs neither the player nor a screen, returning the state
+    /// to move to next. Interactive states are returned unchanged.
+    fn advance(&mut self, runstate: RunState) -> RunState {
+        match runstate {
+            RunState::PreRun => {
+                self.run_systems();
+                self.ecs.maintain();
+                RunState::AwaitingInput
+            }
+            RunState::Ticking => {
+                let mut newrunstate = runstate;
+                while newrunstate == RunState::Ticking {
+                    self.run_systems();
+                    self.ecs.maintain();
+                    match *self.ecs.fetch::<RunState>() {
+                        RunState::AwaitingInput if has_points_to_spend(&self.ecs) => {
+                            newrunstate = RunState::LevelUp
+                        }
+                        RunState::AwaitingInput => newrunstate = RunState::AwaitingInput,
+                        RunState::MagicMapReveal { .. } => {
+                            newrunstate = RunState::MagicMapReveal { row: 0 }
+                        }
+                        RunState::TownPortal => newrunstate = RunState::TownPortal,
+                        RunState::TeleportingToOtherLevel { x, y, depth } => {
+                            newrunstate = RunState::TeleportingToOtherLevel { x, y, depth }
+                        }
+                        _ => newrunstate = RunState::Ticking,
+                    }
+                }
+                newrunstate
+            }
+            RunState::NextLevel => {
+                self.goto_level(1);
+                self.mapgen_next_state = Some(RunState::PreRun);
+                RunState::MapGeneration
+            }
+            RunState::PreviousLevel => {
+                self.goto_level(-1);
+                self.mapgen_next_state = Some(RunState::PreRun);
+                RunState::MapGeneration
+            }
+            RunState::MagicMapReveal { row } => {
+                let mut map = self.ecs.fetch_mut::<Map>();
+                for x in 0..map.width {
+                    let idx = map.xy_idx(x, row);
+                    map.revealed_tiles[idx] = true;
+                }
+                if row == map.height - 1 {
+                    RunState::Ticking
+                } else {
+                    RunState::MagicMapReveal { row: row + 1 }
+                }
+            }
+            RunState::TownPortal => {
+                // spawn the portal
+                spawner::spawn_town_portal(&mut self.ecs);
+
+                // Transition
+                let map_depth = self.ecs.fetch::<Map>().depth;
+                let destination_offset = 0 - (map_depth - 1);
+                self.goto_level(destination_offset);
+                self.mapgen_next_state = Some(RunState::PreRun);
+                RunState::MapGeneration
+            }
+            RunState::TeleportingToOtherLevel { x, y, depth } => {
+                self.goto_level(depth - 1);
+                let player_entity = *self.ecs.fetch::<Entity>();
+                if let Some(pos) = self.ecs.write_storage::<Position>().get_mut(player_entity) {
+                    pos.x = x;
+                    pos.y = y;
+                }
+                let mut player_pos = self.ecs.fetch_mut::<rltk::Point>();
+                player_pos.x = x;
+                player_pos.y = y;
+                self.mapgen_next_state = Some(RunState::PreRun);
+                RunState::MapGeneration
+            }
+            _ => runstate,
+        }
+    }
+
+    fn goto_level(&mut self, offset: i32) {
+        freeze_level_entities(&mut self.ecs);
+
+        // Build a new map and place the player
+        let current_depth = self.ecs.fetch::<Map>().depth;
+        self.generate_world_map(current_depth + offset, offset);
+
+        // Notify the player
+        self.ecs
+            .fetch_mut::<gamelog::GameLog>()
+            .entries
+            .push("You change level".to_string());
+
+        if let Some(slot) = &self.save_slot {
+            saveload_system::save_game(&mut self.ecs, slot);
+        }
+    }
+
+    /// Loads a save slot from the load menu, returning the state to carry on in.
+    fn load_slot(&mut self, slot: String) -> RunState {
+        // A replay can only start from a new game
+        self.recorder = None;
+        match saveload_system::load_game(&mut self.ecs, &slot) {
+            Ok(()) => {
+                self.load_error = None;
+                self.save_slot = Some(slot);
+                if has_points_to_spend(&self.ecs) {
+                    RunState::LevelUp
+                } else {
+                    RunState::AwaitingInput
+                }
+            }
+            Err(e) => {
+                self.load_error = Some(e);
+                let player = *self.ecs.fetch::<Entity>();
+                if self.ecs.is_alive(player) {
+                    RunState::MainMenu {
+                        menu_selection: gui::MainMenuSelection::LoadGame,
+                    }
+                } else {
+                    // It failed after the waiting world was cleared
+                    self.new_run(random_seed());
+                    self.mapgen_next_state = Some(RunState::MainMenu {
+                        menu_selection: gui::MainMenuSelection::LoadGame,
+                    });
+                    RunState::MapGeneration
+                }
+            }
+        }
+    }
+
+    /// Carries out something the player chose, recording it if a replay is being recorded.
+    /// Live play and replays both come through here.
+    fn perform(&mut self, input: replay::ReplayInput, runstate: RunState) -> RunState {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(&input);
+        }
+
+        match input {
+            replay::ReplayInput::Command(command) => apply_command(&mut self.ecs, command),
+            replay::ReplayInput::Buy { tag, price } => {
+                buy_item(&mut self.ecs, &tag, price);
+                runstate
+            }
+            replay::ReplayInput::Sell { item } => {
+                if let Some(item) = backpack_items(&self.ecs).get(item) {
+                    sell_item(&mut self.ecs, *item);
+                }
+                runstate
+            }
+            replay::ReplayInput::LiftCurse { item, price } => {
+                if let Some(item) = equipped_items(&self.ecs).get(item) {
+                    lift_curse(&mut self.ecs, *item, price);
+                }
+                runstate
+            }
+            replay::ReplayInput::Recharge { item, price } => {
+                let exploded = match backpack_items(&self.ecs).get(item) {
+                    Some(item) => pay_for_recharge(&mut self.ecs, *item, price),
+                    None => false,
+                };
+                // The blast lands as time moves on, which ends the shopping
+                if exploded {
+                    RunState::Ticking
+                } else {
+                    runstate
+                }
+            }
+            replay::ReplayInput::Cheat(cheat) => self.apply_cheat(cheat),
+        }
+    }
+
+    /// Feeds the next recorded input in place of the player. Once the replay runs out, the
+    /// player takes over from where it stopped.
+    fn play_back(&mut self, runstate: RunState) -> RunState {
+        let next = self
+            .playback
+            .as_mut()
+            .and_then(|replay| replay.next_input());
+        match next {
+            Some(input) => self.perform(input, runstate),
+            None => {
+                self.playback = None;
+                let mut log = self.ecs.fetch_mut::<gamelog::GameLog>();
+                log.entries
+                    .push("The replay has ended; you are in control.".to_string());
+                RunState::AwaitingInput
+            }
+        }
+    }
+
+    fn apply_cheat(&mut self, cheat: gui::CheatMenuResult) -> RunState {
+        match cheat {
+            gui::CheatMenuResult::NoResponse => RunState::ShowCheatMenu,
+            gui::CheatMenuResult::Cancel => RunState::AwaitingInput,
+            gui::CheatMenuResult::TeleportToExit => {
+                self.goto_level(1);
+                self.mapgen_next_state = Some(RunState::PreRun);
+                RunState::MapGeneration
+            }
+            gui::CheatMenuResult::Heal => {
+                let player = self.ecs.fetch::<Entity>();
+                let mut pools = self.ecs.write_storage::<Pools>();
+                let player_pools = pools.get_mut(*player).unwrap();
+                player_pools.hit_points.current = player_pools.hit_points.max;
+                RunState::AwaitingInput
+            }
+            gui::CheatMenuResult::Reveal => {
+                let mut map = self.ecs.fetch_mut::<Map>();
+                for v in map.revealed_tiles.iter_mut() {
+                    *v = true;
+                }
+                RunState::AwaitingInput
+            }
+            gui::CheatMenuResult::GodMode => {
+                let player = self.ecs.fetch::<Entity>();
+                let mut pools = self.ecs.write_storage::<Pools>();
+                let player_pools = pools.get_mut(*player).unwrap();
+                player_pools.god_mode = true;
+                RunState::AwaitingInput
+            }
+            gui::CheatMenuResult::ReloadRaws => {
+                let mut log = self.ecs.fetch_mut::<gamelog::GameLog>();
+                match raws::reload_raws() {
+                    Ok(()) => log.entries.push("The raws have been reloaded.".to_string()),
+                    Err(errors) => {
+                        for error in errors.iter() {
+                            rltk::console::log(error.to_string());
+                        }
+                        log.entries
+                            .push(format!("The old raws are kept: {}", errors[0]));
+                        log.entries.push(format!(
+                            "The raws have {} problem(s), listed on the console.",
+                            errors.len()
+                        ));
+                    }
+                }
+                RunState::AwaitingInput
+            }
+        }
+    }
+
+    fn game_over_cleanup(&mut self) {
+        // Death is permanent
+        if let Some(slot) = self.save_slot.take() {
+            saveload_system::delete_save(&slot);
+        }
+        self.recorder = None;
+        self.playback = None;
+        self.new_run(random_seed());
+    }
+
+    /// Throws away the current world and builds the first level of a new run from `seed`.
+    fn new_run(&mut self, seed: u64) {
+        // Delete everything
+        let mut to_delete = Vec::new();
+        for entity in self.ecs.entities().join() {
+            to_delete.push(entity);
+        }
+        for del in to_delete.iter() {
+            self.ecs.delete_entity(*del).expect("Deletion failed");
+        }
+
+        // Replace the world maps and the gameplay RNG
+        self.seed_entry = seed.to_string();
+        let dungeon_master = map::MasterDungeonMap::new(seed);
+        self.ecs.insert(rltk::RandomNumberGenerator::seeded(
+            dungeon_master.gameplay_seed(),
+        ));
+        self.ecs.insert(dungeon_master);
+        self.ecs.insert(saveload_system::PlayTime::default());
+        self.ecs.insert(effects::EffectQueue::default());
+
+        // Spawn a new player
+        {
+            let player_entity = spawner::player(&mut self.ecs, 0, 0);
+            let mut player_entity_writer = self.ecs.write_resource::<Entity>();
+            *player_entity_writer = player_entity;
+        }
+
+        // Build a new map and place the player
+        self.generate_world_map(1, 0);
+    }
+
+    fn generate_world_map(&mut self, new_depth: i32, offset: i32) {
+        self.mapgen_index = 0;
+        self.mapgen_timer = 0.0;
+        self.mapgen_history.clear();
+
+        let map_building_info = map::level_transition(&mut self.ecs, new_depth, offset);
+        if let Some(history) = map_building_info {
+            self.mapgen_history = history;
+        } else {
+            map::thaw_level_entities(&mut self.ecs);
+        }
+    }
+}
+
+/// Picks a run seed when the player didn't ask for one.
+pub fn random_seed() -> u64 {
+    rltk::RandomNumberGenerator::new().next_u64()
+}
+
+impl State {
+    /// Builds a fresh world with every component registered, the raws loaded and the first
+    /// level queued for generation. The main menu is shown once that level is ready.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> State {
+        State::with_seed(random_seed())
+    }
+
+    /// Plays back a recorded run from its seed, skipping the main menu.
+    pub fn replaying(path: &str) -> Result<State, String> {
+        let replay = replay::Replay::load(path)?;
+        let mut gs = State::with_seed(replay.seed);
+        gs.playback = Some(replay);
+        gs.mapgen_next_state = Some(RunState::PreRun);
+        Ok(gs)
+    }
+
+    /// Saves are written in `encoding` from now on, until another is picked on the save
+    /// menu. Either kind loads regardless.
+    pub fn set_save_encoding(&mut self, encoding: saveload_system::SaveEncoding) {
+        self.ecs.insert(encoding);
+    }
+
+    /// As `new`, but the run is generated from `seed`: the same seed and the same inputs
+    /// always give the same game.
+    pub fn with_seed(seed: u64) -> State {
+        let mut gs = State {
+            ecs: World::new(),
+            mapgen_next_state: Some(RunState::MainMenu {
+                menu_selection: gui::MainMenuSelection::NewGame,
+            }),
+            mapgen_history: Vec::new(),
+            mapgen_index: 0,
+            mapgen_timer: 0.0,
+            seed_entry: seed.to_string(),
+            note_entry: String::new(),
+            load_error: None,
+            save_slot: None,
+            recorder: None,
+            playback: None,
+        };
+        gs.ecs.register::<Position>();
+        gs.ecs.register::<Renderable>();
+        gs.ecs.register::<Player>();
+        gs.ecs.register::<Viewshed>();
+        gs.ecs.register::<Name>();
+        gs.ecs.register::<BlocksTile>();
+        gs.ecs.register::<WantsToMelee>();
+        gs.ecs.register::<SufferDamage>();
+        gs.ecs.register::<Item>();
+        gs.ecs.register::<ProvidesHealing>();
+        gs.ecs.register::<InBackpack>();
+        gs.ecs.register::<WantsToPickupItem>();
+        gs.ecs.register::<WantsToUseItem>();
+        gs.ecs.register::<WantsToDropItem>();
+        gs.ecs.register::<Ranged>();
+        gs.ecs.register::<InflictsDamage>();
+        gs.ecs.register::<Consumable>();
+        gs.ecs.register::<AreaOfEffect>();
+        gs.ecs.register::<SimpleMarker<SerializeMe>>();
+        gs.ecs.register::<Equippable>();
+        gs.ecs.register::<Equipped>();
+        gs.ecs.register::<MeleeWeapon>();
+        gs.ecs.register::<Wearable>();
+        gs.ecs.register::<WantsToRemoveItem>();
+        gs.ecs.register::<ParticleLifetime>();
+        gs.ecs.register::<SpawnParticleBurst>();
+        gs.ecs.register::<SpawnParticleLine>();
+        gs.ecs.register::<HungerClock>();
+        gs.ecs.register::<ProvidesFood>();
+        gs.ecs.register::<MagicMapper>();
+        gs.ecs.register::<Hidden>();
+        gs.ecs.register::<EntryTrigger>();
+        gs.ecs.register::<EntityMoved>();
+        gs.ecs.register::<SingleActivation>();
+        gs.ecs.register::<BlocksVisibility>();
+        gs.ecs.register::<Door>();
+        gs.ecs.register::<Quips>();
+        gs.ecs.register::<Attributes>();
+        gs.ecs.register::<Skills>();
+        gs.ecs.register::<Pools>();
+        gs.ecs.register::<NaturalAttackDefense>();
+        gs.ecs.register::<LootTable>();
+        gs.ecs.register::<OtherLevelPosition>();
+        gs.ecs.register::<LightSource>();
+        gs.ecs.register::<Initiative>();
+        gs.ecs.register::<MyTurn>();
+        gs.ecs.register::<Faction>();
+        gs.ecs.register::<WantsToApproach>();
+        gs.ecs.register::<WantsToFlee>();
+        gs.ecs.register::<MoveMode>();
+        gs.ecs.register::<Chasing>();
+        gs.ecs.register::<EquipmentChanged>();
+        gs.ecs.register::<Vendor>();
+        gs.ecs.register::<TownPortal>();
+        gs.ecs.register::<TeleportTo>();
+        gs.ecs.register::<ApplyMove>();
+        gs.ecs.register::<ApplyTeleport>();
+        gs.ecs.register::<MagicItem>();
+        gs.ecs.register::<ObfuscatedName>();
+        gs.ecs.register::<IdentifiedItem>();
+        gs.ecs.register::<RangedWeapon>();
+        gs.ecs.register::<Ammunition>();
+        gs.ecs.register::<WantsToShoot>();
+        gs.ecs.register::<KnownSpells>();
+        gs.ecs.register::<SpellTemplate>();
+        gs.ecs.register::<WantsToCastSpell>();
+        gs.ecs.register::<TeachesSpell>();
+        gs.ecs.register::<StatusEffects>();
+        gs.ecs.register::<InflictsStatus>();
+        gs.ecs.register::<RegenerationBonus>();
+        gs.ecs.register::<LevelUpPoints>();
+        gs.ecs.register::<AttributeBonus>();
+        gs.ecs.register::<SkillBonus>();
+        gs.ecs.register::<CursedItem>();
+        gs.ecs.register::<ProvidesRemoveCurse>();
+        gs.ecs.register::<ProvidesIdentification>();
+        gs.ecs.register::<Charges>();
+        gs.ecs.register::<ProvidesRecharge>();
+        gs.ecs.register::<Recharger>();
+        gs.ecs.register::<Stackable>();
+        gs.ecs.register::<Container>();
+        gs.ecs.register::<Locked>();
+        gs.ecs.register::<Trapped>();
+        gs.ecs.register::<Temple>();
+
+        gs.ecs.insert(particle_system::ParticleBuilder::new());
+        gs.ecs.insert(effects::EffectQueue::default());
+        gs.ecs.insert(SimpleMarkerAllocator::<SerializeMe>::new());
+        gs.ecs.insert(saveload_system::PlayTime::default());
+
+        let mod_problems = raws::load_raws();
+
+        let dungeon_master = map::MasterDungeonMap::new(seed);
+        gs.ecs.insert(rltk::RandomNumberGenerator::seeded(
+            dungeon_master.gameplay_seed(),
+        ));
+        gs.ecs.insert(dungeon_master);
+        gs.ecs.insert(Map::new(1, 64, 64, "New Map"));
+        gs.ecs.insert(Point::new(0, 0));
+
+        let player_entity = spawner::player(&mut gs.ecs, 0, 0);
+        gs.ecs.insert(player_entity);
+
+        gs.ecs.insert(rex_assets::RexAssets::new());
+        gs.ecs.insert(player_entity);
+        gs.ecs.insert(RunState::MapGeneration);
+        gs.ecs.insert(gamelog::GameLog {
+            entries: vec!["Welcome to Rusty Roguelike".to_string()],
+        });
+        if !mod_problems.is_empty() {
+            for problem in mod_problems.iter() {
+                rltk::console::log(problem.to_string());
+            }
+            gs.ecs.fetch_mut::<gamelog::GameLog>().entries.push(format!(
+                "Mods with {} problem(s) were skipped, listed on the console.",
+                mod_problems.len()
+            ));
+        }
+
+        gs.generate_world_map(1, 0);
+
+        gs
+    }
+}
@@ -7,22 +7,64 @@ use std::collections::{HashMap, HashSet};
 
 #[derive(Serialize, Deserialize, Default, Clone)]
 pub struct MasterDungeonMap {
+    /// The run seed; every level and the gameplay RNG are derived from it
+    #[serde(default)]
+    pub seed: u64,
     maps: HashMap<i32, Map>,
     pub identified_items: HashSet<String>,
     pub scroll_mappings: HashMap<String, String>,
     pub potion_mappings: HashMap<String, String>,
+    /// What the player has found out about kinds of item they haven't identified, by the
+    /// items' real names
+    #[serde(default)]
+    pub item_notes: HashMap<String, ItemNote>,
+}
+
+/// Hints about an unidentified kind of item, shown after its obfuscated name.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct ItemNote {
+    /// The player has used one and nothing obvious happened
+    pub tried: bool,
+    /// What a vendor last paid for one
+    pub sold_for: Option<f32>,
+    /// Whatever the player wrote about it
+    pub text: String,
+}
+
+impl ItemNote {
+    /// The hints as a suffix for the item's name, e.g. `{tried; sold for 48 gp}`, or
+    /// `None` if nothing is known.
+    pub fn describe(&self) -> Option<String> {
+        let mut hints = Vec::new();
+        if self.tried {
+            hints.push("tried".to_string());
+        }
+        if let Some(price) = self.sold_for {
+            hints.push(format!("sold for {:.0} gp", price));
+        }
+        if !self.text.is_empty() {
+            hints.push(self.text.clone());
+        }
+        if hints.is_empty() {
+            None
+        } else {
+            Some(format!("{{{}}}", hints.join("; ")))
+        }
+    }
 }
 
 impl MasterDungeonMap {
-    pub fn new() -> MasterDungeonMap {
+    pub fn new(seed: u64) -> MasterDungeonMap {
         let mut dungeon_master = MasterDungeonMap {
+            seed,
             maps: HashMap::new(),
             identified_items: HashSet::new(),
             scroll_mappings: HashMap::new(),
             potion_mappings: HashMap::new(),
+            item_notes: HashMap::new(),
         };
 
-        let mut rng = rltk::RandomNumberGenerator::new();
+        let mut rng = rltk::RandomNumberGenerator::seeded(seed);
         for scroll_tag in crate::raws::get_scroll_tags().iter() {
             let masked_name = make_scroll_name(&mut rng);
             dungeon_master
@@ -41,6 +83,23 @@ impl MasterDungeonMap {
         dungeon_master
     }
 
+    /// The seed a level is built from, so a depth always regenerates identically for a
+    /// given run seed no matter what happened on the way there.
+    pub fn depth_seed(&self, depth: i32) -> u64 {
+        derive_seed(self.seed, depth as u64)
+    }
+
+    /// The seed for the gameplay RNG (combat, AI, loot rolls). Depths start at 1, so stream
+    /// 0 is free for it.
+    pub fn gameplay_seed(&self) -> u64 {
+        derive_seed(self.seed, 0)
+    }
+
+    /// The notes on an unidentified kind of item, started empty if there are none yet.
+    pub fn note_mut(&mut self, name: &str) -> &mut ItemNote {
+        self.item_notes.entry(name.to_string()).or_default()
+    }
+
     pub fn store_map(&mut self, map: &Map) {
         self.maps.insert(map.depth, map.clone());
     }
@@ -53,18 +112,31 @@ impl MasterDungeonMap {
             None
         }
     }
+
+    /// See `Map::reset_unsaved`; the remembered levels need it after a load too.
+    pub fn reset_unsaved(&mut self) {
+        for map in self.maps.values_mut() {
+            map.reset_unsaved();
+        }
+    }
+}
+
+/// Mixes a stream number into the run seed (SplitMix64) to get an independent sequence.
+fn derive_seed(seed: u64, stream: u64) -> u64 {
+    let mut z = seed.wrapping_add(stream.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
 }
 
 fn transition_to_new_map(ecs: &mut World, new_depth: i32) -> Vec<Map> {
-    let mut rng = ecs.write_resource::<rltk::RandomNumberGenerator>();
+    let seed = ecs
+        .read_resource::<MasterDungeonMap>()
+        .depth_seed(new_depth);
+    let mut rng = rltk::RandomNumberGenerator::seeded(seed);
+    rltk::console::log(format!("Depth: {}", new_depth));
     let mut builder = level_builder(new_depth, &mut rng, 80, 50);
     builder.build_map(&mut rng);
-    if new_depth > 1 {
-        if let Some(pos) = &builder.build_data.starting_position {
-            let up_idx = builder.build_data.map.xy_idx(pos.x, pos.y);
-            builder.build_data.map.tiles[up_idx] = super::TileType::UpStairs;
-        }
-    }
 
     let mapgen_history = builder.build_data.history.clone();
     let player_start;
@@ -80,7 +152,6 @@ fn transition_to_new_map(ecs: &mut World, new_depth: i32) -> Vec<Map> {
     }
 
     // Spawn the bad guys
-    drop(rng);
     builder.spawn_entities(ecs);
 
     // Place the player and update resources
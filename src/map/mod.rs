@@ -3,7 +3,9 @@ mod theme;
 mod tile_type;
 
 pub use self::{
-    dungeon::{freeze_level_entities, level_transition, thaw_level_entities, MasterDungeonMap},
+    dungeon::{
+        freeze_level_entities, level_transition, thaw_level_entities, ItemNote, MasterDungeonMap,
+    },
     theme::*,
     tile_type::{get_tile_cost, is_tile_opaque, is_tile_walkable, TileType},
 };
@@ -18,19 +20,23 @@ pub struct Map {
     pub width: i32,
     pub height: i32,
     pub revealed_tiles: Vec<bool>,
+    /// Worked out again every turn, so it isn't saved; see `reset_unsaved`
+    #[serde(skip)]
     pub visible_tiles: Vec<bool>,
     pub depth: i32,
     pub bloodstains: HashSet<usize>,
     pub view_blocked: HashSet<usize>,
     pub name: String,
     pub outdoors: bool,
+    #[serde(skip)]
     pub light: Vec<rltk::RGB>,
+    #[serde(skip)]
+    pub spatial: crate::spatial::SpatialMap,
 }
 
 impl Map {
     pub fn new<S: ToString>(new_depth: i32, width: i32, height: i32, name: S) -> Map {
         let map_tile_count = (width * height) as usize;
-        crate::spatial::set_size(map_tile_count);
         Map {
             tiles: vec![TileType::Wall; map_tile_count],
             width,
@@ -43,8 +49,19 @@ impl Map {
             name: name.to_string(),
             outdoors: true,
             light: vec![rltk::RGB::from_f32(0.0, 0.0, 0.0); map_tile_count],
+            spatial: crate::spatial::SpatialMap::new(map_tile_count),
         }
     }
+
+    /// Sizes the tile data that saves leave out to the map again, with nothing visible, no
+    /// light and nothing indexed, as a freshly loaded map needs before it can be drawn.
+    pub fn reset_unsaved(&mut self) {
+        let map_tile_count = (self.width * self.height) as usize;
+        self.visible_tiles = vec![false; map_tile_count];
+        self.light = vec![rltk::RGB::from_f32(0.0, 0.0, 0.0); map_tile_count];
+        self.spatial = crate::spatial::SpatialMap::new(map_tile_count);
+    }
+
     pub fn xy_idx(&self, x: i32, y: i32) -> usize {
         (y as usize * self.width as usize) + x as usize
     }
@@ -54,15 +71,15 @@ impl Map {
             return false;
         }
         let idx = self.xy_idx(x, y);
-        !crate::spatial::is_blocked(idx)
+        !self.spatial.is_blocked(idx)
     }
 
     pub fn populate_blocked(&mut self) {
-        crate::spatial::populate_blocked_from_map(self);
+        self.spatial.populate_blocked_from_map(&self.tiles);
     }
 
     pub fn clear_content_index(&mut self) {
-        crate::spatial::clear();
+        self.spatial.clear();
     }
 }
 
@@ -40,5 +40,10 @@ impl CullUnreachable {
                 }
             }
         }
+
+        // Drop anything that was going to spawn in a culled area
+        build_data
+            .spawn_list
+            .retain(|(idx, _name)| dijkstra_map.map[*idx] != f32::MAX);
     }
 }
@@ -21,6 +21,13 @@ impl DistantExit {
             .map
             .xy_idx(starting_position.x, starting_position.y);
 
+        // Any earlier exit is replaced, so this can also restore one that was built over
+        for tile in build_data.map.tiles.iter_mut() {
+            if *tile == TileType::DownStairs {
+                *tile = TileType::Floor;
+            }
+        }
+
         build_data.map.populate_blocked();
 
         let map_starts: Vec<usize> = vec![start_idx];
@@ -148,6 +148,7 @@ impl CaveTransition {
 
         // Build a BSP-based dungeon
         let mut builder = BuilderChain::new(5, build_data.width, build_data.height, "New Map");
+        builder.build_data.record_history = build_data.record_history;
         builder.start_with(BspDungeonBuilder::new());
         builder.with(RoomDrawer::new());
         builder.with(RoomSorter::new(RoomSort::RightMost));
@@ -174,13 +175,13 @@ impl CaveTransition {
         // Keep Voronoi spawn data from the left half of the map
         let width = build_data.map.width;
         build_data.spawn_list.retain(|spawn| {
-            let x = spawn.0 as i32 / width;
+            let x = spawn.0 as i32 % width;
             x < width / 2
         });
 
         // Keep room spawn data from the right half of the map
         for spawn in builder.build_data.spawn_list.iter() {
-            let x = spawn.0 as i32 / width;
+            let x = spawn.0 as i32 % width;
             if x > width / 2 {
                 build_data.spawn_list.push(spawn.clone());
             }
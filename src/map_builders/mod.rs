@@ -14,37 +14,39 @@ mod prefab_builder;
 mod room_based;
 mod simple;
 mod town;
+mod up_stairs_placement;
 mod voronoi;
 mod voronoi_spawning;
 mod waveform_collapse;
 
 use super::{spawner, Map, Position, Rect, TileType};
-use area_based::{AreaStartingPosition, CullUnreachable, XStart, YStart};
-use bsp_dungeon::BspDungeonBuilder;
-use bsp_interior::BspInteriorBuilder;
-use cellular_automata::CellularAutomataBuilder;
+pub use area_based::{AreaStartingPosition, CullUnreachable, XStart, YStart};
+pub use bsp_dungeon::BspDungeonBuilder;
+pub use bsp_interior::BspInteriorBuilder;
+pub use cellular_automata::CellularAutomataBuilder;
 use common::*;
-use distant_exit::DistantExit;
-use dla::DLABuilder;
-use door_placement::DoorPlacement;
-use drunkward::DrunkardsWalkBuilder;
+pub use distant_exit::DistantExit;
+pub use dla::DLABuilder;
+pub use door_placement::DoorPlacement;
+pub use drunkward::DrunkardsWalkBuilder;
 use forest::forest_builder;
 use limestone_cavern::{
     limestone_cavern_builder, limestone_deep_cavern_builder, limestone_transition_builder,
 };
-use maze::MazeBuilder;
-use prefab_builder::PrefabBuilder;
-use room_based::{
+pub use maze::MazeBuilder;
+pub use prefab_builder::{prefab_levels, prefab_sections, PrefabBuilder};
+pub use room_based::{
     BspCorridors, CorridorSpawner, DoglegCorridors, NearestCorridors, RoomBasedSpawner,
     RoomBasedStairs, RoomBasedStartingPosition, RoomCornerRounder, RoomDrawer, RoomExploder,
     RoomSort, RoomSorter, StraightLineCorridors,
 };
-use simple::SimpleMapBuilder;
+pub use simple::SimpleMapBuilder;
 use specs::prelude::*;
 use town::town_builder;
-use voronoi::VoronoiCellBuilder;
-use voronoi_spawning::VoronoiSpawning;
-use waveform_collapse::WaveformCollapseBuilder;
+pub use up_stairs_placement::UpStairsPlacement;
+pub use voronoi::VoronoiCellBuilder;
+pub use voronoi_spawning::VoronoiSpawning;
+pub use waveform_collapse::WaveformCollapseBuilder;
 
 pub struct BuilderMap {
     pub spawn_list: Vec<(usize, String)>,
@@ -53,22 +55,23 @@ pub struct BuilderMap {
     pub rooms: Option<Vec<Rect>>,
     pub corridors: Option<Vec<Vec<usize>>>,
     pub history: Vec<Map>,
+    /// Whether builders snapshot the map into `history`; on by default in debug builds
+    pub record_history: bool,
     pub width: i32,
     pub height: i32,
 }
 
 impl BuilderMap {
-    #[cfg(debug_assertions)]
     fn take_snapshot(&mut self) {
+        if !self.record_history {
+            return;
+        }
         let mut snapshot = self.map.clone();
         for v in snapshot.revealed_tiles.iter_mut() {
             *v = true;
         }
         self.history.push(snapshot);
     }
-
-    #[cfg(not(debug_assertions))]
-    fn take_snapshot(&mut self) {}
 }
 
 pub struct BuilderChain {
@@ -89,6 +92,7 @@ impl BuilderChain {
                 rooms: None,
                 corridors: None,
                 history: Vec::new(),
+                record_history: cfg!(debug_assertions),
                 width,
                 height,
             },
@@ -178,6 +182,14 @@ fn random_room_builder(rng: &mut rltk::RandomNumberGenerator, builder: &mut Buil
 
         builder.with(RoomDrawer::new());
 
+        // Shape the rooms before digging corridors, so rounding can't wall a corridor off
+        let modifier_roll = rng.roll_dice(1, 6);
+        match modifier_roll {
+            1 => builder.with(RoomExploder::new()),
+            2 => builder.with(RoomCornerRounder::new()),
+            _ => {}
+        }
+
         let corridor_roll = rng.roll_dice(1, 4);
         match corridor_roll {
             1 => builder.with(DoglegCorridors::new()),
@@ -190,13 +202,6 @@ fn random_room_builder(rng: &mut rltk::RandomNumberGenerator, builder: &mut Buil
         if corridor_spawn_roll == 1 {
             builder.with(CorridorSpawner::new());
         }
-
-        let modifier_roll = rng.roll_dice(1, 6);
-        match modifier_roll {
-            1 => builder.with(RoomExploder::new()),
-            2 => builder.with(RoomCornerRounder::new()),
-            _ => {}
-        }
     }
 
     let start_roll = rng.roll_dice(1, 2);
@@ -270,11 +275,11 @@ pub fn random_builder(
 
     if rng.roll_dice(1, 3) == 1 {
         builder.with(WaveformCollapseBuilder::new());
-        builder.with(CullUnreachable::new());
 
-        // Now set the start to a random starting area
+        // Now set the start to a random starting area, before culling from it
         let (start_x, start_y) = random_start_position(rng);
         builder.with(AreaStartingPosition::new(start_x, start_y));
+        builder.with(CullUnreachable::new());
 
         // Setup an exit and spawn mobs
         builder.with(VoronoiSpawning::new());
@@ -285,6 +290,8 @@ pub fn random_builder(
         builder.with(PrefabBuilder::sectional(
             prefab_builder::prefab_sections::UNDERGROUND_FORT,
         ));
+        // The fort can be stamped over the exit
+        builder.with(DistantExit::new());
     }
 
     builder.with(DoorPlacement::new());
@@ -299,13 +306,18 @@ pub fn level_builder(
     width: i32,
     height: i32,
 ) -> BuilderChain {
-    rltk::console::log(format!("Depth: {}", new_depth));
-    match new_depth {
+    let mut builder = match new_depth {
         1 => town_builder(new_depth, rng, width, height),
         2 => forest_builder(new_depth, rng, width, height),
         3 => limestone_cavern_builder(new_depth, rng, width, height),
         4 => limestone_deep_cavern_builder(new_depth, rng, width, height),
         5 => limestone_transition_builder(new_depth, rng, width, height),
         _ => random_builder(new_depth, rng, width, height),
+    };
+
+    if new_depth > 1 {
+        builder.with(UpStairsPlacement::new());
     }
+
+    builder
 }
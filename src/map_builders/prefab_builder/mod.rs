@@ -127,6 +127,12 @@ impl PrefabBuilder {
                 build_data.map.tiles[idx] = TileType::Floor;
                 build_data.spawn_list.push((idx, "Watch Fire".to_string()));
             }
+            '&' => {
+                build_data.map.tiles[idx] = TileType::Floor;
+                build_data
+                    .spawn_list
+                    .push((idx, "Locked Chest".to_string()));
+            }
             _ => {
                 rltk::console::log(format!("Unknown glyph loading map: {}", (ch as u8) as char));
             }
@@ -265,7 +271,7 @@ impl PrefabBuilder {
         }
 
         // Note that his is a place-holder and will be  moved out of this function
-        let master_vault_list = [TOTALLY_NOT_A_TRAP, CHECKERBOARD, SILLY_SMILE];
+        let master_vault_list = [TOTALLY_NOT_A_TRAP, CHECKERBOARD, SILLY_SMILE, GUARDED_CHEST];
 
         // Filter the vault list down to ones that are applicable to the current depth
         let mut possible_vaults: Vec<&PrefabRoom> = master_vault_list
@@ -341,12 +347,13 @@ impl PrefabBuilder {
                 let chunk_x = pos.x;
                 let chunk_y = pos.y;
 
-                let width = build_data.map.width; // The borrow checker really doesn't like it
-                let height = build_data.map.height; // when we access `self` inside the `retain`
+                // The borrow checker really doesn't like it when we access `build_data`
+                // inside the `retain`
+                let width = build_data.map.width;
                 build_data.spawn_list.retain(|e| {
                     let idx = e.0 as i32;
                     let x = idx % width;
-                    let y = idx / height;
+                    let y = idx / width;
                     x < chunk_x
                         || x >= chunk_x + vault.width as i32
                         || y < chunk_y
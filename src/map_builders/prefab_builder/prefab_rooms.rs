@@ -62,3 +62,21 @@ const CHECKERBOARD_MAP: &str = "
  ^# # 
       
 ";
+
+#[allow(dead_code)]
+pub const GUARDED_CHEST: PrefabRoom = PrefabRoom {
+    template: GUARDED_CHEST_MAP,
+    width: 5,
+    height: 5,
+    first_depth: 3,
+    last_depth: 100,
+};
+
+#[allow(dead_code)]
+const GUARDED_CHEST_MAP: &str = "
+     
+ ^g^ 
+ g&g 
+ ^g^ 
+     
+";
@@ -35,8 +35,11 @@ impl RoomDrawer {
                 let idx = build_data.map.xy_idx(x, y);
                 let distance =
                     rltk::DistanceAlg::Pythagoras.distance2d(center_point, rltk::Point::new(x, y));
-                if idx > 0
-                    && idx < ((build_data.map.width * build_data.map.height) - 1) as usize
+                // Keep the map's edge solid
+                if x > 0
+                    && x < build_data.map.width - 1
+                    && y > 0
+                    && y < build_data.map.height - 1
                     && distance <= radius
                 {
                     build_data.map.tiles[idx] = TileType::Floor;
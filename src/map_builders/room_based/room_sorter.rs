@@ -28,26 +28,18 @@ impl RoomSorter {
 
     fn sorter(&mut self, _rng: &mut RandomNumberGenerator, build_data: &mut BuilderMap) {
         match self.sort_by {
-            RoomSort::LeftMost => build_data
-                .rooms
-                .as_mut()
-                .unwrap()
-                .sort_by(|a, b| a.x1.cmp(&b.x1)),
+            RoomSort::LeftMost => build_data.rooms.as_mut().unwrap().sort_by_key(|a| a.x1),
             RoomSort::RightMost => build_data
                 .rooms
                 .as_mut()
                 .unwrap()
-                .sort_by(|a, b| b.x2.cmp(&a.x2)),
-            RoomSort::TopMost => build_data
-                .rooms
-                .as_mut()
-                .unwrap()
-                .sort_by(|a, b| a.y1.cmp(&b.y1)),
+                .sort_by_key(|a| std::cmp::Reverse(a.x2)),
+            RoomSort::TopMost => build_data.rooms.as_mut().unwrap().sort_by_key(|a| a.y1),
             RoomSort::BottomMost => build_data
                 .rooms
                 .as_mut()
                 .unwrap()
-                .sort_by(|a, b| b.y2.cmp(&a.y2)),
+                .sort_by_key(|a| std::cmp::Reverse(a.y2)),
             RoomSort::Central => {
                 let map_center =
                     rltk::Point::new(build_data.map.width / 2, build_data.map.height / 2);
@@ -1,5 +1,6 @@
 use super::{BuilderChain, BuilderMap, InitialMapBuilder, Position, TileType};
-use std::collections::HashSet;
+use crate::map::is_tile_walkable;
+use std::collections::BTreeSet;
 
 #[derive(Debug)]
 enum BuildingTag {
@@ -63,6 +64,7 @@ impl TownBuilder {
         let building_size = self.sort_buildings(&buildings);
         self.building_factory(rng, build_data, &buildings, &building_size);
 
+        self.seal_edges(build_data);
         self.spawn_dockers(build_data, rng);
         self.spawn_townsfolk(build_data, rng, &mut available_building_tiles);
 
@@ -81,6 +83,22 @@ impl TownBuilder {
         build_data.take_snapshot();
     }
 
+    fn seal_edges(&mut self, build_data: &mut BuilderMap) {
+        // Nothing may walk off the edge of the map
+        let (width, height) = (build_data.map.width, build_data.map.height);
+        for y in 0..height {
+            for x in 0..width {
+                if x == 0 || x == width - 1 || y == 0 || y == height - 1 {
+                    let idx = build_data.map.xy_idx(x, y);
+                    if is_tile_walkable(build_data.map.tiles[idx]) {
+                        build_data.map.tiles[idx] = TileType::Wall;
+                    }
+                }
+            }
+        }
+        build_data.take_snapshot();
+    }
+
     fn water_and_piers(
         &mut self,
         rng: &mut rltk::RandomNumberGenerator,
@@ -119,8 +137,8 @@ impl TownBuilder {
         &mut self,
         rng: &mut rltk::RandomNumberGenerator,
         build_data: &mut BuilderMap,
-    ) -> (HashSet<usize>, i32) {
-        let mut available_building_tiles: HashSet<usize> = HashSet::new();
+    ) -> (BTreeSet<usize>, i32) {
+        let mut available_building_tiles: BTreeSet<usize> = BTreeSet::new();
         let wall_gap_y = rng.roll_dice(1, build_data.map.height - 9) + 5;
         for y in 1..build_data.map.height - 2 {
             if !(y > wall_gap_y - 4 && y < wall_gap_y + 4) {
@@ -160,7 +178,7 @@ impl TownBuilder {
         &mut self,
         rng: &mut rltk::RandomNumberGenerator,
         build_data: &mut BuilderMap,
-        available_buildings_tiles: &mut HashSet<usize>,
+        available_buildings_tiles: &mut BTreeSet<usize>,
     ) -> Vec<(i32, i32, i32, i32)> {
         let mut buildings: Vec<(i32, i32, i32, i32)> = Vec::new();
         let mut n_buildings = 0;
@@ -317,7 +335,7 @@ impl TownBuilder {
             building_size.push((i, building.2 * building.3, BuildingTag::Unassigned));
         }
 
-        building_size.sort_by(|a, b| b.1.cmp(&a.1));
+        building_size.sort_by_key(|a| std::cmp::Reverse(a.1));
 
         building_size[0].2 = BuildingTag::Pub;
         building_size[1].2 = BuildingTag::Temple;
@@ -534,7 +552,7 @@ impl TownBuilder {
         &mut self,
         build_data: &mut BuilderMap,
         rng: &mut rltk::RandomNumberGenerator,
-        available_buildings_tiles: &mut HashSet<usize>,
+        available_buildings_tiles: &mut BTreeSet<usize>,
     ) {
         for idx in available_buildings_tiles.iter() {
             if rng.roll_dice(1, 10) == 1 {
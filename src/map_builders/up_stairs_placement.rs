@@ -0,0 +1,26 @@
+use super::{BuilderMap, MetaMapBuilder, TileType};
+use rltk::RandomNumberGenerator;
+
+/// Puts the up staircase under the starting position, so arriving from above leaves the
+/// player on a way back up.
+pub struct UpStairsPlacement {}
+
+impl MetaMapBuilder for UpStairsPlacement {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator, build_data: &mut BuilderMap) {
+        self.build(rng, build_data);
+    }
+}
+
+impl UpStairsPlacement {
+    pub fn new() -> Box<UpStairsPlacement> {
+        Box::new(UpStairsPlacement {})
+    }
+
+    fn build(&mut self, _rng: &mut RandomNumberGenerator, build_data: &mut BuilderMap) {
+        if let Some(pos) = &build_data.starting_position {
+            let up_idx = build_data.map.xy_idx(pos.x, pos.y);
+            build_data.map.tiles[up_idx] = TileType::UpStairs;
+            build_data.take_snapshot();
+        }
+    }
+}
@@ -71,8 +71,9 @@ pub fn build_patterns(
             "Pre de-duplication, there are {} patterns",
             patterns.len()
         ));
-        let set: HashSet<Vec<TileType>> = patterns.drain(..).collect();
-        patterns.extend(set);
+        // Keep the first of each pattern, in order, so a seed always gives the same map
+        let mut seen: HashSet<Vec<TileType>> = HashSet::new();
+        patterns.retain(|pattern| seen.insert(pattern.clone()));
         rltk::console::log(format!("There are {} patterns", patterns.len()));
     }
 
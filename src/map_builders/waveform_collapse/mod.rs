@@ -3,6 +3,7 @@ mod constraints;
 mod solver;
 
 use super::{BuilderMap, Map, MetaMapBuilder, TileType};
+use crate::map::is_tile_walkable;
 use common::MapChunk;
 use constraints::render_pattern_to_map;
 use constraints::{build_patterns, patterns_to_constraints};
@@ -29,15 +30,16 @@ impl WaveformCollapseBuilder {
 
         let patterns = build_patterns(&build_data.map, CHUNK_SIZE, true, true);
         let constraints = patterns_to_constraints(patterns, CHUNK_SIZE);
-        self.render_tile_gallery(&constraints, CHUNK_SIZE, build_data);
 
+        // The gallery draws over the map, so keep the original to fall back on
         let old_map = build_data.map.clone();
+        self.render_tile_gallery(&constraints, CHUNK_SIZE, build_data);
 
         build_data.map = Map::new(
-            build_data.map.depth,
+            old_map.depth,
             build_data.width,
             build_data.height,
-            &build_data.map.name,
+            &old_map.name,
         );
 
         let mut tries = 0;
@@ -61,9 +63,68 @@ impl WaveformCollapseBuilder {
             build_data.map = old_map;
         }
 
+        // Chunks run right up to the edge of the map; wall it off again
+        let (width, height) = (build_data.map.width, build_data.map.height);
+        for x in 0..width {
+            for y in [0, height - 1] {
+                let idx = build_data.map.xy_idx(x, y);
+                build_data.map.tiles[idx] = TileType::Wall;
+            }
+        }
+        for y in 0..height {
+            for x in [0, width - 1] {
+                let idx = build_data.map.xy_idx(x, y);
+                build_data.map.tiles[idx] = TileType::Wall;
+            }
+        }
+        build_data.take_snapshot();
+
+        self.keep_largest_region(build_data);
+        build_data.take_snapshot();
+
         build_data.spawn_list.clear();
     }
 
+    /// The solver doesn't know about connectivity and can leave pockets of floor cut off from
+    /// each other; wall off all but the biggest so the start can't land in a pocket.
+    fn keep_largest_region(&mut self, build_data: &mut BuilderMap) {
+        let map = &build_data.map;
+        let mut region: Vec<Option<usize>> = vec![None; map.tiles.len()];
+        let mut sizes: Vec<usize> = Vec::new();
+        for start in 0..map.tiles.len() {
+            if !is_tile_walkable(map.tiles[start]) || region[start].is_some() {
+                continue;
+            }
+            let id = sizes.len();
+            let mut size = 0;
+            let mut open = vec![start];
+            region[start] = Some(id);
+            while let Some(idx) = open.pop() {
+                size += 1;
+                let (x, y) = (idx as i32 % map.width, idx as i32 / map.width);
+                for (dx, dy) in (-1..=1).flat_map(|dx| (-1..=1).map(move |dy| (dx, dy))) {
+                    let (nx, ny) = (x + dx, y + dy);
+                    if nx < 0 || nx >= map.width || ny < 0 || ny >= map.height {
+                        continue;
+                    }
+                    let exit = map.xy_idx(nx, ny);
+                    if is_tile_walkable(map.tiles[exit]) && region[exit].is_none() {
+                        region[exit] = Some(id);
+                        open.push(exit);
+                    }
+                }
+            }
+            sizes.push(size);
+        }
+
+        let largest = (0..sizes.len()).max_by_key(|id| sizes[*id]);
+        for (idx, tile_region) in region.iter().enumerate() {
+            if tile_region.is_some() && *tile_region != largest {
+                build_data.map.tiles[idx] = TileType::Wall;
+            }
+        }
+    }
+
     fn render_tile_gallery(
         &mut self,
         constraints: &[MapChunk],
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::BTreeSet;
 
 use super::{common::MapChunk, Map};
 
@@ -100,7 +100,7 @@ impl Solver {
             *remain = (remain.0, neighbors_count);
         }
 
-        remain_copy.sort_by(|a, b| b.1.cmp(&a.1));
+        remain_copy.sort_by_key(|a| std::cmp::Reverse(a.1));
         self.remaining = remain_copy;
 
         // Pick a random chunk we haven't dealt with yet and get its index, remove from remaining list
@@ -182,7 +182,7 @@ impl Solver {
             }
         } else {
             // There are neighbors, so we try to be compatible with them
-            let mut options_to_check: HashSet<usize> = HashSet::new();
+            let mut options_to_check: BTreeSet<usize> = BTreeSet::new();
             for option in options.iter() {
                 for opt in option.iter() {
                     options_to_check.insert(*opt);
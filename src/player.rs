@@ -1,11 +1,23 @@
-use crate::{raws::Reaction, Attributes};
+use crate::{
+    effects::{add_effect, EffectType, Targets},
+    raws::Reaction,
+    Attributes,
+};
 
 use super::{
-    gamelog::GameLog, BlocksTile, BlocksVisibility, Door, EntityMoved, Faction, HungerClock,
-    HungerState, Item, Map, Player, Pools, Position, Renderable, RunState, State, TileType, Vendor,
-    Viewshed, WantsToMelee, WantsToPickupItem,
+    attribute_bonus, consume_one, equipped_ranged_weapon, find_ammo, gamelog::GameLog,
+    identify_kind, mana_at_level, player_hp_at_level, recharge_explosion_chance,
+    replay::ReplayInput, skill_cap, skill_level, split_one, stack_carried, Ammunition,
+    AttributeName, BlocksTile, BlocksVisibility, Charges, Consumable, Container, ContainerMode,
+    CursedItem, Door, EntityMoved, EquipmentChanged, Equipped, Faction, IdentifiedItem, InBackpack,
+    Item, KnownSpells, LevelUpPoints, Locked, MagicItem, Map, MasterDungeonMap, Name,
+    ObfuscatedName, Player, Pools, Position, ProvidesIdentification, ProvidesRecharge, Ranged,
+    RangedWeapon, Renderable, RunState, Skill, Skills, Stackable, State, Temple, TileType, Trapped,
+    Vendor, Viewshed, WantsToCastSpell, WantsToDropItem, WantsToMelee, WantsToPickupItem,
+    WantsToRemoveItem, WantsToShoot, WantsToUseItem, TRAINABLE_SKILLS, UNTRAINED_SKILL,
 };
 use rltk::{Point, Rltk, VirtualKeyCode};
+use serde::{Deserialize, Serialize};
 use specs::prelude::*;
 use std::cmp::{max, min};
 
@@ -15,7 +27,7 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState
     let mut viewsheds = ecs.write_storage::<Viewshed>();
     let entities = ecs.entities();
     let combat_stats = ecs.read_storage::<Attributes>();
-    let map = ecs.fetch::<Map>();
+    let mut map = ecs.fetch_mut::<Map>();
     let mut wants_to_melee = ecs.write_storage::<super::WantsToMelee>();
     let mut entity_moved = ecs.write_storage::<EntityMoved>();
     let mut doors = ecs.write_storage::<Door>();
@@ -24,6 +36,7 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState
     let mut rendarables = ecs.write_storage::<Renderable>();
     let factions = ecs.read_storage::<Faction>();
     let vendors = ecs.read_storage::<Vendor>();
+    let temples = ecs.read_storage::<Temple>();
 
     let mut result = RunState::AwaitingInput;
 
@@ -37,66 +50,70 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState
         }
         let destination_idx = map.xy_idx(pos.x + delta_x, pos.y + delta_y);
 
-        result = crate::spatial::for_each_tile_content_with_gamemode(
-            destination_idx,
-            |potential_target| {
-                if let Some(_vendor) = vendors.get(potential_target) {
-                    return Some(RunState::ShowVendor {
-                        vendor: potential_target,
-                        mode: crate::VendorMode::Sell,
-                    });
-                }
-                let mut hostile = true;
-                if combat_stats.get(potential_target).is_some() {
-                    if let Some(faction) = factions.get(potential_target) {
-                        let reaction = crate::raws::faction_reaction(
-                            &faction.name,
-                            "Player",
-                            &crate::raws::RAWS.lock().unwrap(),
-                        );
-                        if reaction != Reaction::Attack {
-                            hostile = false;
+        result =
+            map.spatial
+                .for_each_tile_content_with_gamemode(destination_idx, |potential_target| {
+                    if let Some(_vendor) = vendors.get(potential_target) {
+                        return Some(RunState::ShowVendor {
+                            vendor: potential_target,
+                            mode: crate::VendorMode::Sell,
+                        });
+                    }
+                    if temples.get(potential_target).is_some() {
+                        return Some(RunState::ShowTemple {
+                            priest: potential_target,
+                        });
+                    }
+                    let mut hostile = true;
+                    if combat_stats.get(potential_target).is_some() {
+                        if let Some(faction) = factions.get(potential_target) {
+                            let reaction = crate::raws::faction_reaction(
+                                &faction.name,
+                                "Player",
+                                &crate::raws::RAWS.lock().unwrap(),
+                            );
+                            if reaction != Reaction::Attack {
+                                hostile = false;
+                            }
                         }
                     }
-                }
-                if !hostile {
-                    swap_entities.push((potential_target, pos.x, pos.y));
-
-                    // Move the player
-                    pos.x = min(map.width - 1, max(0, pos.x + delta_x));
-                    pos.y = min(map.height - 1, max(0, pos.y + delta_y));
-                    entity_moved
-                        .insert(entity, EntityMoved {})
-                        .expect("Unable to insert marker");
-                    return Some(RunState::Ticking);
-                } else {
-                    let target = combat_stats.get(potential_target);
-                    if let Some(_target) = target {
-                        wants_to_melee
-                            .insert(
-                                entity,
-                                WantsToMelee {
-                                    target: potential_target,
-                                },
-                            )
-                            .expect("Add target failed");
+                    if !hostile {
+                        swap_entities.push((potential_target, pos.x, pos.y));
+
+                        // Move the player
+                        pos.x = min(map.width - 1, max(0, pos.x + delta_x));
+                        pos.y = min(map.height - 1, max(0, pos.y + delta_y));
+                        entity_moved
+                            .insert(entity, EntityMoved {})
+                            .expect("Unable to insert marker");
+                        return Some(RunState::Ticking);
+                    } else {
+                        let target = combat_stats.get(potential_target);
+                        if let Some(_target) = target {
+                            wants_to_melee
+                                .insert(
+                                    entity,
+                                    WantsToMelee {
+                                        target: potential_target,
+                                    },
+                                )
+                                .expect("Add target failed");
+                            return Some(RunState::Ticking);
+                        }
+                    }
+                    let door = doors.get_mut(potential_target);
+                    if let Some(door) = door {
+                        door.open = true;
+                        blocks_visibility.remove(potential_target);
+                        block_movement.remove(potential_target);
+                        let glyph = rendarables.get_mut(potential_target).unwrap();
+                        glyph.glyph = rltk::to_cp437('/');
+                        viewshed.dirty = true;
                         return Some(RunState::Ticking);
                     }
-                }
-                let door = doors.get_mut(potential_target);
-                if let Some(door) = door {
-                    door.open = true;
-                    blocks_visibility.remove(potential_target);
-                    block_movement.remove(potential_target);
-                    let glyph = rendarables.get_mut(potential_target).unwrap();
-                    glyph.glyph = rltk::to_cp437('/');
-                    viewshed.dirty = true;
-                    return Some(RunState::Ticking);
-                }
-                None
-            },
-        );
-        if !crate::spatial::is_blocked(destination_idx) {
+                    None
+                });
+        if !map.spatial.is_blocked(destination_idx) {
             let old_idx = map.xy_idx(pos.x, pos.y);
             pos.x = min(map.width - 1, max(0, pos.x + delta_x));
             pos.y = min(map.height - 1, max(0, pos.y + delta_y));
@@ -104,7 +121,7 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState
             entity_moved
                 .insert(entity, EntityMoved {})
                 .expect("Unable to insert marker");
-            crate::spatial::move_entity(entity, old_idx, new_idx);
+            map.spatial.move_entity(entity, old_idx, new_idx);
 
             viewshed.dirty = true;
             let mut player_pos = ecs.write_resource::<Point>();
@@ -130,7 +147,7 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState
             their_pos.x = swap.1;
             their_pos.y = swap.2;
             let new_idx = map.xy_idx(their_pos.x, their_pos.y);
-            crate::spatial::move_entity(swap.0, old_idx, new_idx);
+            map.spatial.move_entity(swap.0, old_idx, new_idx);
             result = RunState::Ticking;
         }
     }
@@ -199,153 +216,1060 @@ pub fn try_previous_level(ecs: &mut World) -> bool {
     }
 }
 
+/// An action the player can take on their turn, independent of how it was entered.
+/// Items are referred to by their index in the player's backpack (or equipment, for
+/// `RemoveItem`), in the same order the inventory menus list them, and spells by their
+/// index in the spells the player knows.
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum PlayerCommand {
+    Move {
+        delta_x: i32,
+        delta_y: i32,
+    },
+    PickUp,
+    Wait,
+    DescendStairs,
+    AscendStairs,
+    UseItem {
+        item: usize,
+        target: Option<Point>,
+    },
+    DropItem {
+        item: usize,
+    },
+    RemoveItem {
+        item: usize,
+    },
+    Fire {
+        target: Option<Point>,
+    },
+    CastSpell {
+        spell: usize,
+        target: Option<Point>,
+    },
+    /// Spends a level-up point on an attribute
+    RaiseAttribute {
+        attribute: AttributeName,
+    },
+    /// Spends a level-up point on a skill
+    RaiseSkill {
+        skill: Skill,
+    },
+    /// Reads the identify scroll at backpack index `scroll` on the item at `item`
+    Identify {
+        scroll: usize,
+        item: usize,
+    },
+    /// Reads the recharging scroll at backpack index `scroll` on the item at `item`
+    Recharge {
+        scroll: usize,
+        item: usize,
+    },
+    /// Opens the container the player can reach, picking its lock first if it has one
+    Open,
+    /// Takes the item at this index out of the container at `container`, if the player can
+    /// still reach it
+    TakeItem {
+        container: Point,
+        item: usize,
+    },
+    /// Puts the backpack item at this index in the container at `container`, if the player
+    /// can still reach it
+    PutItem {
+        container: Point,
+        item: usize,
+    },
+}
+
+fn hotkey_number(key: VirtualKeyCode) -> Option<i32> {
+    match key {
+        VirtualKeyCode::Key1 => Some(1),
+        VirtualKeyCode::Key2 => Some(2),
+        VirtualKeyCode::Key3 => Some(3),
+        VirtualKeyCode::Key4 => Some(4),
+        VirtualKeyCode::Key5 => Some(5),
+        VirtualKeyCode::Key6 => Some(6),
+        VirtualKeyCode::Key7 => Some(7),
+        VirtualKeyCode::Key8 => Some(8),
+        VirtualKeyCode::Key9 => Some(9),
+        _ => None,
+    }
+}
+
 pub fn player_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
-    // Hotkeys
-    if ctx.shift && ctx.key.is_some() {
-        let key: Option<i32> = match ctx.key.unwrap() {
-            VirtualKeyCode::Key1 => Some(1),
-            VirtualKeyCode::Key2 => Some(2),
-            VirtualKeyCode::Key3 => Some(3),
-            VirtualKeyCode::Key4 => Some(4),
-            VirtualKeyCode::Key5 => Some(5),
-            VirtualKeyCode::Key6 => Some(6),
-            VirtualKeyCode::Key7 => Some(7),
-            VirtualKeyCode::Key8 => Some(8),
-            VirtualKeyCode::Key9 => Some(9),
-            _ => None,
-        };
-        if let Some(key) = key {
-            return use_consumable_hotkey(gs, key - 1);
+    // Hotkeys: shift for consumables, control for spells
+    if ctx.shift || ctx.control {
+        if let Some(key) = ctx.key.and_then(hotkey_number) {
+            let command = if ctx.shift {
+                consumable_hotkey_command(gs, key - 1)
+            } else {
+                Some(PlayerCommand::CastSpell {
+                    spell: (key - 1) as usize,
+                    target: None,
+                })
+            };
+            return match command {
+                Some(command) => gs.perform(ReplayInput::Command(command), RunState::AwaitingInput),
+                None => RunState::AwaitingInput,
+            };
         }
     }
     // Player movement
-    match ctx.key {
+    let command = match ctx.key {
         None => return RunState::AwaitingInput, // Nothing happened
         Some(key) => match key {
             VirtualKeyCode::W | VirtualKeyCode::Up | VirtualKeyCode::Numpad8 => {
-                return try_move_player(0, -1, &mut gs.ecs)
-            }
-            VirtualKeyCode::Q | VirtualKeyCode::Numpad7 => {
-                return try_move_player(-1, -1, &mut gs.ecs)
+                PlayerCommand::Move {
+                    delta_x: 0,
+                    delta_y: -1,
+                }
             }
+            VirtualKeyCode::Q | VirtualKeyCode::Numpad7 => PlayerCommand::Move {
+                delta_x: -1,
+                delta_y: -1,
+            },
             VirtualKeyCode::A | VirtualKeyCode::Left | VirtualKeyCode::Numpad4 => {
-                return try_move_player(-1, 0, &mut gs.ecs)
-            }
-            VirtualKeyCode::E | VirtualKeyCode::Numpad9 => {
-                return try_move_player(1, -1, &mut gs.ecs)
+                PlayerCommand::Move {
+                    delta_x: -1,
+                    delta_y: 0,
+                }
             }
+            VirtualKeyCode::E | VirtualKeyCode::Numpad9 => PlayerCommand::Move {
+                delta_x: 1,
+                delta_y: -1,
+            },
             VirtualKeyCode::S | VirtualKeyCode::Down | VirtualKeyCode::Numpad2 => {
-                return try_move_player(0, 1, &mut gs.ecs)
-            }
-            VirtualKeyCode::Z | VirtualKeyCode::Numpad1 => {
-                return try_move_player(-1, 1, &mut gs.ecs)
+                PlayerCommand::Move {
+                    delta_x: 0,
+                    delta_y: 1,
+                }
             }
+            VirtualKeyCode::Z | VirtualKeyCode::Numpad1 => PlayerCommand::Move {
+                delta_x: -1,
+                delta_y: 1,
+            },
             VirtualKeyCode::D | VirtualKeyCode::Right | VirtualKeyCode::Numpad6 => {
-                return try_move_player(1, 0, &mut gs.ecs)
-            }
-            VirtualKeyCode::C | VirtualKeyCode::Numpad3 => {
-                return try_move_player(1, 1, &mut gs.ecs)
+                PlayerCommand::Move {
+                    delta_x: 1,
+                    delta_y: 0,
+                }
             }
-            VirtualKeyCode::G => get_item(&mut gs.ecs),
+            VirtualKeyCode::C | VirtualKeyCode::Numpad3 => PlayerCommand::Move {
+                delta_x: 1,
+                delta_y: 1,
+            },
+            VirtualKeyCode::G => PlayerCommand::PickUp,
+            VirtualKeyCode::Period => PlayerCommand::DescendStairs,
+            VirtualKeyCode::Comma => PlayerCommand::AscendStairs,
             VirtualKeyCode::B => return RunState::ShowInventory,
             VirtualKeyCode::V => return RunState::ShowDropItem,
             // Save and Quit
-            VirtualKeyCode::Escape => return RunState::SaveGame,
+            VirtualKeyCode::Escape => return RunState::ShowSaveMenu,
             // Cheating
             VirtualKeyCode::Backslash => return RunState::ShowCheatMenu,
-            VirtualKeyCode::Space => return skip_turn(&mut gs.ecs),
+            VirtualKeyCode::Space => PlayerCommand::Wait,
             VirtualKeyCode::R => return RunState::ShowRemoveItem,
+            VirtualKeyCode::F => PlayerCommand::Fire { target: None },
+            VirtualKeyCode::M => return RunState::ShowCastMenu,
+            VirtualKeyCode::P => return RunState::ShowCharacterSheet,
+            VirtualKeyCode::N => return RunState::ShowAnnotateMenu,
+            VirtualKeyCode::O => PlayerCommand::Open,
             _ => return RunState::AwaitingInput,
         },
+    };
+    gs.perform(ReplayInput::Command(command), RunState::AwaitingInput)
+}
+
+/// Carries out a player command against the world and returns the state to move to.
+/// Commands that refer to an item the player doesn't have are ignored.
+pub fn apply_command(ecs: &mut World, command: PlayerCommand) -> RunState {
+    match command {
+        PlayerCommand::Move { delta_x, delta_y } => try_move_player(delta_x, delta_y, ecs),
+        PlayerCommand::PickUp => {
+            get_item(ecs);
+            RunState::Ticking
+        }
+        // Resting is just letting a turn go by; everyone regenerates on their turn
+        PlayerCommand::Wait => RunState::Ticking,
+        PlayerCommand::DescendStairs => {
+            if try_next_level(ecs) {
+                RunState::NextLevel
+            } else {
+                RunState::AwaitingInput
+            }
+        }
+        PlayerCommand::AscendStairs => {
+            if try_previous_level(ecs) {
+                RunState::PreviousLevel
+            } else {
+                RunState::AwaitingInput
+            }
+        }
+        PlayerCommand::UseItem { item, target } => match backpack_items(ecs).get(item) {
+            Some(item) => use_item(ecs, *item, target),
+            None => RunState::AwaitingInput,
+        },
+        PlayerCommand::DropItem { item } => match backpack_items(ecs).get(item) {
+            Some(item) => drop_item(ecs, *item),
+            None => RunState::AwaitingInput,
+        },
+        PlayerCommand::RemoveItem { item } => match equipped_items(ecs).get(item) {
+            Some(item) => remove_item(ecs, *item),
+            None => RunState::AwaitingInput,
+        },
+        PlayerCommand::Fire { target } => fire(ecs, target),
+        PlayerCommand::CastSpell { spell, target } => match known_spells(ecs).get(spell) {
+            Some(spell) => cast_spell(ecs, spell, target),
+            None => RunState::AwaitingInput,
+        },
+        PlayerCommand::RaiseAttribute { attribute } => raise_attribute(ecs, attribute),
+        PlayerCommand::RaiseSkill { skill } => raise_skill(ecs, skill),
+        PlayerCommand::Identify { scroll, item } => {
+            let backpack = backpack_items(ecs);
+            match (backpack.get(scroll), backpack.get(item)) {
+                (Some(scroll), Some(item)) => identify_item(ecs, *scroll, *item),
+                _ => RunState::AwaitingInput,
+            }
+        }
+        PlayerCommand::Recharge { scroll, item } => {
+            let backpack = backpack_items(ecs);
+            match (backpack.get(scroll), backpack.get(item)) {
+                (Some(scroll), Some(item)) => recharge_with_scroll(ecs, *scroll, *item),
+                _ => RunState::AwaitingInput,
+            }
+        }
+        PlayerCommand::Open => open_container(ecs),
+        PlayerCommand::TakeItem { container, item } => match reachable_container_at(ecs, container)
+        {
+            Some(container) => match container_items(ecs, container).get(item) {
+                Some(item) => take_item(ecs, container, *item),
+                None => RunState::AwaitingInput,
+            },
+            None => RunState::AwaitingInput,
+        },
+        PlayerCommand::PutItem { container, item } => {
+            match (
+                reachable_container_at(ecs, container),
+                backpack_items(ecs).get(item),
+            ) {
+                (Some(container), Some(item)) => put_item(ecs, container, *item),
+                _ => RunState::AwaitingInput,
+            }
+        }
     }
-    RunState::Ticking
 }
 
-fn skip_turn(ecs: &mut World) -> RunState {
-    let player_entity = ecs.fetch::<Entity>();
-    let viewshed_components = ecs.read_storage::<Viewshed>();
-    let factions = ecs.read_storage::<Faction>();
+/// Whether the player has level-up points they could spend. Skill points are kept, but
+/// can't be spent, while every skill is at the cap for the player's level.
+pub fn has_points_to_spend(ecs: &World) -> bool {
+    let player_entity = *ecs.fetch::<Entity>();
+    let Some(points) = ecs
+        .read_storage::<LevelUpPoints>()
+        .get(player_entity)
+        .cloned()
+    else {
+        return false;
+    };
+    if points.attributes > 0 {
+        return true;
+    }
+    let cap = ecs
+        .read_storage::<Pools>()
+        .get(player_entity)
+        .map_or(0, |pools| skill_cap(pools.level));
+    points.skills > 0
+        && ecs
+            .read_storage::<Skills>()
+            .get(player_entity)
+            .is_some_and(|skills| {
+                TRAINABLE_SKILLS
+                    .iter()
+                    .any(|skill| skill_level(*skill, skills) < cap)
+            })
+}
 
-    let worldmap_resource = ecs.fetch::<Map>();
-
-    let mut can_heal = true;
-    let viewshed = viewshed_components.get(*player_entity).unwrap();
-    for tile in viewshed.visible_tiles.iter() {
-        let idx = worldmap_resource.xy_idx(tile.x, tile.y);
-        crate::spatial::for_each_tile_content(idx, |entity_id| {
-            let faction = factions.get(entity_id);
-            match faction {
-                None => {}
-                Some(faction) => {
-                    let reaction = crate::raws::faction_reaction(
-                        &faction.name,
-                        "Player",
-                        &crate::raws::RAWS.lock().unwrap(),
-                    );
-                    if reaction == Reaction::Attack {
-                        can_heal = false;
-                    }
-                }
-            }
-        })
+/// Stays on the level-up screen until there is nothing left to spend.
+fn level_up_state(ecs: &World) -> RunState {
+    if has_points_to_spend(ecs) {
+        RunState::LevelUp
+    } else {
+        RunState::AwaitingInput
     }
+}
 
-    let hunger_clocks = ecs.read_storage::<HungerClock>();
-    let hc = hunger_clocks.get(*player_entity);
-    if let Some(hc) = hc {
-        match hc.state {
-            HungerState::Hungry => can_heal = false,
-            HungerState::Starving => can_heal = false,
-            _ => {}
-        }
+/// Spends an attribute point, recalculating its bonus and whatever depends on it.
+pub fn raise_attribute(ecs: &mut World, attribute: AttributeName) -> RunState {
+    let player_entity = *ecs.fetch::<Entity>();
+    let has_point = ecs
+        .read_storage::<LevelUpPoints>()
+        .get(player_entity)
+        .is_some_and(|points| points.attributes > 0);
+    if !has_point {
+        return level_up_state(ecs);
     }
+    {
+        let mut points = ecs.write_storage::<LevelUpPoints>();
+        let mut attributes = ecs.write_storage::<Attributes>();
+        let mut pools = ecs.write_storage::<Pools>();
+        let (Some(player_points), Some(player_attributes), Some(player_pools)) = (
+            points.get_mut(player_entity),
+            attributes.get_mut(player_entity),
+            pools.get_mut(player_entity),
+        ) else {
+            return RunState::AwaitingInput;
+        };
+        player_points.attributes -= 1;
+
+        let raised = player_attributes.attribute_mut(attribute);
+        raised.base += 1;
+        raised.bonus = attribute_bonus(raised.base + raised.modifiers);
+        let value = raised.base;
+
+        let hp_max = player_hp_at_level(
+            player_attributes.fitness.base + player_attributes.fitness.modifiers,
+            player_pools.level,
+        );
+        player_pools.hit_points.current += hp_max - player_pools.hit_points.max;
+        player_pools.hit_points.max = hp_max;
+        let mana_max = mana_at_level(
+            player_attributes.intelligence.base + player_attributes.intelligence.modifiers,
+            player_pools.level,
+        );
+        player_pools.mana.current += mana_max - player_pools.mana.max;
+        player_pools.mana.max = mana_max;
 
-    if can_heal {
-        let mut health_components = ecs.write_storage::<Pools>();
-        let pools = health_components.get_mut(*player_entity).unwrap();
-        pools.hit_points.current = pools.hit_points.max.min(pools.hit_points.current + 1);
+        ecs.fetch_mut::<GameLog>()
+            .entries
+            .push(format!("Your {:?} rises to {}.", attribute, value));
     }
+    // Might changes how much can be carried
+    ecs.write_storage::<EquipmentChanged>()
+        .insert(player_entity, EquipmentChanged {})
+        .expect("Unable to insert");
+    remove_spent_points(ecs, player_entity);
+    level_up_state(ecs)
+}
 
-    RunState::Ticking
+/// Spends a skill point, up to the cap for the player's level.
+pub fn raise_skill(ecs: &mut World, skill: Skill) -> RunState {
+    let player_entity = *ecs.fetch::<Entity>();
+    let cap = ecs
+        .read_storage::<Pools>()
+        .get(player_entity)
+        .map_or(0, |pools| skill_cap(pools.level));
+    let level = ecs
+        .read_storage::<Skills>()
+        .get(player_entity)
+        .map_or(UNTRAINED_SKILL, |skills| skill_level(skill, skills));
+    let has_point = ecs
+        .read_storage::<LevelUpPoints>()
+        .get(player_entity)
+        .is_some_and(|points| points.skills > 0);
+    if !has_point || level >= cap {
+        return level_up_state(ecs);
+    }
+    {
+        let mut points = ecs.write_storage::<LevelUpPoints>();
+        let mut skills = ecs.write_storage::<Skills>();
+        let (Some(player_points), Some(player_skills)) =
+            (points.get_mut(player_entity), skills.get_mut(player_entity))
+        else {
+            return RunState::AwaitingInput;
+        };
+        player_points.skills -= 1;
+        player_skills.skills.insert(skill, level + 1);
+        player_skills.practice.remove(&skill);
+
+        ecs.fetch_mut::<GameLog>().entries.push(format!(
+            "Your {:?} skill rises to {}.",
+            skill,
+            level + 1
+        ));
+    }
+    remove_spent_points(ecs, player_entity);
+    level_up_state(ecs)
 }
 
-fn use_consumable_hotkey(gs: &mut State, key: i32) -> RunState {
-    use super::{Consumable, InBackpack, WantsToUseItem};
+fn remove_spent_points(ecs: &mut World, player_entity: Entity) {
+    let mut points = ecs.write_storage::<LevelUpPoints>();
+    if points
+        .get(player_entity)
+        .is_some_and(|points| points.attributes < 1 && points.skills < 1)
+    {
+        points.remove(player_entity);
+    }
+}
 
-    let consumable = gs.ecs.read_storage::<Consumable>();
-    let backpack = gs.ecs.read_storage::<InBackpack>();
-    let player_entity = gs.ecs.fetch::<Entity>();
-    let entities = gs.ecs.entities();
-    let mut carried_consumables = Vec::new();
-
-    for (entity, carried_by, _consumable) in (&entities, &backpack, &consumable).join() {
-        if carried_by.owner == *player_entity {
-            carried_consumables.push(entity);
+/// The spells the player knows, in cast menu order.
+pub fn known_spells(ecs: &World) -> Vec<String> {
+    ecs.read_storage::<KnownSpells>()
+        .get(*ecs.fetch::<Entity>())
+        .map_or(Vec::new(), |known| known.spells.clone())
+}
+
+/// Everything the player is carrying, in inventory menu order.
+pub fn backpack_items(ecs: &World) -> Vec<Entity> {
+    let player_entity = ecs.fetch::<Entity>();
+    let backpack = ecs.read_storage::<InBackpack>();
+    let entities = ecs.entities();
+
+    (&entities, &backpack)
+        .join()
+        .filter(|item| item.1.owner == *player_entity)
+        .map(|item| item.0)
+        .collect()
+}
+
+/// Everything the player has equipped, in remove item menu order.
+pub fn equipped_items(ecs: &World) -> Vec<Entity> {
+    let player_entity = ecs.fetch::<Entity>();
+    let equipped = ecs.read_storage::<Equipped>();
+    let entities = ecs.entities();
+
+    (&entities, &equipped)
+        .join()
+        .filter(|item| item.1.owner == *player_entity)
+        .map(|item| item.0)
+        .collect()
+}
+
+/// The magic items in the backpack the player doesn't know yet, in inventory menu order.
+pub fn unidentified_items(ecs: &World) -> Vec<Entity> {
+    let magic_items = ecs.read_storage::<MagicItem>();
+    let names = ecs.read_storage::<Name>();
+    let dungeon_master = ecs.fetch::<MasterDungeonMap>();
+    backpack_items(ecs)
+        .into_iter()
+        .filter(|item| magic_items.get(*item).is_some())
+        .filter(|item| {
+            names
+                .get(*item)
+                .is_some_and(|name| !dungeon_master.identified_items.contains(&name.name))
+        })
+        .collect()
+}
+
+/// The items in the backpack that use charges, in inventory menu order.
+pub fn charged_items(ecs: &World) -> Vec<Entity> {
+    let charges = ecs.read_storage::<Charges>();
+    backpack_items(ecs)
+        .into_iter()
+        .filter(|item| charges.get(*item).is_some())
+        .collect()
+}
+
+/// Where an item sits in the backpack, as used by `PlayerCommand`.
+pub fn backpack_index(ecs: &World, item: Entity) -> usize {
+    backpack_items(ecs)
+        .iter()
+        .position(|entity| *entity == item)
+        .expect("Item is not in the backpack")
+}
+
+/// Where an item sits in the player's equipment, as used by `PlayerCommand::RemoveItem`.
+pub fn equipped_index(ecs: &World, item: Entity) -> usize {
+    equipped_items(ecs)
+        .iter()
+        .position(|entity| *entity == item)
+        .expect("Item is not equipped")
+}
+
+/// Uses an item from the backpack. Ranged items need a target; without one the player is
+/// asked to pick it first. Identify and recharging scrolls ask which item to read them on.
+pub fn use_item(ecs: &mut World, item: Entity, target: Option<Point>) -> RunState {
+    if ecs
+        .read_storage::<Charges>()
+        .get(item)
+        .is_some_and(|charges| charges.remaining < 1)
+    {
+        let name = crate::gui::get_item_display_name(ecs, item);
+        ecs.fetch_mut::<GameLog>()
+            .entries
+            .push(format!("You use the {}, but nothing happens.", name));
+        return RunState::Ticking;
+    }
+    if ecs.read_storage::<ProvidesRecharge>().get(item).is_some() {
+        if charged_items(ecs).is_empty() {
+            ecs.fetch_mut::<GameLog>()
+                .entries
+                .push("You have nothing to recharge.".to_string());
+            return RunState::AwaitingInput;
         }
+        return RunState::ShowRecharge { scroll: item };
     }
-
-    if (key as usize) < carried_consumables.len() {
-        use crate::components::Ranged;
-        if let Some(ranged) = gs
-            .ecs
-            .read_storage::<Ranged>()
-            .get(carried_consumables[key as usize])
+    if ecs
+        .read_storage::<ProvidesIdentification>()
+        .get(item)
+        .is_some()
+    {
+        // The scroll can't be read on itself
+        if unidentified_items(ecs)
+            .iter()
+            .all(|unknown| *unknown == item)
         {
+            ecs.fetch_mut::<GameLog>()
+                .entries
+                .push("You have nothing to identify.".to_string());
+            return RunState::AwaitingInput;
+        }
+        return RunState::ShowIdentify { scroll: item };
+    }
+    if target.is_none() {
+        if let Some(ranged) = ecs.read_storage::<Ranged>().get(item) {
             return RunState::ShowTargeting {
                 range: ranged.range,
-                item: carried_consumables[key as usize],
+                item,
             };
         }
-        let mut intent = gs.ecs.write_storage::<WantsToUseItem>();
-        intent
-            .insert(
-                *player_entity,
-                WantsToUseItem {
-                    item: carried_consumables[key as usize],
-                    target: None,
-                },
-            )
-            .expect("Unable to insert intent");
     }
+    let mut intent = ecs.write_storage::<WantsToUseItem>();
+    intent
+        .insert(*ecs.fetch::<Entity>(), WantsToUseItem { item, target })
+        .expect("Unable to insert intent");
     RunState::Ticking
 }
+
+/// Shoots the equipped ranged weapon at whoever is on the target tile. Without a target the
+/// player is asked to pick one first.
+pub fn fire(ecs: &mut World, target: Option<Point>) -> RunState {
+    let player_entity = *ecs.fetch::<Entity>();
+    let mut log = ecs.fetch_mut::<GameLog>();
+    let weapon = equipped_ranged_weapon(
+        player_entity,
+        &ecs.read_storage::<Equipped>(),
+        &ecs.read_storage::<RangedWeapon>(),
+    );
+    let Some(weapon) = weapon else {
+        log.entries
+            .push("You have no ranged weapon equipped.".to_string());
+        return RunState::AwaitingInput;
+    };
+    let ammo = find_ammo(
+        player_entity,
+        &weapon.ammo,
+        &ecs.entities(),
+        &ecs.read_storage::<InBackpack>(),
+        &ecs.read_storage::<Ammunition>(),
+    );
+    if ammo.is_none() {
+        log.entries
+            .push(format!("You have no {} ammunition left.", weapon.ammo));
+        return RunState::AwaitingInput;
+    }
+    let Some(target) = target else {
+        return RunState::ShowFireTargeting {
+            range: weapon.range,
+        };
+    };
+
+    let player_pos = *ecs.fetch::<Point>();
+    let in_sight = ecs
+        .read_storage::<Viewshed>()
+        .get(player_entity)
+        .is_some_and(|viewshed| viewshed.visible_tiles.contains(&target));
+    if !in_sight
+        || rltk::DistanceAlg::Pythagoras.distance2d(player_pos, target) > weapon.range as f32
+    {
+        log.entries.push("You can't shoot there.".to_string());
+        return RunState::AwaitingInput;
+    }
+
+    let map = ecs.fetch::<Map>();
+    let pools = ecs.read_storage::<Pools>();
+    let mut victim = None;
+    map.spatial
+        .for_each_tile_content(map.xy_idx(target.x, target.y), |entity| {
+            if entity != player_entity && pools.get(entity).is_some() {
+                victim = Some(entity);
+            }
+        });
+    match victim {
+        None => {
+            log.entries
+                .push("There's nothing there to shoot at.".to_string());
+            RunState::AwaitingInput
+        }
+        Some(victim) => {
+            ecs.write_storage::<WantsToShoot>()
+                .insert(player_entity, WantsToShoot { target: victim })
+                .expect("Unable to insert intent");
+            RunState::Ticking
+        }
+    }
+}
+
+/// Casts one of the player's spells, if they have the mana for it. Spells with a range
+/// need a target tile in sight; without one the player is asked to pick it first.
+pub fn cast_spell(ecs: &mut World, spell: &str, target: Option<Point>) -> RunState {
+    let player_entity = *ecs.fetch::<Entity>();
+    let mut log = ecs.fetch_mut::<GameLog>();
+    let Some(info) = crate::raws::spell_info(&crate::raws::RAWS.lock().unwrap(), spell) else {
+        return RunState::AwaitingInput;
+    };
+    let mana = ecs
+        .read_storage::<Pools>()
+        .get(player_entity)
+        .map_or(0, |pools| pools.mana.current);
+    if mana < info.mana_cost {
+        log.entries
+            .push(format!("You don't have enough mana to cast {}.", spell));
+        return RunState::AwaitingInput;
+    }
+    let spell_index = known_spells(ecs).iter().position(|known| known == spell);
+
+    if let Some(range) = info.range {
+        let Some(target) = target else {
+            return match spell_index {
+                Some(spell) => RunState::ShowSpellTargeting { range, spell },
+                None => RunState::AwaitingInput,
+            };
+        };
+        let player_pos = *ecs.fetch::<Point>();
+        let in_sight = ecs
+            .read_storage::<Viewshed>()
+            .get(player_entity)
+            .is_some_and(|viewshed| viewshed.visible_tiles.contains(&target));
+        if !in_sight || rltk::DistanceAlg::Pythagoras.distance2d(player_pos, target) > range as f32
+        {
+            log.entries.push(format!("You can't cast {} there.", spell));
+            return RunState::AwaitingInput;
+        }
+    }
+
+    ecs.write_storage::<WantsToCastSpell>()
+        .insert(
+            player_entity,
+            WantsToCastSpell {
+                spell: spell.to_string(),
+                target: info.range.and(target),
+            },
+        )
+        .expect("Unable to insert intent");
+    RunState::Ticking
+}
+
+/// Drops an item from the backpack; from a stack, one is dropped.
+pub fn drop_item(ecs: &mut World, item: Entity) -> RunState {
+    let item = split_one(ecs, item);
+    let mut intent = ecs.write_storage::<WantsToDropItem>();
+    intent
+        .insert(*ecs.fetch::<Entity>(), WantsToDropItem { item })
+        .expect("Unable to insert intent");
+    RunState::Ticking
+}
+
+pub fn remove_item(ecs: &mut World, item: Entity) -> RunState {
+    let mut intent = ecs.write_storage::<WantsToRemoveItem>();
+    intent
+        .insert(*ecs.fetch::<Entity>(), WantsToRemoveItem { item })
+        .expect("Unable to insert intent");
+    RunState::Ticking
+}
+
+/// The containers the player can reach, on their tile or beside them, with how far away.
+fn containers_in_reach(ecs: &World) -> Vec<(Entity, Point, i32)> {
+    let player_pos = ecs.fetch::<Point>();
+    let entities = ecs.entities();
+    let containers = ecs.read_storage::<Container>();
+    let positions = ecs.read_storage::<Position>();
+
+    (&entities, &containers, &positions)
+        .join()
+        .map(|(entity, _, pos)| {
+            let distance = max((pos.x - player_pos.x).abs(), (pos.y - player_pos.y).abs());
+            (entity, Point::new(pos.x, pos.y), distance)
+        })
+        .filter(|(_, _, distance)| *distance <= 1)
+        .collect()
+}
+
+/// The container the player can reach: one they're standing on, or else one beside them.
+pub fn reachable_container(ecs: &World) -> Option<Entity> {
+    containers_in_reach(ecs)
+        .into_iter()
+        .min_by_key(|(_, _, distance)| *distance)
+        .map(|(entity, _, _)| entity)
+}
+
+/// The container at `pos`, if the player can still reach it.
+fn reachable_container_at(ecs: &World, pos: Point) -> Option<Entity> {
+    containers_in_reach(ecs)
+        .into_iter()
+        .find(|(_, container_pos, _)| *container_pos == pos)
+        .map(|(entity, _, _)| entity)
+}
+
+/// What's in a container, in transfer menu order.
+pub fn container_items(ecs: &World, container: Entity) -> Vec<Entity> {
+    let backpack = ecs.read_storage::<InBackpack>();
+    let entities = ecs.entities();
+
+    (&entities, &backpack)
+        .join()
+        .filter(|item| item.1.owner == container)
+        .map(|item| item.0)
+        .collect()
+}
+
+/// Where a container stands, which is how a command names it. One that isn't on the map
+/// can't be named at all.
+pub fn container_pos(ecs: &World, container: Entity) -> Option<Point> {
+    ecs.read_storage::<Position>()
+        .get(container)
+        .map(|pos| Point::new(pos.x, pos.y))
+}
+
+/// Opens the container the player can reach. A lock has to be picked first, which takes a
+/// turn whether it works or not; a trap goes off on the player instead of the container
+/// opening, and is gone after that.
+fn open_container(ecs: &mut World) -> RunState {
+    let Some(container) = reachable_container(ecs) else {
+        ecs.fetch_mut::<GameLog>()
+            .entries
+            .push("There is nothing here to open.".to_string());
+        return RunState::AwaitingInput;
+    };
+    let player_entity = *ecs.fetch::<Entity>();
+    let name = container_name(ecs, container);
+
+    let lock = ecs
+        .read_storage::<Locked>()
+        .get(container)
+        .map(|lock| lock.difficulty);
+    if let Some(difficulty) = lock {
+        let quickness = ecs
+            .read_storage::<Attributes>()
+            .get(player_entity)
+            .map_or(0, |attr| {
+                attribute_bonus(attr.quickness.base + attr.quickness.modifiers)
+            });
+        let roll = ecs
+            .write_resource::<rltk::RandomNumberGenerator>()
+            .roll_dice(1, 20);
+        let mut log = ecs.fetch_mut::<GameLog>();
+        if roll + quickness < difficulty {
+            log.entries
+                .push(format!("You fail to pick the lock on the {}.", name));
+            return RunState::Ticking;
+        }
+        log.entries
+            .push(format!("You pick the lock on the {}.", name));
+        drop(log);
+        ecs.write_storage::<Locked>().remove(container);
+    }
+
+    let trapped = ecs.write_storage::<Trapped>().remove(container);
+    if let Some(trapped) = trapped {
+        add_effect(
+            ecs,
+            None,
+            EffectType::TriggerFire {
+                trigger: trapped.trap,
+            },
+            Targets::Single {
+                target: player_entity,
+            },
+        );
+        return RunState::Ticking;
+    }
+
+    RunState::ShowContainer {
+        container,
+        mode: ContainerMode::Take,
+    }
+}
+
+/// Moves an item, a whole stack at a time, into someone's or something's inventory.
+fn transfer_item(ecs: &mut World, item: Entity, to: Entity) {
+    ecs.write_storage::<InBackpack>()
+        .insert(item, InBackpack { owner: to })
+        .expect("Unable to insert");
+    stack_carried(ecs, item);
+    let player_entity = *ecs.fetch::<Entity>();
+    ecs.write_storage::<EquipmentChanged>()
+        .insert(player_entity, EquipmentChanged {})
+        .expect("Unable to insert");
+}
+
+fn container_name(ecs: &World, container: Entity) -> String {
+    ecs.read_storage::<Name>()
+        .get(container)
+        .map_or(String::new(), |name| name.name.clone())
+}
+
+/// Whether a container is still locked or trapped, so it has to be opened before anything
+/// can go in or come out. Says so in the log if it is.
+fn still_shut(ecs: &World, container: Entity) -> bool {
+    let shut = ecs.read_storage::<Locked>().get(container).is_some()
+        || ecs.read_storage::<Trapped>().get(container).is_some();
+    if shut {
+        let log = format!("The {} isn't open.", container_name(ecs, container));
+        ecs.fetch_mut::<GameLog>().entries.push(log);
+    }
+    shut
+}
+
+/// Takes an item out of a container and into the backpack, keeping the container open.
+pub fn take_item(ecs: &mut World, container: Entity, item: Entity) -> RunState {
+    if still_shut(ecs, container) {
+        return RunState::AwaitingInput;
+    }
+    let name = crate::gui::get_item_display_name(ecs, item);
+    let player_entity = *ecs.fetch::<Entity>();
+    transfer_item(ecs, item, player_entity);
+    let log = format!(
+        "You take the {} from the {}.",
+        name,
+        container_name(ecs, container)
+    );
+    ecs.fetch_mut::<GameLog>().entries.push(log);
+    RunState::ShowContainer {
+        container,
+        mode: ContainerMode::Take,
+    }
+}
+
+/// Puts an item from the backpack in a container, keeping the container open.
+pub fn put_item(ecs: &mut World, container: Entity, item: Entity) -> RunState {
+    if still_shut(ecs, container) {
+        return RunState::AwaitingInput;
+    }
+    let name = crate::gui::get_item_display_name(ecs, item);
+    transfer_item(ecs, item, container);
+    let log = format!(
+        "You put the {} in the {}.",
+        name,
+        container_name(ecs, container)
+    );
+    ecs.fetch_mut::<GameLog>().entries.push(log);
+    RunState::ShowContainer {
+        container,
+        mode: ContainerMode::Put,
+    }
+}
+
+/// Reads an identify scroll on an unidentified item in the backpack, which makes both
+/// kinds known.
+pub fn identify_item(ecs: &mut World, scroll: Entity, item: Entity) -> RunState {
+    if ecs
+        .read_storage::<ProvidesIdentification>()
+        .get(scroll)
+        .is_none()
+        || scroll == item
+        || !unidentified_items(ecs).contains(&item)
+    {
+        return RunState::AwaitingInput;
+    }
+
+    let (scroll_name, item_name, unknown_as) = {
+        let names = ecs.read_storage::<Name>();
+        let obfuscated_names = ecs.read_storage::<ObfuscatedName>();
+        (
+            names.get(scroll).unwrap().name.clone(),
+            names.get(item).unwrap().name.clone(),
+            obfuscated_names
+                .get(item)
+                .map_or("unidentified item".to_string(), |name| name.name.clone()),
+        )
+    };
+    identify_kind(ecs, &scroll_name);
+    identify_kind(ecs, &item_name);
+    consume_one(ecs, scroll);
+    ecs.fetch_mut::<GameLog>().entries.push(format!(
+        "You read the {}. The {} is a {}.",
+        scroll_name, unknown_as, item_name
+    ));
+    RunState::Ticking
+}
+
+/// Reads a recharging scroll on a charged item in the backpack.
+pub fn recharge_with_scroll(ecs: &mut World, scroll: Entity, item: Entity) -> RunState {
+    if ecs.read_storage::<ProvidesRecharge>().get(scroll).is_none()
+        || !charged_items(ecs).contains(&item)
+    {
+        return RunState::AwaitingInput;
+    }
+    let scroll_name = ecs.read_storage::<Name>().get(scroll).unwrap().name.clone();
+    identify_kind(ecs, &scroll_name);
+    consume_one(ecs, scroll);
+    ecs.fetch_mut::<GameLog>()
+        .entries
+        .push(format!("You read the {}.", scroll_name));
+    recharge(ecs, item);
+    RunState::Ticking
+}
+
+/// Pays a vendor to recharge a charged item in the backpack, if the player can afford it.
+/// Returns whether the item exploded.
+pub fn pay_for_recharge(ecs: &mut World, item: Entity, price: f32) -> bool {
+    if !charged_items(ecs).contains(&item) {
+        return false;
+    }
+    {
+        let mut pools = ecs.write_storage::<Pools>();
+        let player_pools = pools.get_mut(*ecs.fetch::<Entity>()).unwrap();
+        if player_pools.gold < price {
+            ecs.fetch_mut::<GameLog>()
+                .entries
+                .push("You can't afford to have that recharged.".to_string());
+            return false;
+        }
+        player_pools.gold -= price;
+    }
+    recharge(ecs, item)
+}
+
+/// Fills a charged item back up, or - more likely the more often it has been recharged -
+/// blows it up in the player's face. Returns whether it exploded.
+fn recharge(ecs: &mut World, item: Entity) -> bool {
+    let times_recharged = ecs
+        .read_storage::<Charges>()
+        .get(item)
+        .unwrap()
+        .times_recharged;
+    let (explodes, damage) = {
+        let mut rng = ecs.write_resource::<rltk::RandomNumberGenerator>();
+        (
+            rng.roll_dice(1, 100) <= recharge_explosion_chance(times_recharged),
+            rng.roll_dice(2, 6),
+        )
+    };
+
+    if explodes {
+        let name = crate::gui::get_item_display_name(ecs, item);
+        ecs.delete_entity(item).expect("Unable to delete");
+        ecs.fetch_mut::<GameLog>().entries.push(format!(
+            "The {} explodes, hitting you for {} hp!",
+            name, damage
+        ));
+        add_effect(
+            ecs,
+            None,
+            EffectType::Damage { amount: damage },
+            Targets::Single {
+                target: *ecs.fetch::<Entity>(),
+            },
+        );
+    } else {
+        if let Some(charges) = ecs.write_storage::<Charges>().get_mut(item) {
+            charges.remaining = charges.max;
+            charges.times_recharged += 1;
+        }
+        let name = crate::gui::get_item_display_name(ecs, item);
+        ecs.fetch_mut::<GameLog>()
+            .entries
+            .push(format!("The {} glows; it is fully charged.", name));
+    }
+    explodes
+}
+
+/// Writes the player's own note on an unidentified kind of item, shown after its name.
+/// An empty note clears it.
+pub fn annotate_item(ecs: &mut World, item: Entity, text: &str) {
+    if !unidentified_items(ecs).contains(&item) {
+        return;
+    }
+    let name = ecs.read_storage::<Name>().get(item).unwrap().name.clone();
+    ecs.fetch_mut::<MasterDungeonMap>().note_mut(&name).text = text.trim().to_string();
+}
+
+/// Sells a backpack item to a vendor for 80% of its value; from a stack, one is sold. What
+/// an unidentified item fetched is noted as a hint to what it is.
+pub fn sell_item(ecs: &mut World, item: Entity) {
+    let price = ecs.read_storage::<Item>().get(item).unwrap().base_value * 0.8;
+    if unidentified_items(ecs).contains(&item) {
+        let name = ecs.read_storage::<Name>().get(item).unwrap().name.clone();
+        ecs.fetch_mut::<MasterDungeonMap>().note_mut(&name).sold_for = Some(price);
+    }
+    ecs.write_storage::<Pools>()
+        .get_mut(*ecs.fetch::<Entity>())
+        .unwrap()
+        .gold += price;
+    let stacked = ecs
+        .read_storage::<Stackable>()
+        .get(item)
+        .is_some_and(|stack| stack.quantity > 1);
+    if stacked {
+        ecs.write_storage::<Stackable>()
+            .get_mut(item)
+            .unwrap()
+            .quantity -= 1;
+    } else {
+        ecs.delete_entity(item).expect("Unable to delete");
+    }
+    ecs.write_storage::<EquipmentChanged>()
+        .insert(*ecs.fetch::<Entity>(), EquipmentChanged {})
+        .expect("Unable to insert");
+}
+
+/// Pays a temple to lift the curse on an equipped item, if the player can afford it.
+pub fn lift_curse(ecs: &mut World, item: Entity, price: f32) {
+    let player_entity = *ecs.fetch::<Entity>();
+    if ecs.read_storage::<CursedItem>().get(item).is_none() {
+        return;
+    }
+    {
+        let mut pools = ecs.write_storage::<Pools>();
+        let player_pools = pools.get_mut(player_entity).unwrap();
+        if player_pools.gold < price {
+            ecs.fetch_mut::<GameLog>()
+                .entries
+                .push("You can't afford the priest's blessing.".to_string());
+            return;
+        }
+        player_pools.gold -= price;
+    }
+    ecs.write_storage::<CursedItem>().remove(item);
+    let name = crate::gui::get_item_display_name(ecs, item);
+    ecs.fetch_mut::<GameLog>()
+        .entries
+        .push(format!("The priest lifts the curse on your {}.", name));
+}
+
+/// Buys an item from a vendor, if the player can afford it. Bought items are identified.
+pub fn buy_item(ecs: &mut World, tag: &str, price: f32) {
+    let player_entity = *ecs.fetch::<Entity>();
+    {
+        let mut pools = ecs.write_storage::<Pools>();
+        let player_pools = pools.get_mut(player_entity).unwrap();
+        if player_pools.gold < price {
+            return;
+        }
+        player_pools.gold -= price;
+    }
+
+    ecs.write_storage::<IdentifiedItem>()
+        .insert(
+            player_entity,
+            IdentifiedItem {
+                name: tag.to_string(),
+            },
+        )
+        .expect("Unable to insert");
+    let bought = crate::raws::spawn_named_item(
+        &crate::raws::RAWS.lock().unwrap(),
+        ecs,
+        tag,
+        crate::raws::SpawnType::Carried { by: player_entity },
+    );
+    if let Some(bought) = bought {
+        stack_carried(ecs, bought);
+    }
+    ecs.write_storage::<EquipmentChanged>()
+        .insert(player_entity, EquipmentChanged {})
+        .expect("Unable to insert");
+}
+
+/// The command for a consumable hotkey: the Nth consumable in the backpack, if there is
+/// one.
+fn consumable_hotkey_command(gs: &mut State, key: i32) -> Option<PlayerCommand> {
+    let consumable = gs.ecs.read_storage::<Consumable>();
+    let backpack = backpack_items(&gs.ecs);
+    let item = backpack
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| consumable.get(**item).is_some())
+        .map(|(idx, _)| idx)
+        .nth(key as usize)?;
+    Some(PlayerCommand::UseItem { item, target: None })
+}
@@ -0,0 +1,226 @@
+use super::{
+    validate_raws, FactionInfo, Item, LootTable, Mob, Prop, RawError, Raws, SpawnTableEntry, Spell,
+};
+use regex::Regex;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The text of one raws file and where it came from. A file holds any of the lists in
+/// `Raws`; the game's own are split by kind.
+#[derive(Clone)]
+pub struct RawFile {
+    pub path: String,
+    pub json: String,
+}
+
+impl RawFile {
+    pub fn read(path: &Path) -> Result<RawFile, RawError> {
+        let json = fs::read_to_string(path).map_err(|e| RawError {
+            file: Some(path.display().to_string()),
+            path: "(file)".to_string(),
+            message: e.to_string(),
+        })?;
+        Ok(RawFile {
+            path: path.display().to_string(),
+            json,
+        })
+    }
+}
+
+/// Every `.json` file under `dir`, in path order, so that a directory of content packs
+/// always overlays in the same order. A directory that doesn't exist has no files.
+pub fn read_raw_dir(dir: &Path) -> Result<Vec<RawFile>, RawError> {
+    fn collect(dir: &Path, paths: &mut Vec<PathBuf>) -> std::io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                collect(&path, paths)?;
+            } else if path.extension().and_then(|e| e.to_str()) == Some("json") {
+                paths.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    let mut paths = Vec::new();
+    if dir.is_dir() {
+        collect(dir, &mut paths).map_err(|e| RawError {
+            file: Some(dir.display().to_string()),
+            path: "(directory)".to_string(),
+            message: e.to_string(),
+        })?;
+    }
+    paths.sort();
+    paths.iter().map(|path| RawFile::read(path)).collect()
+}
+
+/// Parses raws files and merges them, in order, into one `Raws`, then checks the result.
+/// An entry with the same name as one from an earlier file replaces it, so later files
+/// (mods) can override the game's templates as well as add to them.
+///
+/// Every problem is reported, against the file and entry it came from. If any file
+/// doesn't parse, only those are reported: checking the rest without it would turn up
+/// every reference to what it holds.
+pub fn check_raw_files(files: &[RawFile]) -> Result<Raws, Vec<RawError>> {
+    let mut merged = Merged::default();
+    let mut errors = Vec::new();
+    let mut parse_failed = false;
+
+    for (file, raw_file) in files.iter().enumerate() {
+        let deserializer = &mut serde_json::Deserializer::from_str(&raw_file.json);
+        match serde_path_to_error::deserialize::<_, Raws>(deserializer) {
+            Ok(raws) => merged.overlay(file, &raw_file.path, raws, &mut errors),
+            Err(e) => {
+                parse_failed = true;
+                let path = e.path().to_string();
+                errors.push(RawError {
+                    file: Some(raw_file.path.clone()),
+                    path: if path == "." {
+                        "(root)".to_string()
+                    } else {
+                        path
+                    },
+                    message: e.into_inner().to_string(),
+                });
+            }
+        }
+    }
+
+    if !parse_failed {
+        for error in validate_raws(&merged.raws) {
+            errors.push(merged.locate(error, files));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(merged.raws)
+    } else {
+        Err(errors)
+    }
+}
+
+trait Named {
+    fn name(&self) -> &str;
+}
+
+macro_rules! named {
+    ($( $type:ty ),*) => {
+        $(
+            impl Named for $type {
+                fn name(&self) -> &str {
+                    &self.name
+                }
+            }
+        )*
+    };
+}
+
+named!(
+    Item,
+    Mob,
+    Prop,
+    SpawnTableEntry,
+    LootTable,
+    FactionInfo,
+    Spell
+);
+
+/// Where a merged entry came from: the file, and its index in that file's list.
+#[derive(Clone, Copy)]
+struct Source {
+    file: usize,
+    index: usize,
+}
+
+/// Raws merged so far, remembering where each entry came from so that problems found
+/// in the merged whole can be reported against the file to fix.
+#[derive(Default)]
+struct Merged {
+    raws: Raws,
+    sources: HashMap<&'static str, Vec<Source>>,
+}
+
+impl Merged {
+    fn overlay(&mut self, file: usize, path: &str, raws: Raws, errors: &mut Vec<RawError>) {
+        macro_rules! overlay_lists {
+            ($( $list:ident ),*) => {
+                $(
+                    overlay(
+                        stringify!($list),
+                        (file, path),
+                        &mut self.raws.$list,
+                        self.sources.entry(stringify!($list)).or_default(),
+                        raws.$list,
+                        errors,
+                    );
+                )*
+            };
+        }
+        overlay_lists!(
+            items,
+            mobs,
+            props,
+            spawn_table,
+            loot_tables,
+            faction_table,
+            spells
+        );
+    }
+
+    /// Points an error found in the merged raws at the file and entry it came from.
+    fn locate(&self, mut error: RawError, files: &[RawFile]) -> RawError {
+        lazy_static! {
+            static ref ENTRY_RE: Regex = Regex::new(r"^(\w+)\[(\d+)\](.*)$").unwrap();
+        }
+        let source = ENTRY_RE.captures(&error.path).and_then(|cap| {
+            let sources = self.sources.get(cap.get(1)?.as_str())?;
+            let source = sources.get(cap[2].parse::<usize>().ok()?)?;
+            Some((source, format!("{}[{}]{}", &cap[1], source.index, &cap[3])))
+        });
+        if let Some((source, path)) = source {
+            error.file = Some(files[source.file].path.clone());
+            error.path = path;
+        }
+        error
+    }
+}
+
+/// Adds `entries` from one file to a merged list, replacing entries of the same name.
+fn overlay<T: Named>(
+    list: &str,
+    (file, path): (usize, &str),
+    base: &mut Vec<T>,
+    sources: &mut Vec<Source>,
+    entries: Vec<T>,
+    errors: &mut Vec<RawError>,
+) {
+    let mut in_this_file: HashMap<String, usize> = HashMap::new();
+    for (index, entry) in entries.into_iter().enumerate() {
+        let name = entry.name().to_string();
+        if let Some(first) = in_this_file.get(&name) {
+            errors.push(RawError {
+                file: Some(path.to_string()),
+                path: format!("{}[{}].name", list, index),
+                message: format!(
+                    "\"{}\" is already the name of {}[{}] in this file",
+                    name, list, first
+                ),
+            });
+            continue;
+        }
+        in_this_file.insert(name.clone(), index);
+
+        let source = Source { file, index };
+        match base.iter().position(|existing| existing.name() == name) {
+            Some(existing) => {
+                base[existing] = entry;
+                sources[existing] = source;
+            }
+            None => {
+                base.push(entry);
+                sources.push(source);
+            }
+        }
+    }
+}
@@ -1,5 +1,8 @@
+use crate::components::StatusKind;
+use regex::Regex;
 use serde::Deserialize;
 use std::collections::HashMap;
+use std::convert::TryFrom;
 
 #[derive(Deserialize, Debug)]
 pub struct Item {
@@ -8,11 +11,21 @@ pub struct Item {
     pub consumable: Option<Consumable>,
     pub weapon: Option<Weapon>,
     pub wearable: Option<Wearable>,
+    pub ammunition: Option<String>,
     pub initiative_penalty: Option<f32>,
     pub weight_lbs: Option<f32>,
     pub base_value: Option<f32>,
     pub vendor_category: Option<String>,
     pub magic: Option<MagicItem>,
+    pub regeneration: Option<Regeneration>,
+    pub attributes: Option<AttributeBonuses>,
+    pub skills: Option<HashMap<String, i32>>,
+    /// Cursed equipment can't be removed once worn
+    #[serde(default)]
+    pub cursed: bool,
+    /// Several of it share one backpack line
+    #[serde(default)]
+    pub stackable: bool,
 }
 
 #[derive(Deserialize, Debug)]
@@ -25,7 +38,122 @@ pub struct Renderable {
 
 #[derive(Deserialize, Debug)]
 pub struct Consumable {
-    pub effects: HashMap<String, String>,
+    pub effects: Vec<Effect>,
+    /// How many uses it's made with, e.g. `"1d4+2"`; without it it's used up at once
+    pub charges: Option<Dice>,
+}
+
+/// One effect of using an item or stepping on a prop, written in the raws as an object
+/// tagged with its `type`, e.g. `{ "type": "damage", "dice": "2d6" }`. They are applied
+/// in the order they're listed.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Effect {
+    ProvidesHealing {
+        amount: i32,
+    },
+    Ranged {
+        range: i32,
+    },
+    Damage {
+        dice: Dice,
+    },
+    AreaOfEffect {
+        radius: i32,
+    },
+    /// A timed status put on whatever is hit, e.g.
+    /// `{ "type": "status", "status": "poison", "turns": 5, "magnitude": 2 }`
+    Status {
+        status: StatusKind,
+        turns: i32,
+        #[serde(default)]
+        magnitude: i32,
+    },
+    MagicMapping,
+    TownPortal,
+    RemoveCurse,
+    Identify,
+    Recharge,
+    Food,
+    SingleActivation,
+    TeachesSpell {
+        spell: String,
+    },
+    Particle {
+        glyph: char,
+        color: String,
+        lifetime_ms: f32,
+    },
+    ParticleLine {
+        glyph: char,
+        color: String,
+        lifetime_ms: f32,
+    },
+}
+
+impl Effect {
+    /// The effect's `type` in the raws.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Effect::ProvidesHealing { .. } => "provides_healing",
+            Effect::Ranged { .. } => "ranged",
+            Effect::Damage { .. } => "damage",
+            Effect::AreaOfEffect { .. } => "area_of_effect",
+            Effect::Status { .. } => "status",
+            Effect::MagicMapping => "magic_mapping",
+            Effect::TownPortal => "town_portal",
+            Effect::RemoveCurse => "remove_curse",
+            Effect::Identify => "identify",
+            Effect::Recharge => "recharge",
+            Effect::Food => "food",
+            Effect::SingleActivation => "single_activation",
+            Effect::TeachesSpell { .. } => "teaches_spell",
+            Effect::Particle { .. } => "particle",
+            Effect::ParticleLine { .. } => "particle_line",
+        }
+    }
+}
+
+/// A dice string like `2d4+1`, or a plain number for a fixed amount.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "String")]
+pub struct Dice {
+    pub n_dice: i32,
+    pub die_type: i32,
+    pub bonus: i32,
+}
+
+impl TryFrom<String> for Dice {
+    type Error = String;
+
+    fn try_from(dice: String) -> Result<Self, Self::Error> {
+        lazy_static! {
+            static ref DICE_RE: Regex = Regex::new(r"^(\d+)d(\d+)([\+\-]\d+)?$").unwrap();
+        }
+        let error = || {
+            format!(
+                "\"{}\" isn't a dice string like 1d6 or 2d4+1, or a number",
+                dice
+            )
+        };
+        if let Ok(bonus) = dice.parse::<i32>() {
+            return Ok(Dice {
+                n_dice: 0,
+                die_type: 0,
+                bonus,
+            });
+        }
+        let cap = DICE_RE.captures(&dice).ok_or_else(error)?;
+        let number = |i: usize| -> Result<i32, String> {
+            cap.get(i)
+                .map_or(Ok(0), |group| group.as_str().parse().map_err(|_| error()))
+        };
+        Ok(Dice {
+            n_dice: number(1)?,
+            die_type: number(2)?,
+            bonus: number(3)?,
+        })
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -33,6 +161,16 @@ pub struct Weapon {
     pub attribute: String,
     pub base_damage: String,
     pub hit_bonus: i32,
+    /// "melee", or how many tiles a ranged weapon reaches.
+    pub range: String,
+    /// The kind of `ammunition` a ranged weapon fires.
+    pub ammo: Option<String>,
+}
+
+impl Weapon {
+    pub fn ranged(&self) -> Option<i32> {
+        self.range.parse().ok()
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -41,6 +179,28 @@ pub struct Wearable {
     pub slot: String,
 }
 
+/// Tenths of a point more (or fewer) regained each turn by whoever has the item equipped.
+#[derive(Deserialize, Debug)]
+pub struct Regeneration {
+    #[serde(default)]
+    pub hp: i32,
+    #[serde(default)]
+    pub mana: i32,
+}
+
+/// Points added to (or taken from) the attributes of whoever has the item equipped.
+#[derive(Deserialize, Debug)]
+pub struct AttributeBonuses {
+    #[serde(default)]
+    pub might: i32,
+    #[serde(default)]
+    pub fitness: i32,
+    #[serde(default)]
+    pub quickness: i32,
+    #[serde(default)]
+    pub intelligence: i32,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct MagicItem {
     pub class: String,
@@ -15,12 +15,16 @@ pub struct Mob {
     pub skills: Option<HashMap<String, i32>>,
     pub level: Option<i32>,
     pub equipped: Option<Vec<String>>,
+    pub carried: Option<Vec<String>>,
     pub natural: Option<MobNatural>,
     pub loot_table: Option<String>,
     pub light: Option<MobLight>,
     pub faction: Option<String>,
     pub gold: Option<String>,
     pub vendor: Option<Vec<String>>,
+    pub temple: Option<MobTemple>,
+    pub recharge: Option<MobRecharge>,
+    pub spells: Option<Vec<String>>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -44,6 +48,18 @@ pub struct NaturalAttack {
     pub damage: String,
 }
 
+/// A mob that lifts curses for gold when the player bumps into them.
+#[derive(Deserialize, Debug)]
+pub struct MobTemple {
+    pub curse_price: f32,
+}
+
+/// A vendor who also recharges items, for a set price each time.
+#[derive(Deserialize, Debug)]
+pub struct MobRecharge {
+    pub price: f32,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct MobLight {
     pub range: i32,
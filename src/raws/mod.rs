@@ -1,29 +1,64 @@
 mod faction_structs;
+mod files;
 mod item_structs;
 mod loot_structs;
 mod mob_structs;
 mod prop_structs;
 mod raw_master;
 mod spawn_table_structs;
+mod spell_structs;
+mod validation;
 
 use faction_structs::FactionInfo;
 pub use faction_structs::Reaction;
+pub use files::{check_raw_files, read_raw_dir, RawFile};
 use item_structs::*;
+pub use item_structs::{Dice, Effect};
 use loot_structs::*;
 use mob_structs::*;
 use prop_structs::*;
 pub use raw_master::*;
 use serde::Deserialize;
 use spawn_table_structs::*;
+use spell_structs::*;
 use std::sync::Mutex;
+use validation::validate_raws;
+pub use validation::RawError;
 
 lazy_static! {
     pub static ref RAWS: Mutex<RawMaster> = Mutex::new(RawMaster::empty());
 }
 
-rltk::embedded_resource!(RAW_FILE, "../../raws/spawns.json");
+/// The game's own raws, built in and merged in this order before any mods.
+const BASE_RAWS: [(&str, &str); 7] = [
+    (
+        "raws/factions.json",
+        include_str!("../../raws/factions.json"),
+    ),
+    (
+        "raws/loot_tables.json",
+        include_str!("../../raws/loot_tables.json"),
+    ),
+    ("raws/items.json", include_str!("../../raws/items.json")),
+    ("raws/mobs.json", include_str!("../../raws/mobs.json")),
+    ("raws/props.json", include_str!("../../raws/props.json")),
+    ("raws/spells.json", include_str!("../../raws/spells.json")),
+    (
+        "raws/spawn_table.json",
+        include_str!("../../raws/spawn_table.json"),
+    ),
+];
 
-#[derive(Deserialize, Debug)]
+/// Where the game's own raws are read from when they're reloaded while it runs.
+pub const RAW_DIR: &str = "./raws";
+
+/// Content packs: any `.json` raws under here are merged over the game's own at startup,
+/// adding templates or replacing them by name.
+pub const MOD_DIR: &str = "./mods";
+
+/// Every list is optional, so a file can hold any of them.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
 pub struct Raws {
     pub items: Vec<Item>,
     pub mobs: Vec<Mob>,
@@ -31,21 +66,82 @@ pub struct Raws {
     pub spawn_table: Vec<SpawnTableEntry>,
     pub loot_tables: Vec<LootTable>,
     pub faction_table: Vec<FactionInfo>,
+    pub spells: Vec<Spell>,
 }
 
-pub fn load_raws() {
-    rltk::link_resource!(RAW_FILE, "../../raws/spawns.json");
+/// Loads the game's own raws and any mods over them. A mod file with problems is skipped,
+/// so one broken content pack doesn't keep the game from starting; the problems found are
+/// returned for the caller to show.
+pub fn load_raws() -> Vec<RawError> {
+    let base: Vec<RawFile> = BASE_RAWS
+        .iter()
+        .map(|(path, json)| RawFile {
+            path: path.to_string(),
+            json: json.to_string(),
+        })
+        .collect();
 
-    // Retrieve the raw data as an array of u8 (8-bit unsigned chars)
-    let raw_data = rltk::embedding::EMBED
-        .lock()
-        .get_resource("../../raws/spawns.json".to_string())
-        .unwrap();
+    #[cfg(not(target_arch = "wasm32"))]
+    let (mods, mut problems) = match read_raw_dir(std::path::Path::new(MOD_DIR)) {
+        Ok(mods) => (mods, Vec::new()),
+        Err(e) => (Vec::new(), vec![e]),
+    };
+    #[cfg(target_arch = "wasm32")]
+    let (mods, mut problems) = (Vec::new(), Vec::new());
 
-    let raw_string =
-        std::str::from_utf8(raw_data).expect("Unable to convert to a valid UTF-8 string");
+    let (raws, mod_problems) = check_raws_with_mods(&base, mods);
+    problems.extend(mod_problems);
+    RAWS.lock().unwrap().load(raws);
+    problems
+}
+
+/// Checks the game's own raws with mods merged over them, leaving out any mod file a
+/// problem is found in (or every mod, if a problem can't be pinned on one) until what's left
+/// is clean. Returns what's left, with the problems that were found on the way.
+pub fn check_raws_with_mods(base: &[RawFile], mut mods: Vec<RawFile>) -> (Raws, Vec<RawError>) {
+    let mut problems = Vec::new();
+    loop {
+        let files: Vec<RawFile> = base.iter().chain(mods.iter()).cloned().collect();
+        match check_raw_files(&files) {
+            Ok(raws) => return (raws, problems),
+            Err(errors) if mods.is_empty() => {
+                let report: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                panic!(
+                    "The raws have {} problem(s):\n{}",
+                    errors.len(),
+                    report.join("\n")
+                )
+            }
+            Err(errors) => {
+                let count = mods.len();
+                mods.retain(|file| {
+                    !errors
+                        .iter()
+                        .any(|e| e.file.as_deref() == Some(file.path.as_str()))
+                });
+                if mods.len() == count {
+                    mods.clear();
+                }
+                problems.extend(errors);
+            }
+        }
+    }
+}
 
-    let decoder: Raws = serde_json::from_str(raw_string).expect("Unable to parse JSON");
+/// Reads the raws again from `RAW_DIR` and the mods, so that what's spawned from now on
+/// uses the edited templates. If they have problems, the raws already loaded are kept.
+pub fn reload_raws() -> Result<(), Vec<RawError>> {
+    let mut files = read_raw_dir(std::path::Path::new(RAW_DIR)).map_err(|e| vec![e])?;
+    if files.is_empty() {
+        return Err(vec![RawError {
+            file: Some(RAW_DIR.to_string()),
+            path: "(directory)".to_string(),
+            message: "there are no raws here to reload".to_string(),
+        }]);
+    }
+    files.extend(read_raw_dir(std::path::Path::new(MOD_DIR)).map_err(|e| vec![e])?);
 
-    RAWS.lock().unwrap().load(decoder);
+    let raws = check_raw_files(&files)?;
+    RAWS.lock().unwrap().load(raws);
+    Ok(())
 }
@@ -1,6 +1,5 @@
-use super::Renderable;
+use super::{Dice, Effect, Renderable};
 use serde::Deserialize;
-use std::collections::HashMap;
 
 #[derive(Deserialize, Debug)]
 pub struct Prop {
@@ -12,9 +11,23 @@ pub struct Prop {
     pub door_open: Option<bool>,
     pub entry_trigger: Option<EntryTrigger>,
     pub light: Option<super::mob_structs::MobLight>,
+    pub container: Option<PropContainer>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct EntryTrigger {
-    pub effects: HashMap<String, String>,
+    pub effects: Vec<Effect>,
+}
+
+/// A prop that holds items, such as a chest or a corpse, filled from a loot table when it's
+/// made.
+#[derive(Deserialize, Debug)]
+pub struct PropContainer {
+    pub loot_table: String,
+    /// How many items it's filled with, e.g. `"1d3"`
+    pub items: Dice,
+    /// How hard its lock is to pick; without it, it's unlocked
+    pub lock: Option<i32>,
+    /// What it does to the first one to open it
+    pub trap: Option<EntryTrigger>,
 }
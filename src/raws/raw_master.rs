@@ -1,4 +1,4 @@
-use super::{faction_structs::Reaction, Raws};
+use super::{faction_structs::Reaction, Effect, Raws};
 use crate::{attribute_bonus, components::*, mana_at_level, npc_hp, random_table::RandomTable};
 use regex::Regex;
 use specs::{
@@ -19,6 +19,7 @@ pub struct RawMaster {
     mob_index: HashMap<String, usize>,
     prop_index: HashMap<String, usize>,
     loot_index: HashMap<String, usize>,
+    spell_index: HashMap<String, usize>,
     faction_index: HashMap<String, HashMap<String, Reaction>>,
 }
 
@@ -32,16 +33,20 @@ impl RawMaster {
                 spawn_table: Vec::new(),
                 loot_tables: Vec::new(),
                 faction_table: Vec::new(),
+                spells: Vec::new(),
             },
             item_index: HashMap::new(),
             mob_index: HashMap::new(),
             prop_index: HashMap::new(),
             loot_index: HashMap::new(),
+            spell_index: HashMap::new(),
             faction_index: HashMap::new(),
         }
     }
 
+    /// Replaces whatever was loaded before, indices and all.
     pub fn load(&mut self, raws: Raws) {
+        *self = RawMaster::empty();
         self.raws = raws;
         let mut used_names: HashSet<String> = HashSet::new();
 
@@ -89,6 +94,10 @@ impl RawMaster {
             self.loot_index.insert(loot.name.clone(), idx);
         }
 
+        for (idx, spell) in self.raws.spells.iter().enumerate() {
+            self.spell_index.insert(spell.name.clone(), idx);
+        }
+
         for faction in self.raws.faction_table.iter() {
             let mut reactions: HashMap<String, Reaction> = HashMap::new();
             for other in faction.responses.iter() {
@@ -115,6 +124,7 @@ pub fn string_to_slot(slot: &str) -> EquipmentSlot {
         "Feet" => EquipmentSlot::Feet,
         "Hands" => EquipmentSlot::Hands,
         "Melee" => EquipmentSlot::Melee,
+        "Ranged" => EquipmentSlot::Ranged,
         _ => {
             rltk::console::log(format!("Warning: unknown equipment slot type [{}]", slot));
             EquipmentSlot::Melee
@@ -122,13 +132,26 @@ pub fn string_to_slot(slot: &str) -> EquipmentSlot {
     }
 }
 
+fn string_to_skill(skill: &str) -> Option<Skill> {
+    match skill {
+        "Melee" => Some(Skill::Melee),
+        "Defense" => Some(Skill::Defense),
+        "Magic" => Some(Skill::Magic),
+        "Ranged" => Some(Skill::Ranged),
+        _ => None,
+    }
+}
+
 fn find_slot_for_equippable_item(tag: &str, raws: &RawMaster) -> EquipmentSlot {
     if !raws.item_index.contains_key(tag) {
         panic!("Trying to equip an unknown item: {}", tag);
     }
     let item_index = raws.item_index[tag];
     let item = &raws.raws.items[item_index];
-    if let Some(_weapon) = &item.weapon {
+    if let Some(weapon) = &item.weapon {
+        if weapon.ranged().is_some() {
+            return EquipmentSlot::Ranged;
+        }
         return EquipmentSlot::Melee;
     }
     if let Some(wearable) = &item.wearable {
@@ -205,6 +228,80 @@ pub fn parse_dice_string(dice: &str) -> (i32, i32, i32) {
     (n_dice, die_type, die_bonus)
 }
 
+/// Adds the components that carry out an item's, trap's or spell's effects. Its statuses
+/// all go on one `InflictsStatus`.
+fn with_effects<'a>(
+    mut entity_builder: EntityBuilder<'a>,
+    effects: &[Effect],
+) -> EntityBuilder<'a> {
+    let mut statuses = Vec::new();
+    for effect in effects.iter() {
+        if let Effect::Status {
+            status,
+            turns,
+            magnitude,
+        } = effect
+        {
+            statuses.push(Status {
+                kind: *status,
+                turns: *turns,
+                magnitude: *magnitude,
+            });
+        }
+        entity_builder = with_effect(entity_builder, effect);
+    }
+    if !statuses.is_empty() {
+        entity_builder = entity_builder.with(InflictsStatus { statuses });
+    }
+    entity_builder
+}
+
+/// Adds the component that carries out one effect of an item or trap.
+fn with_effect<'a>(entity_builder: EntityBuilder<'a>, effect: &Effect) -> EntityBuilder<'a> {
+    match effect {
+        Effect::ProvidesHealing { amount } => entity_builder.with(ProvidesHealing {
+            heal_amount: *amount,
+        }),
+        Effect::Ranged { range } => entity_builder.with(Ranged { range: *range }),
+        Effect::Damage { dice } => entity_builder.with(InflictsDamage {
+            n_dice: dice.n_dice,
+            die_type: dice.die_type,
+            bonus: dice.bonus,
+        }),
+        Effect::AreaOfEffect { radius } => entity_builder.with(AreaOfEffect { radius: *radius }),
+        // Gathered up by `with_effects`
+        Effect::Status { .. } => entity_builder,
+        Effect::MagicMapping => entity_builder.with(MagicMapper {}),
+        Effect::TownPortal => entity_builder.with(TownPortal {}),
+        Effect::RemoveCurse => entity_builder.with(ProvidesRemoveCurse {}),
+        Effect::Identify => entity_builder.with(ProvidesIdentification {}),
+        Effect::Recharge => entity_builder.with(ProvidesRecharge {}),
+        Effect::Food => entity_builder.with(ProvidesFood {}),
+        Effect::SingleActivation => entity_builder.with(SingleActivation {}),
+        Effect::TeachesSpell { spell } => entity_builder.with(TeachesSpell {
+            spell: spell.clone(),
+        }),
+        Effect::Particle {
+            glyph,
+            color,
+            lifetime_ms,
+        } => entity_builder.with(SpawnParticleBurst {
+            glyph: rltk::to_cp437(*glyph),
+            color: rltk::RGB::from_hex(color).expect("Bad color"),
+            lifetime_ms: *lifetime_ms,
+        }),
+        Effect::ParticleLine {
+            glyph,
+            color,
+            lifetime_ms,
+        } => entity_builder.with(SpawnParticleLine {
+            glyph: rltk::to_cp437(*glyph),
+            color: rltk::RGB::from_hex(color).expect("Bad color"),
+            lifetime_ms: *lifetime_ms,
+        }),
+    }
+}
+
 pub fn spawn_named_item(
     raws: &RawMaster,
     ecs: &mut World,
@@ -221,6 +318,16 @@ pub fn spawn_named_item(
 
         drop(dungeon_master);
 
+        // Charges come from the gameplay RNG, like mob gold
+        let charges = item_template
+            .consumable
+            .as_ref()
+            .and_then(|consumable| consumable.charges)
+            .map(|dice| {
+                let mut rng = ecs.write_resource::<rltk::RandomNumberGenerator>();
+                1.max(rng.roll_dice(dice.n_dice, dice.die_type) + dice.bonus)
+            });
+
         let mut entity_builder = ecs.create_entity().marked::<SimpleMarker<SerializeMe>>();
 
         // Spawn in the specified location
@@ -242,70 +349,51 @@ pub fn spawn_named_item(
 
         if let Some(consumable) = &item_template.consumable {
             entity_builder = entity_builder.with(Consumable {});
-            for effect in consumable.effects.iter() {
-                let effect_name = effect.0.as_str();
-                match effect_name {
-                    "provides_healing" => {
-                        entity_builder = entity_builder.with(ProvidesHealing {
-                            heal_amount: effect.1.parse::<i32>().unwrap(),
-                        });
-                    }
-                    "ranged" => {
-                        entity_builder = entity_builder.with(Ranged {
-                            range: effect.1.parse::<i32>().unwrap(),
-                        });
-                    }
-                    "damage" => {
-                        entity_builder = entity_builder.with(InflictsDamage {
-                            damage: effect.1.parse::<i32>().unwrap(),
-                        });
-                    }
-                    "area_of_effect" => {
-                        entity_builder = entity_builder.with(AreaOfEffect {
-                            radius: effect.1.parse::<i32>().unwrap(),
-                        });
-                    }
-                    "confusion" => {
-                        entity_builder = entity_builder.with(Confusion {
-                            turns: effect.1.parse::<i32>().unwrap(),
-                        });
-                    }
-                    "magic_mapping" => {
-                        entity_builder = entity_builder.with(MagicMapper {});
-                    }
-                    "town_portal" => {
-                        entity_builder = entity_builder.with(TownPortal {});
-                    }
-                    "food" => {
-                        entity_builder = entity_builder.with(ProvidesFood {});
-                    }
-                    _ => {
-                        rltk::console::log(format!(
-                            "Warning: consumable effect {} not implemented",
-                            effect_name
-                        ));
-                    }
-                }
+            entity_builder = with_effects(entity_builder, &consumable.effects);
+            if let Some(charges) = charges {
+                entity_builder = entity_builder.with(Charges {
+                    remaining: charges,
+                    max: charges,
+                    times_recharged: 0,
+                });
             }
         }
 
         if let Some(weapon) = &item_template.weapon {
-            entity_builder = entity_builder.with(Equippable {
-                slot: EquipmentSlot::Melee,
-            });
             let (n_dice, die_type, bonus) = parse_dice_string(&weapon.base_damage);
-            let mut wpn = MeleeWeapon {
-                attribute: WeaponAttribute::Might,
-                damage_n_dice: n_dice,
-                damage_die_type: die_type,
-                damage_bonus: bonus,
-                hit_bonus: weapon.hit_bonus,
-            };
-            match weapon.attribute.as_str() {
-                "Quickness" => wpn.attribute = WeaponAttribute::Quickness,
-                _ => wpn.attribute = WeaponAttribute::Might,
+            if let Some(range) = weapon.ranged() {
+                entity_builder = entity_builder.with(Equippable {
+                    slot: EquipmentSlot::Ranged,
+                });
+                entity_builder = entity_builder.with(RangedWeapon {
+                    range,
+                    ammo: weapon.ammo.clone().unwrap_or_default(),
+                    damage_n_dice: n_dice,
+                    damage_die_type: die_type,
+                    damage_bonus: bonus,
+                    hit_bonus: weapon.hit_bonus,
+                });
+            } else {
+                entity_builder = entity_builder.with(Equippable {
+                    slot: EquipmentSlot::Melee,
+                });
+                let mut wpn = MeleeWeapon {
+                    attribute: WeaponAttribute::Might,
+                    damage_n_dice: n_dice,
+                    damage_die_type: die_type,
+                    damage_bonus: bonus,
+                    hit_bonus: weapon.hit_bonus,
+                };
+                match weapon.attribute.as_str() {
+                    "Quickness" => wpn.attribute = WeaponAttribute::Quickness,
+                    _ => wpn.attribute = WeaponAttribute::Might,
+                }
+                entity_builder = entity_builder.with(wpn);
             }
-            entity_builder = entity_builder.with(wpn);
+        }
+
+        if let Some(kind) = &item_template.ammunition {
+            entity_builder = entity_builder.with(Ammunition { kind: kind.clone() });
         }
 
         if let Some(wearable) = &item_template.wearable {
@@ -317,6 +405,39 @@ pub fn spawn_named_item(
             });
         }
 
+        if let Some(regeneration) = &item_template.regeneration {
+            entity_builder = entity_builder.with(RegenerationBonus {
+                hp: regeneration.hp,
+                mana: regeneration.mana,
+            });
+        }
+
+        if let Some(bonuses) = &item_template.attributes {
+            entity_builder = entity_builder.with(AttributeBonus {
+                might: bonuses.might,
+                fitness: bonuses.fitness,
+                quickness: bonuses.quickness,
+                intelligence: bonuses.intelligence,
+            });
+        }
+
+        if let Some(item_skills) = &item_template.skills {
+            entity_builder = entity_builder.with(SkillBonus {
+                skills: item_skills
+                    .iter()
+                    .filter_map(|(name, bonus)| string_to_skill(name).map(|skill| (skill, *bonus)))
+                    .collect(),
+            });
+        }
+
+        if item_template.cursed {
+            entity_builder = entity_builder.with(CursedItem {});
+        }
+
+        if item_template.stackable {
+            entity_builder = entity_builder.with(Stackable { quantity: 1 });
+        }
+
         if let Some(magic) = &item_template.magic {
             let class = match magic.class.as_str() {
                 "rare" => MagicItemClass::Rare,
@@ -326,23 +447,16 @@ pub fn spawn_named_item(
             entity_builder = entity_builder.with(MagicItem { class });
 
             if !identified.contains(&item_template.name) {
-                match magic.naming.as_str() {
-                    "scroll" => {
-                        entity_builder = entity_builder.with(ObfuscatedName {
-                            name: scroll_names[&item_template.name].clone(),
-                        });
-                    }
-                    "potion" => {
-                        entity_builder = entity_builder.with(ObfuscatedName {
-                            name: potion_names[&item_template.name].clone(),
-                        });
-                    }
-                    _ => {
-                        entity_builder = entity_builder.with(ObfuscatedName {
-                            name: magic.naming.clone(),
-                        });
-                    }
-                }
+                // A scroll or potion the run has no made-up name for (added to the raws
+                // after it started) goes by its kind instead
+                let name = match magic.naming.as_str() {
+                    "scroll" => scroll_names.get(&item_template.name),
+                    "potion" => potion_names.get(&item_template.name),
+                    _ => None,
+                };
+                entity_builder = entity_builder.with(ObfuscatedName {
+                    name: name.unwrap_or(&magic.naming).clone(),
+                });
             }
         }
 
@@ -360,6 +474,15 @@ pub fn spawn_named_mob(
     if raws.mob_index.contains_key(key) {
         let mob_template = &raws.raws.mobs[raws.mob_index[key]];
 
+        // Gold comes from the gameplay RNG so that a seeded run stays reproducible
+        let mob_gold = if let Some(gold) = &mob_template.gold {
+            let mut rng = ecs.write_resource::<rltk::RandomNumberGenerator>();
+            let (number, dice, base) = parse_dice_string(gold);
+            (rng.roll_dice(number, dice) + base) as f32
+        } else {
+            0.0
+        };
+
         let mut entity_builder = ecs.create_entity().marked::<SimpleMarker<SerializeMe>>();
 
         // Spawn in the specified location
@@ -462,36 +585,29 @@ pub fn spawn_named_mob(
 
         let mut skills = Skills {
             skills: HashMap::new(),
+            practice: HashMap::new(),
+            modifiers: HashMap::new(),
         };
         skills.skills.insert(Skill::Melee, 1);
         skills.skills.insert(Skill::Defense, 1);
         skills.skills.insert(Skill::Magic, 1);
+        skills.skills.insert(Skill::Ranged, 1);
 
         if let Some(mob_skill) = &mob_template.skills {
-            for skill in mob_skill.iter() {
-                match skill.0.as_str() {
-                    "Melee" => {
-                        skills.skills.insert(Skill::Melee, *skill.1);
-                    }
-                    "Defense" => {
-                        skills.skills.insert(Skill::Defense, *skill.1);
-                    }
-                    "Magic" => {
-                        skills.skills.insert(Skill::Magic, *skill.1);
+            for (name, level) in mob_skill.iter() {
+                match string_to_skill(name) {
+                    Some(skill) => {
+                        skills.skills.insert(skill, *level);
                     }
-                    _ => {
-                        rltk::console::log(format!("Unknown skill referenced: [{}]", skill.0));
+                    None => {
+                        rltk::console::log(format!("Unknown skill referenced: [{}]", name));
                     }
                 }
             }
         }
         entity_builder = entity_builder.with(skills);
 
-        let mob_level = if mob_template.level.is_some() {
-            mob_template.level.unwrap()
-        } else {
-            1
-        };
+        let mob_level = mob_template.level.unwrap_or(1);
         let mob_hp = npc_hp(mob_fitness, mob_level);
         let mob_mana = mana_at_level(mob_intelligence, mob_level);
 
@@ -508,14 +624,10 @@ pub fn spawn_named_mob(
             },
             total_weight: 0.0,
             total_initiative_penalty: 0.0,
-            gold: if let Some(gold) = &mob_template.gold {
-                let mut rng = rltk::RandomNumberGenerator::new();
-                let (number, dice, base) = parse_dice_string(gold);
-                (rng.roll_dice(number, dice) + base) as f32
-            } else {
-                0.0
-            },
+            gold: mob_gold,
             god_mode: false,
+            hp_regen_progress: 0,
+            mana_regen_progress: 0,
         };
         entity_builder = entity_builder.with(pools);
 
@@ -579,6 +691,24 @@ pub fn spawn_named_mob(
             });
         }
 
+        if let Some(temple) = &mob_template.temple {
+            entity_builder = entity_builder.with(Temple {
+                curse_price: temple.curse_price,
+            });
+        }
+
+        if let Some(recharge) = &mob_template.recharge {
+            entity_builder = entity_builder.with(Recharger {
+                price: recharge.price,
+            });
+        }
+
+        if let Some(spells) = &mob_template.spells {
+            entity_builder = entity_builder.with(KnownSpells {
+                spells: spells.clone(),
+            });
+        }
+
         let new_mob = entity_builder.build();
 
         // Are they wielding anything
@@ -587,6 +717,11 @@ pub fn spawn_named_mob(
                 spawn_named_entity(raws, ecs, tag, SpawnType::Equipped { by: new_mob });
             }
         }
+        if let Some(carrying) = &mob_template.carried {
+            for tag in carrying.iter() {
+                spawn_named_entity(raws, ecs, tag, SpawnType::Carried { by: new_mob });
+            }
+        }
 
         return Some(new_mob);
     }
@@ -640,19 +775,7 @@ pub fn spawn_named_prop(
 
         if let Some(entry_trigger) = &prop_template.entry_trigger {
             entity_builder = entity_builder.with(EntryTrigger {});
-            for effect in entry_trigger.effects.iter() {
-                match effect.0.as_str() {
-                    "damage" => {
-                        entity_builder = entity_builder.with(InflictsDamage {
-                            damage: effect.1.parse::<i32>().unwrap(),
-                        });
-                    }
-                    "single_activation" => {
-                        entity_builder = entity_builder.with(SingleActivation {});
-                    }
-                    _ => {}
-                }
-            }
+            entity_builder = with_effects(entity_builder, &entry_trigger.effects);
         }
 
         if let Some(light) = &prop_template.light {
@@ -667,11 +790,100 @@ pub fn spawn_named_prop(
             });
         }
 
-        return Some(entity_builder.build());
+        if let Some(container) = &prop_template.container {
+            entity_builder = entity_builder.with(Container {});
+            if let Some(difficulty) = container.lock {
+                entity_builder = entity_builder.with(Locked { difficulty });
+            }
+        }
+
+        let prop = entity_builder.build();
+
+        // A trap is built on its own entity, named for what it's hidden in
+        if let Some(trap) = prop_template
+            .container
+            .as_ref()
+            .and_then(|c| c.trap.as_ref())
+        {
+            let trap_builder = ecs
+                .create_entity()
+                .marked::<SimpleMarker<SerializeMe>>()
+                .with(Name {
+                    name: prop_template.name.clone(),
+                })
+                .with(SingleActivation {});
+            let trap = with_effects(trap_builder, &trap.effects).build();
+            ecs.write_storage::<Trapped>()
+                .insert(prop, Trapped { trap })
+                .expect("Insert fail");
+        }
+
+        // Fill it from its loot table, like a mob's drops
+        if let Some(container) = &prop_template.container {
+            let drops: Vec<String> = {
+                let mut rng = ecs.write_resource::<rltk::RandomNumberGenerator>();
+                let dice = container.items;
+                let count = rng.roll_dice(dice.n_dice, dice.die_type) + dice.bonus;
+                (0..count)
+                    .filter_map(|_| get_item_drop(raws, &mut rng, &container.loot_table))
+                    .collect()
+            };
+            for drop in drops.iter() {
+                if let Some(item) =
+                    spawn_named_item(raws, ecs, drop, SpawnType::Carried { by: prop })
+                {
+                    crate::stack_carried(ecs, item);
+                }
+            }
+        }
+
+        return Some(prop);
     }
     None
 }
 
+/// What casting a spell costs and how it is aimed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpellInfo {
+    pub mana_cost: i32,
+    pub range: Option<i32>,
+    pub radius: Option<i32>,
+}
+
+pub fn spell_info(raws: &RawMaster, key: &str) -> Option<SpellInfo> {
+    let spell = &raws.raws.spells[*raws.spell_index.get(key)?];
+    let mut info = SpellInfo {
+        mana_cost: spell.mana_cost,
+        range: None,
+        radius: None,
+    };
+    for effect in spell.effects.iter() {
+        match effect {
+            Effect::Ranged { range } => info.range = Some(*range),
+            Effect::AreaOfEffect { radius } => info.radius = Some(*radius),
+            _ => {}
+        }
+    }
+    Some(info)
+}
+
+/// Builds an entity carrying a spell's effects for the effects system to fire, in the same
+/// way as an item's. It isn't saved, and should be deleted once the spell is cast.
+pub fn spawn_named_spell(raws: &RawMaster, ecs: &mut World, key: &str) -> Option<Entity> {
+    let spell_template = &raws.raws.spells[*raws.spell_index.get(key)?];
+
+    let mut entity_builder = ecs
+        .create_entity()
+        .with(Name {
+            name: spell_template.name.clone(),
+        })
+        .with(SpellTemplate {
+            mana_cost: spell_template.mana_cost,
+        });
+    entity_builder = with_effects(entity_builder, &spell_template.effects);
+    Some(entity_builder.build())
+}
+
 pub fn spawn_named_entity(
     raws: &RawMaster,
     ecs: &mut World,
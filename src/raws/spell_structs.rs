@@ -0,0 +1,9 @@
+use super::Effect;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct Spell {
+    pub name: String,
+    pub mana_cost: i32,
+    pub effects: Vec<Effect>,
+}
@@ -0,0 +1,734 @@
+use super::{mob_structs::MobLight, prop_structs::PropContainer, Effect, Raws, Renderable, Weapon};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Effects that only make sense when an item is used, and those that only make sense when
+/// a prop is stepped on. Any other effect can be either. Spells can have what items do,
+/// bar being eaten or teaching spells. A container's trap is like a prop's, but only ever
+/// goes off once.
+const CONSUMABLE_ONLY_EFFECTS: &[&str] = &[
+    "provides_healing",
+    "ranged",
+    "area_of_effect",
+    "magic_mapping",
+    "town_portal",
+    "remove_curse",
+    "identify",
+    "recharge",
+    "food",
+    "teaches_spell",
+];
+const TRIGGER_ONLY_EFFECTS: &[&str] = &["single_activation"];
+const NOT_CONTAINER_TRAP_EFFECTS: &[&str] = &[
+    "provides_healing",
+    "ranged",
+    "area_of_effect",
+    "magic_mapping",
+    "town_portal",
+    "remove_curse",
+    "identify",
+    "recharge",
+    "food",
+    "teaches_spell",
+    "single_activation",
+];
+const NOT_SPELL_EFFECTS: &[&str] = &[
+    "single_activation",
+    "food",
+    "teaches_spell",
+    "identify",
+    "recharge",
+];
+
+const SLOTS: &[&str] = &["Shield", "Head", "Torso", "Legs", "Feet", "Hands", "Melee"];
+const WEAPON_ATTRIBUTES: &[&str] = &["Might", "Quickness"];
+const SKILLS: &[&str] = &["Melee", "Defense", "Magic", "Ranged"];
+const MOVEMENT_MODES: &[&str] = &["static", "random", "random_waypoint"];
+const MAGIC_CLASSES: &[&str] = &["common", "rare", "legendary"];
+const REACTIONS: &[&str] = &["ignore", "attack", "flee"];
+
+/// Something wrong in the raws, with the file it's in and the JSON path of the value at
+/// fault within it, e.g. `mobs[3].equipped[1]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawError {
+    pub file: Option<String>,
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for RawError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}: ", file)?;
+        }
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Every problem with raws that parsed: names that are used twice or that refer to
+/// nothing, and values the spawner would panic on or quietly ignore.
+pub fn validate_raws(raws: &Raws) -> Vec<RawError> {
+    let mut check = Checker::default();
+    check.names(raws);
+    check.spawn_table(raws);
+    check.loot_tables(raws);
+    check.factions(raws);
+    check.items(raws);
+    check.mobs(raws);
+    check.props(raws);
+    check.spells(raws);
+    check.errors
+}
+
+#[derive(Default)]
+struct Checker {
+    errors: Vec<RawError>,
+}
+
+impl Checker {
+    fn error<S: ToString>(&mut self, path: &str, message: S) {
+        self.errors.push(RawError {
+            file: None,
+            path: path.to_string(),
+            message: message.to_string(),
+        });
+    }
+
+    fn one_of(&mut self, path: &str, what: &str, value: &str, allowed: &[&str]) {
+        if !allowed.contains(&value) {
+            self.error(
+                path,
+                format!(
+                    "unknown {} \"{}\"; expected one of {}",
+                    what,
+                    value,
+                    allowed.join(", ")
+                ),
+            );
+        }
+    }
+
+    fn dice(&mut self, path: &str, dice: &str) {
+        lazy_static! {
+            static ref DICE_RE: Regex = Regex::new(r"^\d+d\d+([\+\-]\d+)?$").unwrap();
+        }
+        if !DICE_RE.is_match(dice) {
+            self.error(
+                path,
+                format!("\"{}\" isn't a dice string like 1d6 or 2d4+1", dice),
+            );
+        }
+    }
+
+    fn color(&mut self, path: &str, color: &str) {
+        if color.len() != 7 || rltk::RGB::from_hex(color).is_err() {
+            self.error(path, format!("\"{}\" isn't a colour like #FF00FF", color));
+        }
+    }
+
+    fn renderable(&mut self, path: &str, renderable: &Renderable) {
+        let mut chars = renderable.glyph.chars();
+        match (chars.next(), chars.next()) {
+            (Some(glyph), None) => self.glyph(&format!("{}.glyph", path), glyph),
+            _ => self.error(
+                &format!("{}.glyph", path),
+                format!("\"{}\" should be a single character", renderable.glyph),
+            ),
+        }
+        self.color(&format!("{}.fg", path), &renderable.fg);
+        self.color(&format!("{}.bg", path), &renderable.bg);
+    }
+
+    fn light(&mut self, path: &str, light: &MobLight) {
+        if light.range < 1 {
+            self.error(
+                &format!("{}.range", path),
+                "a light needs a range of 1 or more",
+            );
+        }
+        self.color(&format!("{}.color", path), &light.color);
+    }
+
+    fn glyph(&mut self, path: &str, glyph: char) {
+        if rltk::to_cp437(glyph) == 0 {
+            self.error(path, format!("'{}' isn't in code page 437", glyph));
+        }
+    }
+
+    /// Each effect is a component on the item or prop, so it can only be listed once.
+    fn effects(
+        &mut self,
+        raws: &Raws,
+        path: &str,
+        effects: &[Effect],
+        not_here: &[&str],
+        on: &str,
+    ) {
+        let mut seen = HashSet::new();
+        let mut seen_statuses = HashSet::new();
+        for (i, effect) in effects.iter().enumerate() {
+            let path = format!("{}[{}]", path, i);
+            let name = effect.name();
+            if not_here.contains(&name) {
+                self.error(
+                    &path,
+                    format!("{} {} effect can't be used {}", article(name), name, on),
+                );
+            }
+            // There can be several statuses, but only one of each kind
+            if !matches!(effect, Effect::Status { .. }) && !seen.insert(name) {
+                self.error(
+                    &path,
+                    format!("there is already {} {} effect", article(name), name),
+                );
+            }
+            match effect {
+                Effect::Ranged { range: number } | Effect::AreaOfEffect { radius: number }
+                    if *number < 1 =>
+                {
+                    self.error(&path, format!("a {} effect needs 1 or more", name))
+                }
+                Effect::Status {
+                    status,
+                    turns,
+                    magnitude,
+                } => {
+                    let status_name = format!("{:?}", status).to_lowercase();
+                    if !seen_statuses.insert(*status) {
+                        self.error(
+                            &format!("{}.status", path),
+                            format!("there is already a {} status", status_name),
+                        );
+                    }
+                    if *turns < 1 {
+                        self.error(
+                            &format!("{}.turns", path),
+                            "a status has to last 1 turn or more",
+                        );
+                    }
+                    if status.has_magnitude() && *magnitude < 1 {
+                        self.error(
+                            &format!("{}.magnitude", path),
+                            format!("a {} status needs a magnitude of 1 or more", status_name),
+                        );
+                    } else if !status.has_magnitude() && *magnitude != 0 {
+                        self.error(
+                            &format!("{}.magnitude", path),
+                            format!("a {} status has no magnitude", status_name),
+                        );
+                    }
+                }
+                Effect::TeachesSpell { spell } if !is_spell(raws, spell) => self.error(
+                    &format!("{}.spell", path),
+                    format!("no spell is called \"{}\"", spell),
+                ),
+                Effect::Particle {
+                    glyph,
+                    color,
+                    lifetime_ms,
+                }
+                | Effect::ParticleLine {
+                    glyph,
+                    color,
+                    lifetime_ms,
+                } => {
+                    self.glyph(&format!("{}.glyph", path), *glyph);
+                    self.color(&format!("{}.color", path), color);
+                    if *lifetime_ms <= 0.0 {
+                        self.error(
+                            &format!("{}.lifetime_ms", path),
+                            "a particle needs a lifetime above 0",
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// A weapon with a range in tiles is a ranged weapon, and fires ammunition of its
+    /// `ammo` kind.
+    fn ranged_weapon(&mut self, raws: &Raws, path: &str, weapon: &Weapon) {
+        match (weapon.ranged(), &weapon.ammo) {
+            (None, ammo) => {
+                if weapon.range != "melee" {
+                    self.error(
+                        &format!("{}.range", path),
+                        format!(
+                            "\"{}\" should be \"melee\" or a number of tiles",
+                            weapon.range
+                        ),
+                    );
+                } else if ammo.is_some() {
+                    self.error(
+                        &format!("{}.ammo", path),
+                        "melee weapons don't take ammunition",
+                    );
+                }
+            }
+            (Some(range), Some(ammo)) => {
+                if range < 1 {
+                    self.error(
+                        &format!("{}.range", path),
+                        "a ranged weapon needs a range of 1 or more",
+                    );
+                }
+                if weapon.attribute != "Quickness" {
+                    self.error(
+                        &format!("{}.attribute", path),
+                        "ranged weapons are aimed with Quickness",
+                    );
+                }
+                if !raws
+                    .items
+                    .iter()
+                    .any(|item| item.ammunition.as_ref() == Some(ammo))
+                {
+                    self.error(
+                        &format!("{}.ammo", path),
+                        format!("no item is ammunition of kind \"{}\"", ammo),
+                    );
+                }
+            }
+            (Some(_), None) => self.error(
+                &format!("{}.ammo", path),
+                "a ranged weapon needs an ammo kind",
+            ),
+        }
+    }
+
+    /// Items, mobs and props are spawned by name, so no two can share one. (Within each
+    /// list a later entry with the same name overrides the earlier one instead.)
+    fn names(&mut self, raws: &Raws) {
+        let mut seen: HashMap<&str, &str> = HashMap::new();
+        let all = raws
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| ("items", "an item", i, &item.name))
+            .chain(
+                raws.mobs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, mob)| ("mobs", "a mob", i, &mob.name)),
+            )
+            .chain(
+                raws.props
+                    .iter()
+                    .enumerate()
+                    .map(|(i, prop)| ("props", "a prop", i, &prop.name)),
+            );
+        for (list, kind, i, name) in all {
+            match seen.get(name.as_str()) {
+                Some(first) => self.error(
+                    &format!("{}[{}].name", list, i),
+                    format!("\"{}\" is already the name of {}", name, first),
+                ),
+                None => {
+                    seen.insert(name, kind);
+                }
+            }
+        }
+    }
+
+    fn spawn_table(&mut self, raws: &Raws) {
+        for (i, entry) in raws.spawn_table.iter().enumerate() {
+            let path = format!("spawn_table[{}]", i);
+            if !is_spawnable(raws, &entry.name) {
+                self.error(
+                    &format!("{}.name", path),
+                    format!("no item, mob or prop is called \"{}\"", entry.name),
+                );
+            }
+            if entry.weight < 1 {
+                self.error(&format!("{}.weight", path), "weights must be 1 or more");
+            }
+            if entry.min_depth > entry.max_depth {
+                self.error(
+                    &format!("{}.max_depth", path),
+                    format!(
+                        "max_depth {} is below min_depth {}",
+                        entry.max_depth, entry.min_depth
+                    ),
+                );
+            }
+        }
+    }
+
+    fn loot_tables(&mut self, raws: &Raws) {
+        for (i, table) in raws.loot_tables.iter().enumerate() {
+            if table.drops.is_empty() {
+                self.error(
+                    &format!("loot_tables[{}].drops", i),
+                    "the table drops nothing",
+                );
+            }
+            for (j, drop) in table.drops.iter().enumerate() {
+                let path = format!("loot_tables[{}].drops[{}]", i, j);
+                if !raws.items.iter().any(|item| item.name == drop.name) {
+                    self.error(
+                        &format!("{}.name", path),
+                        format!("no item is called \"{}\"", drop.name),
+                    );
+                }
+                if drop.weight < 1 {
+                    self.error(&format!("{}.weight", path), "weights must be 1 or more");
+                }
+            }
+        }
+    }
+
+    fn factions(&mut self, raws: &Raws) {
+        for (i, faction) in raws.faction_table.iter().enumerate() {
+            let mut others: Vec<&String> = faction.responses.keys().collect();
+            others.sort();
+            for other in others {
+                let path = format!("faction_table[{}].responses.{}", i, other);
+                if other != "Default" && !is_faction(raws, other) {
+                    self.error(&path, format!("no faction is called \"{}\"", other));
+                }
+                self.one_of(&path, "reaction", &faction.responses[other], REACTIONS);
+            }
+        }
+    }
+
+    fn items(&mut self, raws: &Raws) {
+        for (i, item) in raws.items.iter().enumerate() {
+            let path = format!("items[{}]", i);
+            if let Some(renderable) = &item.renderable {
+                self.renderable(&format!("{}.renderable", path), renderable);
+            }
+            if let Some(consumable) = &item.consumable {
+                self.effects(
+                    raws,
+                    &format!("{}.consumable.effects", path),
+                    &consumable.effects,
+                    TRIGGER_ONLY_EFFECTS,
+                    "by an item",
+                );
+                if consumable
+                    .charges
+                    .is_some_and(|charges| charges.n_dice + charges.bonus < 1)
+                {
+                    self.error(
+                        &format!("{}.consumable.charges", path),
+                        "an item with charges needs at least 1 to begin with",
+                    );
+                }
+            }
+            if let Some(weapon) = &item.weapon {
+                self.one_of(
+                    &format!("{}.weapon.attribute", path),
+                    "attribute",
+                    &weapon.attribute,
+                    WEAPON_ATTRIBUTES,
+                );
+                self.dice(&format!("{}.weapon.base_damage", path), &weapon.base_damage);
+                self.ranged_weapon(raws, &format!("{}.weapon", path), weapon);
+            }
+            if let Some(wearable) = &item.wearable {
+                self.one_of(
+                    &format!("{}.wearable.slot", path),
+                    "slot",
+                    &wearable.slot,
+                    SLOTS,
+                );
+            }
+            if item.regeneration.is_some() && item.weapon.is_none() && item.wearable.is_none() {
+                self.error(
+                    &format!("{}.regeneration", path),
+                    "only equipment can change how quickly its wearer regenerates",
+                );
+            }
+            if item.cursed && item.weapon.is_none() && item.wearable.is_none() {
+                self.error(&format!("{}.cursed", path), "only equipment can be cursed");
+            }
+            if item.stackable {
+                let charged = item
+                    .consumable
+                    .as_ref()
+                    .is_some_and(|consumable| consumable.charges.is_some());
+                let problem = if item.weapon.is_some() || item.wearable.is_some() {
+                    Some("equipment can't stack")
+                } else if charged {
+                    Some("an item with charges can't stack")
+                } else {
+                    None
+                };
+                if let Some(problem) = problem {
+                    self.error(&format!("{}.stackable", path), problem);
+                }
+            }
+            if item.attributes.is_some() && item.weapon.is_none() && item.wearable.is_none() {
+                self.error(
+                    &format!("{}.attributes", path),
+                    "only equipment can change its wearer's attributes",
+                );
+            }
+            if let Some(skills) = &item.skills {
+                if item.weapon.is_none() && item.wearable.is_none() {
+                    self.error(
+                        &format!("{}.skills", path),
+                        "only equipment can change its wearer's skills",
+                    );
+                }
+                let mut names: Vec<&String> = skills.keys().collect();
+                names.sort();
+                for skill in names {
+                    self.one_of(
+                        &format!("{}.skills.{}", path, skill),
+                        "skill",
+                        skill,
+                        SKILLS,
+                    );
+                }
+            }
+            if item.vendor_category.is_some() && item.base_value.is_none() {
+                self.error(
+                    &format!("{}.vendor_category", path),
+                    "items sold by vendors need a base_value",
+                );
+            }
+            if let Some(magic) = &item.magic {
+                self.one_of(
+                    &format!("{}.magic.class", path),
+                    "magic class",
+                    &magic.class,
+                    MAGIC_CLASSES,
+                );
+            }
+        }
+    }
+
+    fn mobs(&mut self, raws: &Raws) {
+        let vendor_categories: HashSet<&String> = raws
+            .items
+            .iter()
+            .filter_map(|item| item.vendor_category.as_ref())
+            .collect();
+
+        for (i, mob) in raws.mobs.iter().enumerate() {
+            let path = format!("mobs[{}]", i);
+            if let Some(renderable) = &mob.renderable {
+                self.renderable(&format!("{}.renderable", path), renderable);
+            }
+            self.one_of(
+                &format!("{}.movement", path),
+                "movement mode",
+                &mob.movement,
+                MOVEMENT_MODES,
+            );
+            if let Some(skills) = &mob.skills {
+                let mut names: Vec<&String> = skills.keys().collect();
+                names.sort();
+                for skill in names {
+                    self.one_of(
+                        &format!("{}.skills.{}", path, skill),
+                        "skill",
+                        skill,
+                        SKILLS,
+                    );
+                }
+            }
+            for (j, tag) in mob.equipped.iter().flatten().enumerate() {
+                let equipped_path = format!("{}.equipped[{}]", path, j);
+                match raws.items.iter().find(|item| &item.name == tag) {
+                    None => self.error(&equipped_path, format!("no item is called \"{}\"", tag)),
+                    Some(item) if item.weapon.is_none() && item.wearable.is_none() => self.error(
+                        &equipped_path,
+                        format!("\"{}\" is neither a weapon nor wearable", tag),
+                    ),
+                    _ => {}
+                }
+            }
+            for (j, tag) in mob.carried.iter().flatten().enumerate() {
+                if !raws.items.iter().any(|item| &item.name == tag) {
+                    self.error(
+                        &format!("{}.carried[{}]", path, j),
+                        format!("no item is called \"{}\"", tag),
+                    );
+                }
+            }
+            if let Some(natural) = &mob.natural {
+                for (j, attack) in natural.attacks.iter().flatten().enumerate() {
+                    self.dice(
+                        &format!("{}.natural.attacks[{}].damage", path, j),
+                        &attack.damage,
+                    );
+                }
+            }
+            if let Some(loot) = &mob.loot_table {
+                if !raws.loot_tables.iter().any(|table| &table.name == loot) {
+                    self.error(
+                        &format!("{}.loot_table", path),
+                        format!("no loot table is called \"{}\"", loot),
+                    );
+                }
+            }
+            if let Some(light) = &mob.light {
+                self.light(&format!("{}.light", path), light);
+            }
+            if let Some(faction) = &mob.faction {
+                if !is_faction(raws, faction) {
+                    self.error(
+                        &format!("{}.faction", path),
+                        format!("no faction is called \"{}\"", faction),
+                    );
+                }
+            }
+            if let Some(gold) = &mob.gold {
+                self.dice(&format!("{}.gold", path), gold);
+            }
+            for (j, spell) in mob.spells.iter().flatten().enumerate() {
+                if !is_spell(raws, spell) {
+                    self.error(
+                        &format!("{}.spells[{}]", path, j),
+                        format!("no spell is called \"{}\"", spell),
+                    );
+                }
+            }
+            if mob
+                .temple
+                .as_ref()
+                .is_some_and(|temple| temple.curse_price < 0.0)
+            {
+                self.error(
+                    &format!("{}.temple.curse_price", path),
+                    "a temple can't charge less than 0 gold",
+                );
+            }
+            if let Some(recharge) = &mob.recharge {
+                if mob.vendor.is_none() {
+                    self.error(
+                        &format!("{}.recharge", path),
+                        "only a vendor can recharge items",
+                    );
+                }
+                if recharge.price < 0.0 {
+                    self.error(
+                        &format!("{}.recharge.price", path),
+                        "a recharge can't cost less than 0 gold",
+                    );
+                }
+            }
+            for (j, category) in mob.vendor.iter().flatten().enumerate() {
+                if !vendor_categories.contains(category) {
+                    self.error(
+                        &format!("{}.vendor[{}]", path, j),
+                        format!("no item has the vendor_category \"{}\"", category),
+                    );
+                }
+            }
+        }
+    }
+
+    fn props(&mut self, raws: &Raws) {
+        for (i, prop) in raws.props.iter().enumerate() {
+            let path = format!("props[{}]", i);
+            if let Some(renderable) = &prop.renderable {
+                self.renderable(&format!("{}.renderable", path), renderable);
+            }
+            if let Some(trigger) = &prop.entry_trigger {
+                self.effects(
+                    raws,
+                    &format!("{}.entry_trigger.effects", path),
+                    &trigger.effects,
+                    CONSUMABLE_ONLY_EFFECTS,
+                    "by a trap",
+                );
+            }
+            if let Some(light) = &prop.light {
+                self.light(&format!("{}.light", path), light);
+            }
+            if let Some(container) = &prop.container {
+                self.container(raws, &format!("{}.container", path), container);
+                if prop.entry_trigger.is_some() {
+                    self.error(
+                        &format!("{}.entry_trigger", path),
+                        "a container can't have an entry trigger; give it a trap instead",
+                    );
+                }
+            }
+        }
+    }
+
+    fn container(&mut self, raws: &Raws, path: &str, container: &PropContainer) {
+        if !raws
+            .loot_tables
+            .iter()
+            .any(|table| table.name == container.loot_table)
+        {
+            self.error(
+                &format!("{}.loot_table", path),
+                format!("no loot table is called \"{}\"", container.loot_table),
+            );
+        }
+        let items = container.items;
+        if items.n_dice + items.bonus < 0 {
+            self.error(
+                &format!("{}.items", path),
+                "a container can't hold fewer than 0 items",
+            );
+        }
+        if container.lock.is_some_and(|difficulty| difficulty < 1) {
+            self.error(
+                &format!("{}.lock", path),
+                "a lock needs a difficulty of 1 or more",
+            );
+        }
+        if let Some(trap) = &container.trap {
+            self.effects(
+                raws,
+                &format!("{}.trap.effects", path),
+                &trap.effects,
+                NOT_CONTAINER_TRAP_EFFECTS,
+                "by a container's trap",
+            );
+        }
+    }
+
+    fn spells(&mut self, raws: &Raws) {
+        for (i, spell) in raws.spells.iter().enumerate() {
+            let path = format!("spells[{}]", i);
+            if spell.mana_cost < 0 {
+                self.error(
+                    &format!("{}.mana_cost", path),
+                    "a spell can't cost less than 0 mana",
+                );
+            }
+            self.effects(
+                raws,
+                &format!("{}.effects", path),
+                &spell.effects,
+                NOT_SPELL_EFFECTS,
+                "by a spell",
+            );
+        }
+    }
+}
+
+fn is_spawnable(raws: &Raws, name: &str) -> bool {
+    raws.items.iter().any(|item| item.name == name)
+        || raws.mobs.iter().any(|mob| mob.name == name)
+        || raws.props.iter().any(|prop| prop.name == name)
+}
+
+fn is_spell(raws: &Raws, name: &str) -> bool {
+    raws.spells.iter().any(|spell| spell.name == name)
+}
+
+fn is_faction(raws: &Raws, name: &str) -> bool {
+    raws.faction_table
+        .iter()
+        .any(|faction| faction.name == name)
+}
+
+/// "a" or "an", to go before an effect's name.
+fn article(name: &str) -> &'static str {
+    if name.starts_with(['a', 'e', 'i', 'o', 'u']) {
+        "an"
+    } else {
+        "a"
+    }
+}
@@ -0,0 +1,109 @@
+use super::{gui::CheatMenuResult, PlayerCommand};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+
+pub const REPLAY_FILE: &str = "./replay.jsonl";
+
+/// Something the player chose that changes the world. Together with the run seed, the
+/// inputs of a run are enough to play it again exactly.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum ReplayInput {
+    /// A command from `player_input` or from an item menu
+    Command(PlayerCommand),
+    /// Buying from a vendor
+    Buy {
+        tag: String,
+        price: f32,
+    },
+    /// Selling the backpack item at this index to a vendor
+    Sell {
+        item: usize,
+    },
+    /// Paying a temple to lift the curse on the equipped item at this index
+    LiftCurse {
+        item: usize,
+        price: f32,
+    },
+    /// Paying a vendor to recharge the backpack item at this index
+    Recharge {
+        item: usize,
+        price: f32,
+    },
+    Cheat(CheatMenuResult),
+}
+
+#[derive(Serialize, Deserialize)]
+struct ReplayHeader {
+    seed: u64,
+}
+
+/// Writes a replay file as the game is played. The file holds a header line with the seed
+/// and then one input per line, written as it happens, so a crash still leaves every input
+/// up to it on disk.
+pub struct Recorder {
+    file: File,
+}
+
+impl Recorder {
+    pub fn start(seed: u64) -> Option<Recorder> {
+        let mut file = match File::create(REPLAY_FILE) {
+            Ok(file) => file,
+            Err(e) => {
+                rltk::console::log(format!("Unable to record replay: {}", e));
+                return None;
+            }
+        };
+        let header = serde_json::to_string(&ReplayHeader { seed }).unwrap();
+        writeln!(file, "{}", header).ok()?;
+        Some(Recorder { file })
+    }
+
+    pub fn record(&mut self, input: &ReplayInput) {
+        let line = serde_json::to_string(input).unwrap();
+        if let Err(e) = writeln!(self.file, "{}", line) {
+            rltk::console::log(format!("Unable to record replay: {}", e));
+        }
+    }
+}
+
+/// A recorded run, played back one input each time the game would ask the player.
+pub struct Replay {
+    pub seed: u64,
+    inputs: VecDeque<ReplayInput>,
+}
+
+impl Replay {
+    pub fn load(path: &str) -> Result<Replay, String> {
+        let file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+        let mut lines = BufReader::new(file).lines();
+
+        let header = match lines.next() {
+            Some(line) => line.map_err(|e| format!("{}: {}", path, e))?,
+            None => return Err(format!("{}: the replay is empty", path)),
+        };
+        let header: ReplayHeader = serde_json::from_str(&header)
+            .map_err(|e| format!("{}, line 1: bad header: {}", path, e))?;
+
+        let mut inputs = VecDeque::new();
+        for (i, line) in lines.enumerate() {
+            let line = line.map_err(|e| format!("{}: {}", path, e))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let input = serde_json::from_str(&line)
+                .map_err(|e| format!("{}, line {}: {}", path, i + 2, e))?;
+            inputs.push_back(input);
+        }
+
+        Ok(Replay {
+            seed: header.seed,
+            inputs,
+        })
+    }
+
+    pub fn next_input(&mut self) -> Option<ReplayInput> {
+        self.inputs.pop_front()
+    }
+}
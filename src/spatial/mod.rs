@@ -1,134 +1,118 @@
 use specs::prelude::*;
-use std::sync::Mutex;
 
-use crate::{is_tile_walkable, Map, RunState};
+use crate::{is_tile_walkable, RunState, TileType};
 
-struct SpatialMap {
+/// Which entities are on each tile of a map, and which tiles are blocked by the map or by
+/// something standing there. It lives on the `Map` and is built again every turn by
+/// `MapIndexingSystem`, so it isn't saved.
+#[derive(Default, Clone)]
+pub struct SpatialMap {
     blocked: Vec<(bool, bool)>,
     tile_content: Vec<Vec<(Entity, bool)>>,
 }
 
 impl SpatialMap {
-    fn new() -> Self {
+    pub fn new(map_tile_count: usize) -> Self {
         SpatialMap {
-            blocked: Vec::new(),
-            tile_content: Vec::new(),
+            blocked: vec![(false, false); map_tile_count],
+            tile_content: vec![Vec::new(); map_tile_count],
         }
     }
-}
 
-lazy_static! {
-    static ref SPATIAL_MAP: Mutex<SpatialMap> = Mutex::new(SpatialMap::new());
-}
+    pub fn clear(&mut self) {
+        self.blocked.iter_mut().for_each(|b| {
+            b.0 = false;
+            b.1 = false;
+        });
+        for content in self.tile_content.iter_mut() {
+            content.clear();
+        }
+    }
 
-pub fn set_size(map_tile_count: usize) {
-    let mut lock = SPATIAL_MAP.lock().unwrap();
-    lock.blocked = vec![(false, false); map_tile_count];
-    lock.tile_content = vec![Vec::new(); map_tile_count];
-}
+    pub fn populate_blocked_from_map(&mut self, tiles: &[TileType]) {
+        for (idx, tile) in tiles.iter().enumerate() {
+            self.blocked[idx].0 = !is_tile_walkable(*tile);
+        }
+    }
 
-pub fn clear() {
-    let mut lock = SPATIAL_MAP.lock().unwrap();
-    lock.blocked.iter_mut().for_each(|b| {
-        b.0 = false;
-        b.1 = false;
-    });
-    for content in lock.tile_content.iter_mut() {
-        content.clear();
+    pub fn index_entity(&mut self, entity: Entity, idx: usize, blocks_tile: bool) {
+        self.tile_content[idx].push((entity, blocks_tile));
+        if blocks_tile {
+            self.blocked[idx].1 = true;
+        }
     }
-}
 
-pub fn populate_blocked_from_map(map: &Map) {
-    let mut lock = SPATIAL_MAP.lock().unwrap();
-    for (idx, tile) in map.tiles.iter().enumerate() {
-        lock.blocked[idx].0 = !is_tile_walkable(*tile);
+    pub fn is_blocked(&self, idx: usize) -> bool {
+        self.blocked[idx].0 || self.blocked[idx].1
     }
-}
 
-pub fn index_entity(entity: Entity, idx: usize, blocks_tile: bool) {
-    let mut lock = SPATIAL_MAP.lock().unwrap();
-    lock.tile_content[idx].push((entity, blocks_tile));
-    if blocks_tile {
-        lock.blocked[idx].1 = true;
+    pub fn for_each_tile_content<F>(&self, idx: usize, mut f: F)
+    where
+        F: FnMut(Entity),
+    {
+        for entity in self.tile_content[idx].iter() {
+            f(entity.0);
+        }
     }
-}
 
-pub fn is_blocked(idx: usize) -> bool {
-    let lock = SPATIAL_MAP.lock().unwrap();
-    lock.blocked[idx].0 || lock.blocked[idx].1
-}
+    pub fn move_entity(&mut self, entity: Entity, moving_from: usize, moving_to: usize) {
+        let mut entity_blocks = false;
+        self.tile_content[moving_from].retain(|(e, blocks)| {
+            if *e == entity {
+                entity_blocks = *blocks;
+                false
+            } else {
+                true
+            }
+        });
+        self.tile_content[moving_to].push((entity, entity_blocks));
 
-pub fn for_each_tile_content<F>(idx: usize, mut f: F)
-where
-    F: FnMut(Entity),
-{
-    let lock = SPATIAL_MAP.lock().unwrap();
-    for entity in lock.tile_content[idx].iter() {
-        f(entity.0);
+        // Recalculate blocks for both tiles
+        let mut from_blocked = false;
+        let mut to_blocked = false;
+        self.tile_content[moving_from]
+            .iter()
+            .for_each(|(_, blocks)| {
+                if *blocks {
+                    from_blocked = true;
+                }
+            });
+        self.tile_content[moving_to].iter().for_each(|(_, blocks)| {
+            if *blocks {
+                to_blocked = true;
+            }
+        });
+        self.blocked[moving_from].1 = from_blocked;
+        self.blocked[moving_to].1 = to_blocked;
     }
-}
 
-pub fn move_entity(entity: Entity, moving_from: usize, moving_to: usize) {
-    let mut lock = SPATIAL_MAP.lock().unwrap();
-    let mut entity_blocks = false;
-    lock.tile_content[moving_from].retain(|(e, blocks)| {
-        if *e == entity {
-            entity_blocks = *blocks;
-            false
-        } else {
-            true
+    pub fn for_each_tile_content_with_gamemode<F>(&self, idx: usize, mut f: F) -> RunState
+    where
+        F: FnMut(Entity) -> Option<RunState>,
+    {
+        for entity in self.tile_content[idx].iter() {
+            if let Some(runstate) = f(entity.0) {
+                return runstate;
+            }
         }
-    });
-    lock.tile_content[moving_to].push((entity, entity_blocks));
+        RunState::AwaitingInput
+    }
 
-    // Recalculate blocks for both tiles
-    let mut from_blocked = false;
-    let mut to_blocked = false;
-    lock.tile_content[moving_from]
-        .iter()
-        .for_each(|(_, blocks)| {
+    pub fn remove_entity(&mut self, entity: Entity, idx: usize) {
+        self.tile_content[idx].retain(|(e, _)| *e != entity);
+        let mut from_blocked = false;
+        self.tile_content[idx].iter().for_each(|(_, blocks)| {
             if *blocks {
                 from_blocked = true;
             }
         });
-    lock.tile_content[moving_to].iter().for_each(|(_, blocks)| {
-        if *blocks {
-            to_blocked = true;
-        }
-    });
-    lock.blocked[moving_from].1 = from_blocked;
-    lock.blocked[moving_to].1 = to_blocked;
-}
-
-pub fn for_each_tile_content_with_gamemode<F>(idx: usize, mut f: F) -> RunState
-where
-    F: FnMut(Entity) -> Option<RunState>,
-{
-    let lock = SPATIAL_MAP.lock().unwrap();
-    for entity in lock.tile_content[idx].iter() {
-        if let Some(runstate) = f(entity.0) {
-            return runstate;
-        }
+        self.blocked[idx].1 = from_blocked;
     }
-    RunState::AwaitingInput
-}
 
-pub fn remove_entity(entity: Entity, idx: usize) {
-    let mut lock = SPATIAL_MAP.lock().unwrap();
-    lock.tile_content[idx].retain(|(e, _)| *e != entity);
-    let mut from_blocked = false;
-    lock.tile_content[idx].iter().for_each(|(_, blocks)| {
-        if *blocks {
-            from_blocked = true;
-        }
-    });
-    lock.blocked[idx].1 = from_blocked;
-}
-
-pub fn get_tile_content_clone(idx: usize) -> Vec<Entity> {
-    let lock = SPATIAL_MAP.lock().unwrap();
-    lock.tile_content[idx]
-        .iter()
-        .map(|(entity, _)| *entity)
-        .collect()
+    pub fn get_tile_content_clone(&self, idx: usize) -> Vec<Entity> {
+        self.tile_content[idx]
+            .iter()
+            .map(|(entity, _)| *entity)
+            .collect()
+    }
 }
@@ -1,14 +1,14 @@
 use super::{
     attribute_bonus, mana_at_level, player_hp_at_level, random_table::RandomTable, raws::*,
     Attribute, Attributes, EntryTrigger, EquipmentChanged, Faction, HungerClock, HungerState,
-    Initiative, LightSource, Map, MasterDungeonMap, Name, OtherLevelPosition, Player, Pool, Pools,
-    Position, Rect, Renderable, SerializeMe, SingleActivation, Skill, Skills, TeleportTo, TileType,
-    Viewshed,
+    Initiative, KnownSpells, LightSource, Map, MasterDungeonMap, Name, OtherLevelPosition, Player,
+    Pool, Pools, Position, Rect, Renderable, SerializeMe, SingleActivation, Skill, Skills,
+    TeleportTo, TileType, Viewshed,
 };
 use rltk::{RandomNumberGenerator, RGB};
 use specs::prelude::*;
 use specs::saveload::{MarkedBuilder, SimpleMarker};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 const MAX_MONSTERS: i32 = 4;
 
@@ -16,11 +16,14 @@ const MAX_MONSTERS: i32 = 4;
 pub fn player(ecs: &mut World, player_x: i32, player_y: i32) -> Entity {
     let mut skills = Skills {
         skills: HashMap::new(),
+        practice: HashMap::new(),
+        modifiers: HashMap::new(),
     };
 
     skills.skills.insert(Skill::Melee, 1);
     skills.skills.insert(Skill::Defense, 1);
     skills.skills.insert(Skill::Magic, 1);
+    skills.skills.insert(Skill::Ranged, 1);
 
     let player = ecs
         .create_entity()
@@ -85,6 +88,8 @@ pub fn player(ecs: &mut World, player_x: i32, player_y: i32) -> Entity {
             total_initiative_penalty: 0.0,
             gold: 0.0,
             god_mode: false,
+            hp_regen_progress: 0,
+            mana_regen_progress: 0,
         })
         .with(LightSource {
             color: rltk::RGB::from_f32(1.0, 1.0, 0.5),
@@ -95,6 +100,9 @@ pub fn player(ecs: &mut World, player_x: i32, player_y: i32) -> Entity {
             name: "Player".to_string(),
         })
         .with(EquipmentChanged {})
+        .with(KnownSpells {
+            spells: vec!["Zap".to_string()],
+        })
         .marked::<SimpleMarker<SerializeMe>>()
         .build();
 
@@ -170,7 +178,7 @@ pub fn spawn_region(
     spawn_list: &mut Vec<(usize, String)>,
 ) {
     let spawn_table = room_table(map_depth);
-    let mut spawn_points: HashMap<usize, String> = HashMap::new();
+    let mut spawn_points: BTreeMap<usize, String> = BTreeMap::new();
     let mut areas: Vec<usize> = Vec::from(area);
 
     {
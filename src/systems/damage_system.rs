@@ -1,6 +1,6 @@
 use crate::{
-    gamelog::GameLog, mana_at_level, player_hp_at_level, Attributes, Equipped, InBackpack,
-    LootTable, Map, Name, ParticleBuilder, Player, Pools, Position, RunState, SufferDamage,
+    gamelog::GameLog, level_up, Attributes, Equipped, InBackpack, LevelUpPoints, LootTable, Map,
+    Name, ParticleBuilder, Player, Pools, Position, RunState, SufferDamage, Trapped,
 };
 use specs::prelude::*;
 
@@ -17,6 +17,7 @@ impl<'a> System<'a> for DamageSystem {
         ReadStorage<'a, Attributes>,
         WriteExpect<'a, GameLog>,
         WriteExpect<'a, ParticleBuilder>,
+        WriteStorage<'a, LevelUpPoints>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -30,6 +31,7 @@ impl<'a> System<'a> for DamageSystem {
             attributes,
             mut logs,
             mut particles,
+            mut level_up_points,
         ) = data;
 
         let player_pos = positions.get(*player).unwrap().clone();
@@ -54,7 +56,7 @@ impl<'a> System<'a> for DamageSystem {
 
                     if let Some(pos) = pos {
                         let idx = map.xy_idx(pos.x, pos.y);
-                        crate::spatial::remove_entity(entity, idx);
+                        map.spatial.remove_entity(entity, idx);
                     }
                 }
             }
@@ -65,25 +67,13 @@ impl<'a> System<'a> for DamageSystem {
             let player_attributes = attributes.get(*player).unwrap();
             player_stats.xp += xp_gain;
             player_stats.gold += gold_gain;
-            if player_stats.xp >= player_stats.level * 1000 {
-                // We've gone up a level
-                player_stats.level += 1;
-                player_stats.hit_points.max = player_hp_at_level(
-                    player_attributes.fitness.base + player_attributes.fitness.modifiers,
-                    player_stats.level,
-                );
-                player_stats.hit_points.current = player_stats.hit_points.max;
-                player_stats.mana.max = mana_at_level(
-                    player_attributes.intelligence.base + player_attributes.intelligence.modifiers,
-                    player_stats.level,
-                );
-                player_stats.mana.current = player_stats.mana.max;
-
-                logs.entries.push(format!(
-                    "Congratulations, you are now level {}",
-                    player_stats.level
-                ));
-
+            if level_up(
+                *player,
+                player_stats,
+                player_attributes,
+                &mut level_up_points,
+                &mut logs,
+            ) {
                 for i in 0..10 {
                     if player_pos.y - i > 1 {
                         particles.request(
@@ -197,7 +187,15 @@ pub fn delete_the_dead(ecs: &mut World) {
         }
     }
 
-    for victim in dead {
+    // A container's trap goes with it
+    let traps: Vec<Entity> = {
+        let trapped = ecs.read_storage::<Trapped>();
+        dead.iter()
+            .filter_map(|victim| trapped.get(*victim).map(|t| t.trap))
+            .collect()
+    };
+
+    for victim in dead.into_iter().chain(traps) {
         ecs.delete_entity(victim).expect("Unable to delete");
     }
 }
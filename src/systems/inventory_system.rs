@@ -1,11 +1,109 @@
 use crate::{
-    gamelog::GameLog, AreaOfEffect, Confusion, Consumable, EquipmentChanged, Equippable, Equipped,
-    HungerClock, HungerState, IdentifiedItem, InBackpack, InflictsDamage, Item, MagicItem,
-    MagicMapper, Map, MasterDungeonMap, Name, ObfuscatedName, ParticleBuilder, Pools, Position,
-    ProvidesFood, ProvidesHealing, RunState, SufferDamage, TownPortal, WantsToDropItem,
-    WantsToPickupItem, WantsToRemoveItem, WantsToUseItem,
+    effects::{aoe_tiles, EffectQueue, EffectType, Targets},
+    gamelog::GameLog,
+    AreaOfEffect, CursedItem, EquipmentChanged, Equippable, Equipped, IdentifiedItem, InBackpack,
+    Item, MagicItem, Map, MasterDungeonMap, Name, ObfuscatedName, Position, Stackable,
+    WantsToDropItem, WantsToPickupItem, WantsToRemoveItem, WantsToUseItem,
 };
 use specs::prelude::*;
+use specs::storage::MaskedStorage;
+use std::ops::Deref;
+
+/// The stack in `owner`'s backpack that `item` would join: another stackable item of the
+/// same name.
+pub fn matching_stack<B, N, S>(
+    item: Entity,
+    owner: Entity,
+    entities: &Entities,
+    backpack: &Storage<InBackpack, B>,
+    names: &Storage<Name, N>,
+    stacks: &Storage<Stackable, S>,
+) -> Option<Entity>
+where
+    B: Deref<Target = MaskedStorage<InBackpack>>,
+    N: Deref<Target = MaskedStorage<Name>>,
+    S: Deref<Target = MaskedStorage<Stackable>>,
+{
+    stacks.get(item)?;
+    let name = &names.get(item)?.name;
+    (entities, backpack, names, stacks)
+        .join()
+        .find(|(other, carried, other_name, _)| {
+            *other != item && carried.owner == owner && other_name.name == *name
+        })
+        .map(|(other, _, _, _)| other)
+}
+
+/// Adds a newly carried item to the matching stack in its owner's backpack, if there is
+/// one. Returns the entity the item ended up as.
+pub fn stack_carried(ecs: &mut World, item: Entity) -> Entity {
+    let Some(owner) = ecs.read_storage::<InBackpack>().get(item).map(|c| c.owner) else {
+        return item;
+    };
+    let stack = matching_stack(
+        item,
+        owner,
+        &ecs.entities(),
+        &ecs.read_storage::<InBackpack>(),
+        &ecs.read_storage::<Name>(),
+        &ecs.read_storage::<Stackable>(),
+    );
+    let Some(stack) = stack else {
+        return item;
+    };
+    let mut stacks = ecs.write_storage::<Stackable>();
+    let quantity = stacks.get(item).map_or(1, |s| s.quantity);
+    stacks.get_mut(stack).unwrap().quantity += quantity;
+    drop(stacks);
+    ecs.delete_entity(item).expect("Unable to delete");
+    stack
+}
+
+/// Takes one item off a stack in a backpack, as a new item beside it, and returns it. An
+/// item that isn't stacked comes as it is; so does a whole stack with no raws template to
+/// make the one from.
+pub fn split_one(ecs: &mut World, stack: Entity) -> Entity {
+    let quantity = ecs
+        .read_storage::<Stackable>()
+        .get(stack)
+        .map_or(1, |s| s.quantity);
+    let owner = ecs.read_storage::<InBackpack>().get(stack).map(|c| c.owner);
+    let (Some(owner), Some(name), true) = (
+        owner,
+        ecs.read_storage::<Name>()
+            .get(stack)
+            .map(|n| n.name.clone()),
+        quantity > 1,
+    ) else {
+        return stack;
+    };
+    let Some(one) = crate::raws::spawn_named_item(
+        &crate::raws::RAWS.lock().unwrap(),
+        ecs,
+        &name,
+        crate::raws::SpawnType::Carried { by: owner },
+    ) else {
+        return stack;
+    };
+    ecs.write_storage::<Stackable>()
+        .get_mut(stack)
+        .unwrap()
+        .quantity -= 1;
+    one
+}
+
+/// Uses up one of an item: one off its stack, or the item itself if it's the last.
+pub fn consume_one(ecs: &World, item: Entity) {
+    if let Some(stack) = ecs
+        .write_storage::<Stackable>()
+        .get_mut(item)
+        .filter(|stack| stack.quantity > 1)
+    {
+        stack.quantity -= 1;
+    } else {
+        ecs.entities().delete(item).expect("Delete Failed");
+    }
+}
 
 pub struct ItemCollectionSystem {}
 
@@ -22,6 +120,8 @@ impl<'a> System<'a> for ItemCollectionSystem {
         ReadStorage<'a, MagicItem>,
         ReadStorage<'a, ObfuscatedName>,
         ReadExpect<'a, MasterDungeonMap>,
+        Entities<'a>,
+        WriteStorage<'a, Stackable>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -36,18 +136,34 @@ impl<'a> System<'a> for ItemCollectionSystem {
             magic_items,
             obfuscated_names,
             dungeon_map,
+            entities,
+            mut stacks,
         ) = data;
 
         for pickup in wants_pickup.join() {
             positions.remove(pickup.item);
-            backpack
-                .insert(
-                    pickup.item,
-                    InBackpack {
-                        owner: pickup.collected_by,
-                    },
-                )
-                .expect("Unable to insert backpack entry");
+            // Joining a stack already carried uses the item up into it
+            if let Some(stack) = matching_stack(
+                pickup.item,
+                pickup.collected_by,
+                &entities,
+                &backpack,
+                &names,
+                &stacks,
+            ) {
+                let quantity = stacks.get(pickup.item).map_or(1, |s| s.quantity);
+                stacks.get_mut(stack).unwrap().quantity += quantity;
+                entities.delete(pickup.item).expect("Delete failed");
+            } else {
+                backpack
+                    .insert(
+                        pickup.item,
+                        InBackpack {
+                            owner: pickup.collected_by,
+                        },
+                    )
+                    .expect("Unable to insert backpack entry");
+            }
             dirty
                 .insert(pickup.collected_by, EquipmentChanged {})
                 .expect("Unable to insert");
@@ -77,32 +193,20 @@ impl<'a> System<'a> for ItemUseSystem {
     type SystemData = (
         ReadExpect<'a, Entity>,
         WriteExpect<'a, GameLog>,
-        WriteExpect<'a, Map>,
+        ReadExpect<'a, Map>,
         Entities<'a>,
         WriteStorage<'a, WantsToUseItem>,
         ReadStorage<'a, Name>,
-        ReadStorage<'a, Consumable>,
-        ReadStorage<'a, ProvidesHealing>,
-        ReadStorage<'a, InflictsDamage>,
-        WriteStorage<'a, Pools>,
-        WriteStorage<'a, SufferDamage>,
         ReadStorage<'a, AreaOfEffect>,
-        WriteStorage<'a, Confusion>,
         ReadStorage<'a, Equippable>,
         WriteStorage<'a, Equipped>,
         WriteStorage<'a, InBackpack>,
-        WriteExpect<'a, ParticleBuilder>,
-        ReadStorage<'a, Position>,
-        ReadStorage<'a, ProvidesFood>,
-        WriteStorage<'a, HungerClock>,
-        ReadStorage<'a, MagicMapper>,
-        WriteExpect<'a, RunState>,
         WriteStorage<'a, EquipmentChanged>,
-        ReadStorage<'a, TownPortal>,
         WriteStorage<'a, IdentifiedItem>,
+        ReadStorage<'a, CursedItem>,
+        WriteExpect<'a, EffectQueue>,
     );
 
-    #[allow(clippy::cognitive_complexity)]
     fn run(&mut self, data: Self::SystemData) {
         let (
             player_entity,
@@ -111,71 +215,59 @@ impl<'a> System<'a> for ItemUseSystem {
             entities,
             mut wants_use,
             names,
-            consumables,
-            healing,
-            inflict_damage,
-            mut combat_stats,
-            mut suffer_damage,
             aoe,
-            mut confused,
             equippable,
             mut equipped,
             mut backpack,
-            mut particle_builder,
-            positions,
-            provides_food,
-            mut hunger_clock,
-            magic_mapper,
-            mut runstate,
             mut dirty,
-            town_portal,
             mut identified_items,
+            cursed,
+            mut effect_queue,
         ) = data;
 
         for (entity, useitem) in (&entities, &wants_use).join() {
+            // A cursed item won't come off to make room, and what was to go on in its place
+            // isn't put on, so nothing is learned about it either
+            if let Some(can_equip) = equippable.get(useitem.item) {
+                if let Some((_, _, stuck)) = (&equipped, &cursed, &names)
+                    .join()
+                    .find(|(worn, _, _)| worn.owner == entity && worn.slot == can_equip.slot)
+                {
+                    if entity == *player_entity {
+                        log.entries
+                            .push(format!("You can't remove the {}; it's cursed!", stuck.name));
+                    }
+                    continue;
+                }
+            }
+
             dirty
                 .insert(entity, EquipmentChanged {})
                 .expect("Unable to insert");
-            let mut used_item = true;
-            let mut targets: Vec<Entity> = Vec::new();
-            // Targeting
-            match useitem.target {
-                None => {
-                    targets.push(*player_entity);
-                }
-                Some(target) => {
-                    let area_effect = aoe.get(useitem.item);
-                    match area_effect {
-                        None => {
-                            // Single target in tile
-                            let idx = map.xy_idx(target.x, target.y);
-                            crate::spatial::for_each_tile_content(idx, |mob| targets.push(mob));
-                        }
-                        Some(area_effect) => {
-                            // AOE
-                            let mut blast_tiles =
-                                rltk::field_of_view(target, area_effect.radius, &*map);
-                            blast_tiles.retain(|p| {
-                                p.x > 0 && p.x < map.width - 1 && p.y > 0 && p.y < map.height - 1
-                            });
-                            for tile_idx in blast_tiles.iter() {
-                                let idx = map.xy_idx(tile_idx.x, tile_idx.y);
-                                crate::spatial::for_each_tile_content(idx, |mob| targets.push(mob));
-                                particle_builder.request(
-                                    tile_idx.x,
-                                    tile_idx.y,
-                                    rltk::RGB::named(rltk::ORANGE),
-                                    rltk::RGB::named(rltk::BLACK),
-                                    rltk::to_cp437('░'),
-                                    200.0,
-                                );
+
+            // What the item does is up to the effects system
+            effect_queue.add_effect(
+                Some(entity),
+                EffectType::ItemUse { item: useitem.item },
+                match useitem.target {
+                    None => Targets::Single { target: entity },
+                    Some(target) => {
+                        if let Some(aoe) = aoe.get(useitem.item) {
+                            Targets::Tiles {
+                                tiles: aoe_tiles(&map, target, aoe.radius),
+                            }
+                        } else {
+                            Targets::Tile {
+                                tile_idx: map.xy_idx(target.x, target.y) as i32,
                             }
                         }
                     }
-                }
-            }
-            // Identify
-            if entity == *player_entity {
+                },
+            );
+
+            // Putting something on shows what it is; anything else is identified by what it
+            // does, once the effect goes off
+            if entity == *player_entity && equippable.get(useitem.item).is_some() {
                 identified_items
                     .insert(
                         entity,
@@ -185,212 +277,51 @@ impl<'a> System<'a> for ItemUseSystem {
                     )
                     .expect("Unable to insert");
             }
+
             // If it is equippable, then we want to equip it - and unequip whatever else was in that slot
-            let item_equippable = equippable.get(useitem.item);
-            match item_equippable {
-                None => {}
-                Some(can_equip) => {
-                    let target_slot = can_equip.slot;
-                    let target = targets[0];
-
-                    // Remove any items the target has in the item's slot
-                    let mut to_unequip: Vec<Entity> = Vec::new();
-                    for (item_entity, already_equipped, name) in
-                        (&entities, &equipped, &names).join()
-                    {
-                        if already_equipped.owner == target && already_equipped.slot == target_slot
-                        {
-                            to_unequip.push(item_entity);
-                            if entity == *player_entity {
-                                log.entries.push(format!("You unequip {}.", name.name));
-                            }
+            if let Some(can_equip) = equippable.get(useitem.item) {
+                let target_slot = can_equip.slot;
+
+                // Remove any items the user has in the item's slot
+                let mut to_unequip: Vec<Entity> = Vec::new();
+                for (item_entity, already_equipped, name) in (&entities, &equipped, &names).join() {
+                    if already_equipped.owner == entity && already_equipped.slot == target_slot {
+                        to_unequip.push(item_entity);
+                        if entity == *player_entity {
+                            log.entries.push(format!("You unequip {}.", name.name));
                         }
                     }
-                    for item in to_unequip.iter() {
-                        equipped.remove(*item);
-                        backpack
-                            .insert(*item, InBackpack { owner: target })
-                            .expect("Unable to insert backpack entry");
-                    }
-                    // Wield the item
-                    equipped
-                        .insert(
-                            useitem.item,
-                            Equipped {
-                                owner: target,
-                                slot: target_slot,
-                            },
-                        )
-                        .expect("Unable to insert equipped component");
-                    backpack.remove(useitem.item);
-                    if target == *player_entity {
-                        log.entries.push(format!(
-                            "You equip {}.",
-                            names.get(useitem.item).unwrap().name
-                        ));
-                    }
                 }
-            }
-            // If it is edible, eat it!
-            let item_edible = provides_food.get(useitem.item);
-            match item_edible {
-                None => {}
-                Some(_) => {
-                    used_item = true;
-                    let target = targets[0];
-                    let hc = hunger_clock.get_mut(target);
-                    if let Some(hc) = hc {
-                        hc.state = HungerState::WellFed;
-                        hc.duration = 20;
+                for item in to_unequip.iter() {
+                    equipped.remove(*item);
+                    backpack
+                        .insert(*item, InBackpack { owner: entity })
+                        .expect("Unable to insert backpack entry");
+                }
+                // Wield the item
+                equipped
+                    .insert(
+                        useitem.item,
+                        Equipped {
+                            owner: entity,
+                            slot: target_slot,
+                        },
+                    )
+                    .expect("Unable to insert equipped component");
+                backpack.remove(useitem.item);
+                if entity == *player_entity {
+                    log.entries.push(format!(
+                        "You equip {}.",
+                        names.get(useitem.item).unwrap().name
+                    ));
+                    if cursed.get(useitem.item).is_some() {
                         log.entries.push(format!(
-                            "You eat the {}.",
+                            "The {} clings to you; it's cursed!",
                             names.get(useitem.item).unwrap().name
                         ));
                     }
                 }
             }
-            // If it is a magic mapper...
-            let is_mapper = magic_mapper.get(useitem.item);
-            match is_mapper {
-                None => {}
-                Some(_) => {
-                    used_item = true;
-                    log.entries.push("The map is revealed to you!".to_string());
-                    *runstate = RunState::MagicMapReveal { row: 0 };
-                }
-            }
-            // If its a town portal...
-            if let Some(_town_portal) = town_portal.get(useitem.item) {
-                if map.depth == 1 {
-                    used_item = false;
-                    log.entries
-                        .push("You are already in town, so the scroll does nothing".to_string());
-                } else {
-                    used_item = true;
-                    log.entries
-                        .push("You are teleported back to town!".to_string());
-                    *runstate = RunState::TownPortal;
-                }
-            }
-            // If it heals, apply the healing
-            let item_heals = healing.get(useitem.item);
-            match item_heals {
-                None => {}
-                Some(healer) => {
-                    used_item = false;
-                    for target in targets.iter() {
-                        let stats = combat_stats.get_mut(*target);
-                        if let Some(stats) = stats {
-                            stats.hit_points.current = stats
-                                .hit_points
-                                .max
-                                .min(stats.hit_points.current + healer.heal_amount);
-                            if entity == *player_entity {
-                                log.entries.push(format!(
-                                    "You use the {}, healing {} hp.",
-                                    names.get(useitem.item).unwrap().name,
-                                    healer.heal_amount
-                                ));
-                            }
-                            used_item = true;
-
-                            let pos = positions.get(*target);
-                            if let Some(pos) = pos {
-                                particle_builder.request(
-                                    pos.x,
-                                    pos.y,
-                                    rltk::RGB::named(rltk::GREEN),
-                                    rltk::RGB::named(rltk::BLACK),
-                                    rltk::to_cp437('♥'),
-                                    200.0,
-                                );
-                            }
-                        }
-                    }
-                }
-            }
-            // If it inflicts damage, apply it to the target cell
-            let item_damages = inflict_damage.get(useitem.item);
-            match item_damages {
-                None => {}
-                Some(damage) => {
-                    let target_point = useitem.target.unwrap();
-                    let idx = map.xy_idx(target_point.x, target_point.y);
-                    used_item = false;
-                    crate::spatial::for_each_tile_content(idx, |mob| {
-                        SufferDamage::new_damage(&mut suffer_damage, mob, damage.damage, true);
-                        if entity == *player_entity {
-                            let mob_name = names.get(mob).unwrap();
-                            let item_name = names.get(useitem.item).unwrap();
-                            log.entries.push(format!(
-                                "You use {} on {}, inflicting {} hp.",
-                                item_name.name, mob_name.name, damage.damage
-                            ));
-                            let pos = positions.get(mob);
-                            if let Some(pos) = pos {
-                                particle_builder.request(
-                                    pos.x,
-                                    pos.y,
-                                    rltk::RGB::named(rltk::RED),
-                                    rltk::RGB::named(rltk::BLACK),
-                                    rltk::to_cp437('‼'),
-                                    200.0,
-                                )
-                            }
-                        }
-                        used_item = true;
-                    });
-                }
-            }
-            // Can it pass along confusion?
-            let mut add_confusion = Vec::new();
-            {
-                let causes_confusion = confused.get(useitem.item);
-                match causes_confusion {
-                    None => {}
-                    Some(confusion) => {
-                        // used_item = false;
-                        for mob in targets.iter() {
-                            add_confusion.push((*mob, confusion.turns));
-                            if entity == *player_entity {
-                                let mob_name = names.get(*mob).unwrap();
-                                let item_name = names.get(useitem.item).unwrap();
-                                log.entries.push(format!(
-                                    "You use {} on {}, confusing them.",
-                                    item_name.name, mob_name.name
-                                ));
-                                let pos = positions.get(*mob);
-                                if let Some(pos) = pos {
-                                    particle_builder.request(
-                                        pos.x,
-                                        pos.y,
-                                        rltk::RGB::named(rltk::MAGENTA),
-                                        rltk::RGB::named(rltk::BLACK),
-                                        rltk::to_cp437('?'),
-                                        200.0,
-                                    )
-                                }
-                            }
-                        }
-                        used_item = true;
-                    }
-                }
-            }
-            for mob in add_confusion.iter() {
-                confused
-                    .insert(mob.0, Confusion { turns: mob.1 })
-                    .expect("Unable to insert status");
-            }
-            // If its a consumable, we delete it on use
-            if used_item {
-                let consumable = consumables.get(useitem.item);
-                match consumable {
-                    None => {}
-                    Some(_) => {
-                        entities.delete(useitem.item).expect("Delete failed");
-                    }
-                }
-            }
         }
         wants_use.clear();
     }
@@ -476,16 +407,43 @@ impl<'a> System<'a> for ItemRemoveSystem {
         WriteStorage<'a, WantsToRemoveItem>,
         WriteStorage<'a, Equipped>,
         WriteStorage<'a, InBackpack>,
+        WriteStorage<'a, EquipmentChanged>,
+        ReadStorage<'a, CursedItem>,
+        ReadStorage<'a, Name>,
+        ReadExpect<'a, Entity>,
+        WriteExpect<'a, GameLog>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut wants_remove, mut equipped, mut backpack) = data;
+        let (
+            entities,
+            mut wants_remove,
+            mut equipped,
+            mut backpack,
+            mut dirty,
+            cursed,
+            names,
+            player_entity,
+            mut log,
+        ) = data;
 
         for (entity, to_remove) in (&entities, &wants_remove).join() {
+            if cursed.get(to_remove.item).is_some() {
+                if entity == *player_entity {
+                    if let Some(name) = names.get(to_remove.item) {
+                        log.entries
+                            .push(format!("You can't remove the {}; it's cursed!", name.name));
+                    }
+                }
+                continue;
+            }
             equipped.remove(to_remove.item);
             backpack
                 .insert(to_remove.item, InBackpack { owner: entity })
                 .expect("Unable to insert backpack");
+            dirty
+                .insert(entity, EquipmentChanged {})
+                .expect("Unable to insert");
         }
         wants_remove.clear();
     }
@@ -521,6 +479,7 @@ impl<'a> System<'a> for ItemIdentificationSystem {
                 && crate::raws::is_tag_magic(&id.name)
             {
                 dungeon_master.identified_items.insert(id.name.clone());
+                dungeon_master.item_notes.remove(&id.name);
 
                 for (entity, _item, name) in (&entities, &items, &names).join() {
                     if name.name == id.name {
@@ -534,6 +493,30 @@ impl<'a> System<'a> for ItemIdentificationSystem {
     }
 }
 
+/// Makes every item with this name known for what it is, as the identification system
+/// does, without waiting for it to run.
+pub fn identify_kind(ecs: &mut World, name: &str) {
+    if !crate::raws::is_tag_magic(name) {
+        return;
+    }
+    let mut dungeon_master = ecs.fetch_mut::<MasterDungeonMap>();
+    dungeon_master.identified_items.insert(name.to_string());
+    dungeon_master.item_notes.remove(name);
+
+    let mut obfuscated_names = ecs.write_storage::<ObfuscatedName>();
+    for (entity, _item, item_name) in (
+        &ecs.entities(),
+        &ecs.read_storage::<Item>(),
+        &ecs.read_storage::<Name>(),
+    )
+        .join()
+    {
+        if item_name.name == name {
+            obfuscated_names.remove(entity);
+        }
+    }
+}
+
 fn obfuscate_name(
     item: Entity,
     names: &ReadStorage<Name>,
@@ -1,11 +1,11 @@
-use crate::{spatial, BlocksTile, Map, Pools, Position};
+use crate::{BlocksTile, Map, Pools, Position};
 use specs::prelude::*;
 
 pub struct MapIndexingSystem {}
 
 impl<'a> System<'a> for MapIndexingSystem {
     type SystemData = (
-        ReadExpect<'a, Map>,
+        WriteExpect<'a, Map>,
         ReadStorage<'a, Position>,
         ReadStorage<'a, BlocksTile>,
         ReadStorage<'a, Pools>,
@@ -13,10 +13,10 @@ impl<'a> System<'a> for MapIndexingSystem {
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (map, position, blockers, pools, entities) = data;
+        let (mut map, position, blockers, pools, entities) = data;
 
-        spatial::clear();
-        spatial::populate_blocked_from_map(&map);
+        map.clear_content_index();
+        map.populate_blocked();
         for (entity, position) in (&entities, &position).join() {
             let mut alive = true;
             if let Some(pools) = pools.get(entity) {
@@ -24,7 +24,8 @@ impl<'a> System<'a> for MapIndexingSystem {
             }
             if alive {
                 let idx = map.xy_idx(position.x, position.y);
-                spatial::index_entity(entity, idx, blockers.get(entity).is_some());
+                map.spatial
+                    .index_entity(entity, idx, blockers.get(entity).is_some());
             }
         }
     }
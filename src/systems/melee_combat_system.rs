@@ -1,9 +1,10 @@
 use crate::{
-    effects::{add_effect, EffectType, Targets},
+    effects::{EffectQueue, EffectType, Targets},
     gamelog::GameLog,
     particle_system::ParticleBuilder,
-    skill_bonus, Attributes, EquipmentSlot, Equipped, HungerClock, HungerState, MeleeWeapon, Name,
-    NaturalAttackDefense, Pools, Position, Skill, Skills, WantsToMelee, WeaponAttribute, Wearable,
+    practice_skills, skill_bonus, Attributes, EquipmentSlot, Equipped, HungerClock, HungerState,
+    MeleeWeapon, Name, NaturalAttackDefense, Pools, Position, Skill, Skills, WantsToMelee,
+    WeaponAttribute, Wearable,
 };
 use specs::prelude::*;
 
@@ -16,7 +17,7 @@ impl<'a> System<'a> for MeleeCombatSystem {
         WriteStorage<'a, WantsToMelee>,
         ReadStorage<'a, Name>,
         ReadStorage<'a, Attributes>,
-        ReadStorage<'a, Skills>,
+        WriteStorage<'a, Skills>,
         WriteExpect<'a, ParticleBuilder>,
         ReadStorage<'a, Position>,
         ReadStorage<'a, HungerClock>,
@@ -26,6 +27,8 @@ impl<'a> System<'a> for MeleeCombatSystem {
         ReadStorage<'a, MeleeWeapon>,
         ReadStorage<'a, Wearable>,
         ReadStorage<'a, NaturalAttackDefense>,
+        ReadExpect<'a, Entity>,
+        WriteExpect<'a, EffectQueue>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -35,7 +38,7 @@ impl<'a> System<'a> for MeleeCombatSystem {
             mut wants_melee,
             names,
             attributes,
-            skills,
+            mut skills,
             mut particle_builder,
             positions,
             hunger_clock,
@@ -45,8 +48,13 @@ impl<'a> System<'a> for MeleeCombatSystem {
             melee_weapon,
             wearables,
             natural,
+            player,
+            mut effect_queue,
         ) = data;
 
+        // Hitting trains Melee, and being attacked at all trains Defense
+        let mut practiced: Vec<(Entity, Skill)> = Vec::new();
+
         for (entity, wants_melee, name, attacker_attributes, attacker_skills, attacker_pools) in (
             &entities,
             &wants_melee,
@@ -132,7 +140,9 @@ impl<'a> System<'a> for MeleeCombatSystem {
                 let armor_class =
                     base_armor_class + armor_quickness_bonus + armor_skill_bonus + armor_item_bonus;
 
+                practiced.push((wants_melee.target, Skill::Defense));
                 if natural_roll != 1 && (natural_roll == 20 || modified_hit_roll > armor_class) {
+                    practiced.push((entity, Skill::Melee));
                     // Target hit! Until we support weapons, we're going with 1d4
                     let base_damage =
                         rng.roll_dice(weapon_info.damage_n_dice, weapon_info.damage_die_type);
@@ -146,7 +156,7 @@ impl<'a> System<'a> for MeleeCombatSystem {
                             + skill_damage_bonus
                             + weapon_damage_bonus,
                     );
-                    add_effect(
+                    effect_queue.add_effect(
                         Some(entity),
                         EffectType::Damage { amount: damage },
                         Targets::Single {
@@ -204,5 +214,6 @@ impl<'a> System<'a> for MeleeCombatSystem {
         }
 
         wants_melee.clear();
+        practice_skills(&practiced, &mut skills, &pools, *player, &mut log);
     }
 }
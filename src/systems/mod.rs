@@ -6,12 +6,16 @@ pub mod map_indexing_system;
 pub mod melee_combat_system;
 pub mod movement_system;
 pub mod particle_system;
+pub mod ranged_combat_system;
+pub mod regeneration_system;
 pub mod saveload_system;
+pub mod spell_system;
 pub mod trigger_system;
 pub mod visibility_system;
 
 pub use self::{
     damage_system::*, hunger_system::*, inventory_system::*, lighting_system::*,
-    map_indexing_system::*, melee_combat_system::*, particle_system::*, saveload_system::*,
-    trigger_system::*, visibility_system::*,
+    map_indexing_system::*, melee_combat_system::*, particle_system::*, ranged_combat_system::*,
+    regeneration_system::*, saveload_system::*, spell_system::*, trigger_system::*,
+    visibility_system::*,
 };
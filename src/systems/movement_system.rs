@@ -8,7 +8,7 @@ pub struct MovementSystem {}
 impl<'a> System<'a> for MovementSystem {
     #[allow(clippy::type_complexity)]
     type SystemData = (
-        ReadExpect<'a, Map>,
+        WriteExpect<'a, Map>,
         WriteStorage<'a, Position>,
         Entities<'a>,
         WriteStorage<'a, ApplyMove>,
@@ -22,7 +22,7 @@ impl<'a> System<'a> for MovementSystem {
 
     fn run(&mut self, data: Self::SystemData) {
         let (
-            map,
+            mut map,
             mut positions,
             entities,
             mut apply_move,
@@ -55,7 +55,7 @@ impl<'a> System<'a> for MovementSystem {
             } else if let Some(pos) = positions.get(entity) {
                 let idx = map.xy_idx(pos.x, pos.y);
                 let destination_idx = map.xy_idx(teleport.destination_x, teleport.destination_y);
-                crate::spatial::move_entity(entity, idx, destination_idx);
+                map.spatial.move_entity(entity, idx, destination_idx);
                 other_level_position
                     .insert(
                         entity,
@@ -75,7 +75,7 @@ impl<'a> System<'a> for MovementSystem {
         for (entity, movement, pos) in (&entities, &apply_move, &mut positions).join() {
             let start_idx = map.xy_idx(pos.x, pos.y);
             let destination_idx = movement.destination_idx;
-            crate::spatial::move_entity(entity, start_idx, destination_idx);
+            map.spatial.move_entity(entity, start_idx, destination_idx);
             pos.x = destination_idx as i32 % map.width;
             pos.y = destination_idx as i32 / map.width;
             if let Some(viewshed) = viewsheds.get_mut(entity) {
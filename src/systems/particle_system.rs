@@ -1,14 +1,14 @@
-use crate::{ParticleLifetime, Position, Renderable, Rltk};
+use crate::{ParticleLifetime, Position, Renderable};
 use specs::prelude::*;
 
-pub fn cull_dead_particles(ecs: &mut World, ctx: &Rltk) {
+pub fn cull_dead_particles(ecs: &mut World, frame_time_ms: f32) {
     let mut dead_particles: Vec<Entity> = Vec::new();
     {
         // Age out particles
         let mut particles = ecs.write_storage::<ParticleLifetime>();
         let entities = ecs.entities();
         for (entity, particle) in (&entities, &mut particles).join() {
-            particle.lifetime_ms -= ctx.frame_time_ms;
+            particle.lifetime_ms -= frame_time_ms;
             if particle.lifetime_ms < 0.0 {
                 dead_particles.push(entity);
             }
@@ -0,0 +1,269 @@
+use crate::{
+    effects::{EffectQueue, EffectType, Targets},
+    gamelog::GameLog,
+    particle_system::ParticleBuilder,
+    practice_skills, skill_bonus, Ammunition, Attributes, EquipmentChanged, EquipmentSlot,
+    Equipped, HungerClock, HungerState, InBackpack, Name, NaturalAttackDefense, Pools, Position,
+    RangedWeapon, Skill, Skills, Stackable, WantsToShoot, Wearable,
+};
+use specs::prelude::*;
+use specs::storage::MaskedStorage;
+use std::ops::Deref;
+
+/// The ranged weapon `owner` has equipped, if any.
+pub fn equipped_ranged_weapon<E, W>(
+    owner: Entity,
+    equipped: &Storage<Equipped, E>,
+    ranged_weapons: &Storage<RangedWeapon, W>,
+) -> Option<RangedWeapon>
+where
+    E: Deref<Target = MaskedStorage<Equipped>>,
+    W: Deref<Target = MaskedStorage<RangedWeapon>>,
+{
+    (equipped, ranged_weapons)
+        .join()
+        .find(|(wielded, _)| wielded.owner == owner && wielded.slot == EquipmentSlot::Ranged)
+        .map(|(_, weapon)| weapon.clone())
+}
+
+/// A piece of `kind` ammunition in `owner`'s backpack, if they have any left.
+pub fn find_ammo<B, A>(
+    owner: Entity,
+    kind: &str,
+    entities: &Entities,
+    backpack: &Storage<InBackpack, B>,
+    ammunition: &Storage<Ammunition, A>,
+) -> Option<Entity>
+where
+    B: Deref<Target = MaskedStorage<InBackpack>>,
+    A: Deref<Target = MaskedStorage<Ammunition>>,
+{
+    (entities, backpack, ammunition)
+        .join()
+        .find(|(_, carried, ammo)| carried.owner == owner && ammo.kind == kind)
+        .map(|(entity, _, _)| entity)
+}
+
+pub struct RangedCombatSystem {}
+
+impl<'a> System<'a> for RangedCombatSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        WriteExpect<'a, GameLog>,
+        WriteStorage<'a, WantsToShoot>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Attributes>,
+        WriteStorage<'a, Skills>,
+        WriteExpect<'a, ParticleBuilder>,
+        WriteStorage<'a, Position>,
+        ReadStorage<'a, HungerClock>,
+        ReadStorage<'a, Pools>,
+        WriteExpect<'a, rltk::RandomNumberGenerator>,
+        ReadStorage<'a, Equipped>,
+        ReadStorage<'a, RangedWeapon>,
+        ReadStorage<'a, Wearable>,
+        ReadStorage<'a, NaturalAttackDefense>,
+        WriteStorage<'a, InBackpack>,
+        ReadStorage<'a, Ammunition>,
+        WriteStorage<'a, EquipmentChanged>,
+        ReadExpect<'a, Entity>,
+        WriteExpect<'a, EffectQueue>,
+        WriteStorage<'a, Stackable>,
+        Read<'a, LazyUpdate>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut log,
+            mut wants_shoot,
+            names,
+            attributes,
+            mut skills,
+            mut particle_builder,
+            mut positions,
+            hunger_clock,
+            pools,
+            mut rng,
+            equipped_items,
+            ranged_weapons,
+            wearables,
+            natural,
+            mut backpack,
+            ammunition,
+            mut dirty,
+            player,
+            mut effect_queue,
+            mut stacks,
+            lazy,
+        ) = data;
+
+        let shots: Vec<(Entity, Entity)> = (&entities, &wants_shoot)
+            .join()
+            .map(|(entity, shoot)| (entity, shoot.target))
+            .collect();
+        wants_shoot.clear();
+
+        let mut practiced: Vec<(Entity, Skill)> = Vec::new();
+        for (entity, target) in shots {
+            let (
+                Some(name),
+                Some(attacker_attributes),
+                Some(attacker_skills),
+                Some(attacker_pools),
+                Some(target_name),
+                Some(target_attributes),
+                Some(target_skills),
+                Some(target_pools),
+            ) = (
+                names.get(entity),
+                attributes.get(entity),
+                skills.get(entity),
+                pools.get(entity),
+                names.get(target),
+                attributes.get(target),
+                skills.get(target),
+                pools.get(target),
+            )
+            else {
+                continue;
+            };
+            // Are the attacker and defender alive? Only shoot if they are
+            if attacker_pools.hit_points.current < 1 || target_pools.hit_points.current < 1 {
+                continue;
+            }
+            let Some(weapon) = equipped_ranged_weapon(entity, &equipped_items, &ranged_weapons)
+            else {
+                continue;
+            };
+            let Some(ammo) = find_ammo(entity, &weapon.ammo, &entities, &backpack, &ammunition)
+            else {
+                log.entries.push(format!(
+                    "{} has no {} left to shoot.",
+                    name.name, weapon.ammo
+                ));
+                continue;
+            };
+            let (Some(start), Some(end)) = (positions.get(entity), positions.get(target)) else {
+                continue;
+            };
+            let (start, end) = (
+                rltk::Point::new(start.x, start.y),
+                rltk::Point::new(end.x, end.y),
+            );
+
+            // The shot flies from the shooter to the target, taking one off a stack
+            let stack = stacks.get_mut(ammo).filter(|stack| stack.quantity > 1);
+            let from_stack = stack.is_some();
+            if let Some(stack) = stack {
+                stack.quantity -= 1;
+            } else {
+                backpack.remove(ammo);
+            }
+            dirty
+                .insert(entity, EquipmentChanged {})
+                .expect("Unable to insert");
+            for pt in rltk::line2d(rltk::LineAlg::Bresenham, start, end)
+                .iter()
+                .skip(1)
+            {
+                particle_builder.request(
+                    pt.x,
+                    pt.y,
+                    rltk::RGB::named(rltk::CYAN),
+                    rltk::RGB::named(rltk::BLACK),
+                    rltk::to_cp437('∙'),
+                    100.0,
+                );
+            }
+
+            let natural_roll = rng.roll_dice(1, 20);
+            let attribute_hit_bonus = attacker_attributes.quickness.bonus;
+            let skill_hit_bonus = skill_bonus(Skill::Ranged, attacker_skills);
+            let weapon_hit_bonus = weapon.hit_bonus;
+            let mut status_hit_bonus = 0;
+            if let Some(hc) = hunger_clock.get(entity) {
+                // Well-Fed grants +1
+                if hc.state == HungerState::WellFed {
+                    status_hit_bonus += 1;
+                }
+            }
+            let modified_hit_roll = natural_roll
+                + attribute_hit_bonus
+                + skill_hit_bonus
+                + weapon_hit_bonus
+                + status_hit_bonus;
+
+            let mut armor_item_bonus_f = 0.0;
+            for (wielded, armor) in (&equipped_items, &wearables).join() {
+                if wielded.owner == target {
+                    armor_item_bonus_f += armor.armor_class;
+                }
+            }
+            let base_armor_class = match natural.get(target) {
+                None => 10,
+                Some(natural_attack) => natural_attack.armor_class.unwrap_or(10),
+            };
+            let armor_quickness_bonus = target_attributes.quickness.bonus;
+            let armor_skill_bonus = skill_bonus(Skill::Defense, target_skills);
+            let armor_item_bonus = armor_item_bonus_f as i32;
+            let armor_class =
+                base_armor_class + armor_quickness_bonus + armor_skill_bonus + armor_item_bonus;
+
+            practiced.push((target, Skill::Defense));
+            if natural_roll != 1 && (natural_roll == 20 || modified_hit_roll > armor_class) {
+                practiced.push((entity, Skill::Ranged));
+                let base_damage = rng.roll_dice(weapon.damage_n_dice, weapon.damage_die_type);
+                let attribute_damage_bonus = attacker_attributes.quickness.bonus;
+                let skill_damage_bonus = skill_bonus(Skill::Ranged, attacker_skills);
+                let damage = 0.max(
+                    base_damage + attribute_damage_bonus + skill_damage_bonus + weapon.damage_bonus,
+                );
+                effect_queue.add_effect(
+                    Some(entity),
+                    EffectType::Damage { amount: damage },
+                    Targets::Single { target },
+                );
+                log.entries.push(format!(
+                    "{} shoots {}, for {} hp.",
+                    &name.name, &target_name.name, damage
+                ));
+            } else if natural_roll == 1 {
+                log.entries.push(format!(
+                    "{} shoots at {}, but fumbles the shot.",
+                    &name.name, &target_name.name
+                ));
+            } else {
+                log.entries.push(format!(
+                    "{} shoots at {}, but misses.",
+                    &name.name, &target_name.name
+                ));
+            }
+
+            // One shot in three breaks; the rest can be picked up where they landed
+            if rng.roll_dice(1, 3) == 1 {
+                if !from_stack {
+                    entities.delete(ammo).expect("Delete failed");
+                }
+            } else if from_stack {
+                // The stack stays in the backpack, so a new one lands
+                let ammo_name = names.get(ammo).map(|n| n.name.clone()).unwrap_or_default();
+                lazy.exec_mut(move |world| {
+                    crate::raws::spawn_named_item(
+                        &crate::raws::RAWS.lock().unwrap(),
+                        world,
+                        &ammo_name,
+                        crate::raws::SpawnType::AtPosition { x: end.x, y: end.y },
+                    );
+                });
+            } else {
+                positions
+                    .insert(ammo, Position { x: end.x, y: end.y })
+                    .expect("Unable to insert position");
+            }
+        }
+
+        practice_skills(&practiced, &mut skills, &pools, *player, &mut log);
+    }
+}
@@ -0,0 +1,127 @@
+use crate::{
+    hp_regen_rate, mana_regen_rate,
+    raws::{faction_reaction, Reaction, RAWS},
+    Attributes, Equipped, Faction, HungerClock, HungerState, Map, MyTurn, Pools, RegenerationBonus,
+    StatusEffects, StatusKind, Viewshed,
+};
+use specs::prelude::*;
+
+/// Regeneration builds up in tenths of a point, so slow rates still add up.
+const PROGRESS_PER_POINT: i32 = 10;
+
+/// Everything with pools slowly regains hit points and mana on its turns, as long as
+/// nothing it's fighting or fleeing is in sight. Fitness and Intelligence, level, hunger
+/// and equipment set how quickly; poison stops hit points coming back at all.
+pub struct RegenerationSystem {}
+
+impl<'a> System<'a> for RegenerationSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        ReadStorage<'a, MyTurn>,
+        WriteStorage<'a, Pools>,
+        ReadStorage<'a, Attributes>,
+        ReadStorage<'a, HungerClock>,
+        ReadStorage<'a, StatusEffects>,
+        ReadStorage<'a, Equipped>,
+        ReadStorage<'a, RegenerationBonus>,
+        ReadStorage<'a, Viewshed>,
+        ReadStorage<'a, Faction>,
+        ReadExpect<'a, Map>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            turns,
+            mut pools,
+            attributes,
+            hunger,
+            statuses,
+            equipped,
+            bonuses,
+            viewsheds,
+            factions,
+            map,
+        ) = data;
+
+        let raws = RAWS.lock().unwrap();
+        let in_danger = |entity: Entity| {
+            let (Some(viewshed), Some(faction)) = (viewsheds.get(entity), factions.get(entity))
+            else {
+                return false;
+            };
+            let mut danger = false;
+            for tile in viewshed.visible_tiles.iter() {
+                map.spatial
+                    .for_each_tile_content(map.xy_idx(tile.x, tile.y), |other| {
+                        if other == entity {
+                            return;
+                        }
+                        if let Some(other_faction) = factions.get(other) {
+                            let theirs =
+                                faction_reaction(&other_faction.name, &faction.name, &raws);
+                            let mine = faction_reaction(&faction.name, &other_faction.name, &raws);
+                            if theirs == Reaction::Attack || mine != Reaction::Ignore {
+                                danger = true;
+                            }
+                        }
+                    });
+            }
+            danger
+        };
+
+        for (entity, _turn, pool, attr) in (&entities, &turns, &mut pools, &attributes).join() {
+            if pool.hit_points.current < 1 || in_danger(entity) {
+                continue;
+            }
+
+            // Percent of the usual rate
+            let hunger_rate = match hunger.get(entity).map(|clock| clock.state) {
+                Some(HungerState::WellFed) => 150,
+                None | Some(HungerState::Normal) => 100,
+                Some(HungerState::Hungry) => 50,
+                Some(HungerState::Starving) => 0,
+            };
+            let (mut hp_rate, mut mana_rate) = (
+                hp_regen_rate(attr.fitness.base + attr.fitness.modifiers, pool.level),
+                mana_regen_rate(
+                    attr.intelligence.base + attr.intelligence.modifiers,
+                    pool.level,
+                ),
+            );
+            for (bonus, _) in (&bonuses, &equipped)
+                .join()
+                .filter(|(_, equipped)| equipped.owner == entity)
+            {
+                hp_rate += bonus.hp;
+                mana_rate += bonus.mana;
+            }
+            hp_rate = 0.max(hp_rate * hunger_rate / 100);
+            mana_rate = 0.max(mana_rate * hunger_rate / 100);
+            if statuses
+                .get(entity)
+                .is_some_and(|s| s.has(StatusKind::Poison))
+            {
+                hp_rate = 0;
+            }
+
+            if pool.hit_points.current < pool.hit_points.max {
+                pool.hp_regen_progress += hp_rate;
+                pool.hit_points.current = pool
+                    .hit_points
+                    .max
+                    .min(pool.hit_points.current + pool.hp_regen_progress / PROGRESS_PER_POINT);
+                pool.hp_regen_progress %= PROGRESS_PER_POINT;
+            }
+            if pool.mana.current < pool.mana.max {
+                pool.mana_regen_progress += mana_rate;
+                pool.mana.current = pool
+                    .mana
+                    .max
+                    .min(pool.mana.current + pool.mana_regen_progress / PROGRESS_PER_POINT);
+                pool.mana_regen_progress %= PROGRESS_PER_POINT;
+            }
+        }
+    }
+}
@@ -1,61 +1,206 @@
 use crate::components::*;
+use crate::map::{Map, MasterDungeonMap};
+use crate::LightingSystem;
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use specs::prelude::*;
 use specs::saveload::{
-    DeserializeComponents, MarkedBuilder, SerializeComponents, SimpleMarker, SimpleMarkerAllocator,
+    DeserializeComponents, SerializeComponents, SimpleMarker, SimpleMarkerAllocator,
 };
 use std::convert::Infallible;
 use std::fs;
-use std::fs::File;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const SAVE_DIR: &str = "./saves";
+
+/// Where the game kept its one save before there were slots.
+const OLD_SAVE_FILE: &str = "./savegame.json";
+
+/// Save files are named after their slot with this extension, whatever their encoding.
+/// Slots used to be `.json` files.
+const SAVE_EXTENSION: &str = "sav";
+
+/// Bump this whenever a saved component or resource changes shape, and add the function
+/// that brings the previous version up to it to `MIGRATIONS`. New components and new
+/// `#[serde(default)]` fields load from older saves without one.
+pub const SAVE_FORMAT_VERSION: u32 = 7;
+
+/// Brings a save up from one format version to the next.
+type Migration = fn(&mut SaveEnvelope) -> Result<(), String>;
+
+/// `MIGRATIONS[n]` turns a save in format `n` into format `n + 1`.
+const MIGRATIONS: [Migration; SAVE_FORMAT_VERSION as usize] = [
+    migrate_v0_to_v1,
+    migrate_v1_to_v2,
+    migrate_v2_to_v3,
+    migrate_v3_to_v4,
+    migrate_v4_to_v5,
+    migrate_v5_to_v6,
+    migrate_v6_to_v7,
+];
+
+/// How the body of a save is written. The header line is always JSON, so the load menu can
+/// list saves of either kind; loading reads the encoding from it. The world keeps the one
+/// to save with as a resource, JSON if it has none.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SaveEncoding {
+    /// A line of JSON, easy to read and to repair by hand
+    #[default]
+    Json,
+    /// CBOR, deflated; much smaller and quicker to write for long runs
+    Binary,
+}
+
+impl FromStr for SaveEncoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<SaveEncoding, String> {
+        match s {
+            "json" => Ok(SaveEncoding::Json),
+            "binary" => Ok(SaveEncoding::Binary),
+            _ => Err(format!("unknown save encoding '{}'; use json or binary", s)),
+        }
+    }
+}
+
+/// How long the current run has been played for. It's kept in the save's metadata.
+#[derive(Default)]
+pub struct PlayTime {
+    pub ms: u64,
+}
+
+/// A summary of a save, written as its first line so that the saves can be listed without
+/// reading the worlds in them.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SaveMetadata {
+    pub character_level: i32,
+    pub depth: i32,
+    pub map_name: String,
+    pub play_time_ms: u64,
+    /// When the save was written, in seconds since the Unix epoch
+    pub timestamp: u64,
+    /// Set once the save has been loaded. Death is permanent, so it can't be loaded again;
+    /// the run carries on and saves over it.
+    #[serde(default)]
+    pub consumed: bool,
+}
+
+/// A save file in the save directory, and its metadata if it has any (saves from before
+/// slots don't).
+pub struct SaveSlot {
+    pub name: String,
+    pub metadata: Option<SaveMetadata>,
+}
+
+impl SaveSlot {
+    pub fn can_load(&self) -> bool {
+        !self.metadata.as_ref().is_some_and(|m| m.consumed)
+    }
+}
+
+/// What a save holds: the versions that wrote it, its metadata, the world's resources and
+/// every serialized component storage, keyed by name.
+#[derive(Serialize, Deserialize)]
+pub struct SaveEnvelope {
+    pub format_version: u32,
+    pub game_version: String,
+    #[serde(default)]
+    pub metadata: Option<SaveMetadata>,
+    #[serde(default)]
+    pub resources: serde_json::Map<String, Value>,
+    pub components: serde_json::Map<String, Value>,
+}
+
+/// The first line of a save file.
+#[derive(Serialize, Deserialize)]
+struct SaveHeader {
+    format_version: u32,
+    game_version: String,
+    metadata: Option<SaveMetadata>,
+    #[serde(default)]
+    encoding: SaveEncoding,
+}
+
+/// The rest of a save file.
+#[derive(Serialize, Deserialize)]
+struct SaveBody {
+    resources: serde_json::Map<String, Value>,
+    components: serde_json::Map<String, Value>,
+}
 
 macro_rules! serialize_individually {
-    ($ecs:expr, $ser:expr, $data:expr, $( $type:ty), *) => {
+    ($ecs:expr, $components:expr, $data:expr, $( $type:ty), *) => {
         $(
-            SerializeComponents::<Infallible, SimpleMarker<SerializeMe>>::serialize(
+            let storage = SerializeComponents::<Infallible, SimpleMarker<SerializeMe>>::serialize(
                 &( $ecs.read_storage::<$type>(), ),
                 &$data.0,
                 &$data.1,
-                &mut $ser,
+                serde_json::value::Serializer,
             )
             .unwrap();
+            $components.insert(stringify!($type).to_string(), storage);
         )*
     };
 }
 
+fn slot_path(slot: &str) -> PathBuf {
+    Path::new(SAVE_DIR).join(format!("{}.{}", slot, SAVE_EXTENSION))
+}
+
 #[cfg(not(target_arch = "wasm32"))]
-pub fn save_game(ecs: &mut World) {
-    // Create helper
-    let mapcopy = ecs.get_mut::<crate::map::Map>().unwrap().clone();
-    let dungeon_master = ecs
-        .get_mut::<crate::map::MasterDungeonMap>()
-        .unwrap()
-        .clone();
-
-    let save_helper = ecs
-        .create_entity()
-        .with(SerializationHelper { map: mapcopy })
-        .marked::<SimpleMarker<SerializeMe>>()
-        .build();
-    let save_helper2 = ecs
-        .create_entity()
-        .with(DMSerializationHelper {
-            map: dungeon_master,
-        })
-        .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+pub fn save_game(ecs: &mut World, slot: &str) {
+    write_slot(ecs, slot, false);
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn save_game(_ecs: &mut World, _slot: &str) {}
 
-    // Actually Serialize
+fn write_slot(ecs: &mut World, slot: &str, consumed: bool) {
+    let encoding = ecs
+        .try_fetch::<SaveEncoding>()
+        .map_or_else(Default::default, |e| *e);
+    let data = serialize_world(ecs, consumed, encoding);
+    let written = fs::create_dir_all(SAVE_DIR).and_then(|_| fs::write(slot_path(slot), data));
+    if let Err(e) = written {
+        rltk::console::log(format!("Unable to save the game: {}", e));
+    }
+}
+
+/// Serializes the whole world as JSON, as `save_game` writes it by default.
+pub fn save_to_string(ecs: &mut World) -> String {
+    String::from_utf8(serialize_world(ecs, false, SaveEncoding::Json)).unwrap()
+}
+
+/// Serializes the whole world in the given encoding, as `save_game` writes it.
+pub fn save_to_bytes(ecs: &mut World, encoding: SaveEncoding) -> Vec<u8> {
+    serialize_world(ecs, false, encoding)
+}
+
+fn serialize_world(ecs: &mut World, consumed: bool, encoding: SaveEncoding) -> Vec<u8> {
+    let mut resources = serde_json::Map::new();
+    resources.insert(
+        "Map".to_string(),
+        serde_json::to_value(&*ecs.fetch::<Map>()).unwrap(),
+    );
+    resources.insert(
+        "MasterDungeonMap".to_string(),
+        serde_json::to_value(&*ecs.fetch::<MasterDungeonMap>()).unwrap(),
+    );
+
+    let mut components = serde_json::Map::new();
     {
         let data = (
             ecs.entities(),
             ecs.read_storage::<SimpleMarker<SerializeMe>>(),
         );
 
-        let writer = File::create("./savegame.json").unwrap();
-        let mut serializer = serde_json::Serializer::new(writer);
         serialize_individually!(
             ecs,
-            serializer,
+            components,
             data,
             Position,
             Renderable,
@@ -70,13 +215,11 @@ pub fn save_game(ecs: &mut World) {
             Ranged,
             InflictsDamage,
             AreaOfEffect,
-            Confusion,
             ProvidesHealing,
             InBackpack,
             WantsToPickupItem,
             WantsToUseItem,
             WantsToDropItem,
-            SerializationHelper,
             Equippable,
             Equipped,
             MeleeWeapon,
@@ -99,7 +242,6 @@ pub fn save_game(ecs: &mut World) {
             NaturalAttackDefense,
             LootTable,
             OtherLevelPosition,
-            DMSerializationHelper,
             LightSource,
             Initiative,
             MyTurn,
@@ -116,38 +258,196 @@ pub fn save_game(ecs: &mut World) {
             ApplyTeleport,
             MagicItem,
             ObfuscatedName,
-            IdentifiedItem
+            IdentifiedItem,
+            RangedWeapon,
+            Ammunition,
+            WantsToShoot,
+            KnownSpells,
+            WantsToCastSpell,
+            TeachesSpell,
+            StatusEffects,
+            InflictsStatus,
+            RegenerationBonus,
+            LevelUpPoints,
+            AttributeBonus,
+            SkillBonus,
+            CursedItem,
+            ProvidesRemoveCurse,
+            ProvidesIdentification,
+            Charges,
+            ProvidesRecharge,
+            Recharger,
+            Stackable,
+            Container,
+            Locked,
+            Trapped,
+            Temple
         );
     }
 
-    // Cleanup
-    ecs.delete_entity(save_helper).expect("Crash on cleanup");
-    ecs.delete_entity(save_helper2).expect("Crash on cleanup");
+    let mut metadata = metadata(ecs);
+    metadata.consumed = consumed;
+    let header = SaveHeader {
+        format_version: SAVE_FORMAT_VERSION,
+        game_version: env!("CARGO_PKG_VERSION").to_string(),
+        metadata: Some(metadata),
+        encoding,
+    };
+    let body = SaveBody {
+        resources,
+        components,
+    };
+    let mut data = serde_json::to_vec(&header).unwrap();
+    data.push(b'\n');
+    match encoding {
+        SaveEncoding::Json => {
+            serde_json::to_writer(&mut data, &body).unwrap();
+            data.push(b'\n');
+        }
+        SaveEncoding::Binary => {
+            let mut encoder = DeflateEncoder::new(data, Compression::default());
+            ciborium::into_writer(&body, &mut encoder).unwrap();
+            data = encoder.finish().unwrap();
+        }
+    }
+    data
 }
 
-#[cfg(target_arch = "wasm32")]
-pub fn save_game(_ecs: &mut World) {}
+fn metadata(ecs: &World) -> SaveMetadata {
+    let player = *ecs.fetch::<Entity>();
+    let map = ecs.fetch::<Map>();
+    SaveMetadata {
+        character_level: ecs
+            .read_storage::<Pools>()
+            .get(player)
+            .map_or(1, |p| p.level),
+        depth: map.depth,
+        map_name: map.name.clone(),
+        play_time_ms: ecs.try_fetch::<PlayTime>().map_or(0, |t| t.ms),
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs()),
+        consumed: false,
+    }
+}
+
+/// Every save in the save directory, most recently written first.
+pub fn list_saves() -> Vec<SaveSlot> {
+    // Bring the save from before slots into the directory
+    if Path::new(OLD_SAVE_FILE).exists() && fs::create_dir_all(SAVE_DIR).is_ok() {
+        let _ = fs::rename(OLD_SAVE_FILE, slot_path("savegame"));
+    }
+
+    let mut slots = Vec::new();
+    if let Ok(entries) = fs::read_dir(SAVE_DIR) {
+        for entry in entries.flatten() {
+            let mut path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) == Some("json") {
+                let renamed = path.with_extension(SAVE_EXTENSION);
+                if fs::rename(&path, &renamed).is_ok() {
+                    path = renamed;
+                }
+            }
+            if path.extension().and_then(|e| e.to_str()) != Some(SAVE_EXTENSION) {
+                continue;
+            }
+            if let Some(name) = path.file_stem().and_then(|n| n.to_str()) {
+                slots.push(SaveSlot {
+                    name: name.to_string(),
+                    metadata: read_metadata(&path),
+                });
+            }
+        }
+    }
+    slots.sort_by(|a, b| {
+        let time = |slot: &SaveSlot| slot.metadata.as_ref().map_or(0, |m| m.timestamp);
+        time(b).cmp(&time(a)).then_with(|| a.name.cmp(&b.name))
+    });
+    slots
+}
+
+/// Reads only the first line of a save.
+fn read_metadata(path: &Path) -> Option<SaveMetadata> {
+    use std::io::{BufRead, BufReader};
+    let file = fs::File::open(path).ok()?;
+    let mut line = String::new();
+    BufReader::new(file).read_line(&mut line).ok()?;
+    serde_json::from_str::<SaveHeader>(&line).ok()?.metadata
+}
 
 pub fn does_save_exist() -> bool {
-    Path::new("./savegame.json").exists()
+    list_saves().iter().any(|slot| slot.can_load())
+}
+
+/// A slot name no save is using yet, for a new run.
+pub fn new_slot_name() -> String {
+    let mut n = 1;
+    while slot_path(&format!("slot{}", n)).exists() {
+        n += 1;
+    }
+    format!("slot{}", n)
 }
 
 macro_rules! deserialize_individually {
-    ($ecs:expr, $de:expr, $data:expr, $( $type:ty), *) => {
+    ($ecs:expr, $components:expr, $data:expr, $( $type:ty), *) => {
         $(
-            DeserializeComponents::<Infallible, _>::deserialize(
-                &mut ( &mut $ecs.write_storage::<$type>(), ),
-                &$data.0, // entities
-                &mut $data.1, // marker
-                &mut $data.2, // allocator
-                &mut $de,
-        )
-        .unwrap();
+            // A component this save predates simply has no entries
+            if let Some(storage) = $components.remove(stringify!($type)) {
+                DeserializeComponents::<Infallible, _>::deserialize(
+                    &mut ( &mut $ecs.write_storage::<$type>(), ),
+                    &$data.0, // entities
+                    &mut $data.1, // marker
+                    &mut $data.2, // allocator
+                    storage,
+                )
+                .map_err(|e| format!("{}: {}", stringify!($type), e))?;
+            }
         )*
     };
 }
 
-pub fn load_game(ecs: &mut World) {
+/// Loads a save slot into the world and marks it consumed. A save that can't be read or
+/// migrated is refused before anything is touched; if it fails part way through loading,
+/// the world is left empty and has to be rebuilt.
+pub fn load_game(ecs: &mut World, slot: &str) -> Result<(), String> {
+    let path = slot_path(slot);
+    if read_metadata(&path).is_some_and(|m| m.consumed) {
+        return Err("This save has already been loaded once; death is permanent".to_string());
+    }
+    let data = fs::read(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    load_from_bytes(ecs, &data)?;
+
+    // The slot keeps its encoding unless the player picks another when they next save
+    ecs.insert(read_encoding(&data));
+
+    // Written back straight away, so a crash can't be used to load it again
+    write_slot(ecs, slot, true);
+    Ok(())
+}
+
+/// Loads a world serialized by `save_to_string`, or by any older version of the game.
+pub fn load_from_str(ecs: &mut World, data: &str) -> Result<(), String> {
+    load_from_bytes(ecs, data.as_bytes())
+}
+
+/// Loads a world serialized by `save_to_bytes` in either encoding, or by any older version
+/// of the game.
+pub fn load_from_bytes(ecs: &mut World, data: &[u8]) -> Result<(), String> {
+    let save = read_save(data)?;
+    let mut components = save.components;
+    let resource = |name: &str| {
+        save.resources
+            .get(name)
+            .ok_or(format!("the save has no {}", name))
+    };
+    let mut map: Map =
+        serde_json::from_value(resource("Map")?.clone()).map_err(|e| format!("Map: {}", e))?;
+    let mut dungeon_master: MasterDungeonMap =
+        serde_json::from_value(resource("MasterDungeonMap")?.clone())
+            .map_err(|e| format!("MasterDungeonMap: {}", e))?;
+    map.reset_unsaved();
+    dungeon_master.reset_unsaved();
+
     {
         // Delete everything
         let mut to_delete = Vec::new();
@@ -159,9 +459,6 @@ pub fn load_game(ecs: &mut World) {
         }
     }
 
-    let data = fs::read_to_string("./savegame.json").unwrap();
-    let mut de = serde_json::Deserializer::from_str(&data);
-
     {
         let mut d = (
             &mut ecs.entities(),
@@ -171,7 +468,7 @@ pub fn load_game(ecs: &mut World) {
 
         deserialize_individually!(
             ecs,
-            de,
+            components,
             d,
             Position,
             Renderable,
@@ -186,13 +483,11 @@ pub fn load_game(ecs: &mut World) {
             Ranged,
             InflictsDamage,
             AreaOfEffect,
-            Confusion,
             ProvidesHealing,
             InBackpack,
             WantsToPickupItem,
             WantsToUseItem,
             WantsToDropItem,
-            SerializationHelper,
             Equippable,
             Equipped,
             MeleeWeapon,
@@ -215,7 +510,6 @@ pub fn load_game(ecs: &mut World) {
             NaturalAttackDefense,
             LootTable,
             OtherLevelPosition,
-            DMSerializationHelper,
             LightSource,
             Initiative,
             MyTurn,
@@ -232,45 +526,390 @@ pub fn load_game(ecs: &mut World) {
             ApplyTeleport,
             MagicItem,
             ObfuscatedName,
-            IdentifiedItem
+            IdentifiedItem,
+            RangedWeapon,
+            Ammunition,
+            WantsToShoot,
+            KnownSpells,
+            WantsToCastSpell,
+            TeachesSpell,
+            StatusEffects,
+            InflictsStatus,
+            RegenerationBonus,
+            LevelUpPoints,
+            AttributeBonus,
+            SkillBonus,
+            CursedItem,
+            ProvidesRemoveCurse,
+            ProvidesIdentification,
+            Charges,
+            ProvidesRecharge,
+            Recharger,
+            Stackable,
+            Container,
+            Locked,
+            Trapped,
+            Temple
         );
     }
 
-    let mut deleteme: Option<Entity> = None;
-    let mut deleteme2: Option<Entity> = None;
+    ecs.insert(map);
+    ecs.insert(dungeon_master);
+    ecs.insert(PlayTime {
+        ms: save.metadata.map_or(0, |m| m.play_time_ms),
+    });
+
+    let mut found_player = false;
     {
         let entities = ecs.entities();
-        let helper = ecs.read_storage::<SerializationHelper>();
-        let helper2 = ecs.read_storage::<DMSerializationHelper>();
         let player = ecs.read_storage::<Player>();
         let position = ecs.read_storage::<Position>();
-        for (e, h) in (&entities, &helper).join() {
-            let mut worldmap = ecs.write_resource::<crate::map::Map>();
-            *worldmap = h.map.clone();
-            crate::spatial::set_size((worldmap.height * worldmap.width) as usize);
-            deleteme = Some(e);
-        }
-        for (e, h) in (&entities, &helper2).join() {
-            let mut dungeon_master = ecs.write_resource::<crate::map::MasterDungeonMap>();
-            *dungeon_master = h.map.clone();
-            deleteme2 = Some(e);
-        }
-
         for (e, _p, pos) in (&entities, &player, &position).join() {
             let mut playerpos = ecs.write_resource::<rltk::Point>();
             *playerpos = rltk::Point::new(pos.x, pos.y);
             let mut player_resource = ecs.write_resource::<Entity>();
             *player_resource = e;
+            found_player = true;
+        }
+    }
+    if !found_player {
+        return Err("the save has no player".to_string());
+    }
+
+    // What the player can see and the light on it weren't saved. The player's viewshed
+    // was, so they can be put back as they were without waiting for a turn.
+    {
+        let mut map = ecs.write_resource::<Map>();
+        let player = *ecs.fetch::<Entity>();
+        if let Some(viewshed) = ecs.read_storage::<Viewshed>().get(player) {
+            for tile in viewshed.visible_tiles.iter() {
+                let idx = map.xy_idx(tile.x, tile.y);
+                map.visible_tiles[idx] = true;
+            }
+        }
+    }
+    LightingSystem {}.run_now(ecs);
+    Ok(())
+}
+
+/// Reads a save in either encoding and migrates it to the current format.
+pub fn read_save(data: &[u8]) -> Result<SaveEnvelope, String> {
+    let mut save = match read_binary(data)? {
+        Some(save) => save,
+        None => read_json(data)?,
+    };
+
+    if save.format_version > SAVE_FORMAT_VERSION {
+        return Err(format!(
+            "This save is from a newer version of the game ({}, save format {})",
+            save.game_version, save.format_version
+        ));
+    }
+    while save.format_version < SAVE_FORMAT_VERSION {
+        MIGRATIONS[save.format_version as usize](&mut save).map_err(|e| {
+            format!(
+                "This save is incompatible (format {}): {}",
+                save.format_version, e
+            )
+        })?;
+        save.format_version += 1;
+    }
+
+    Ok(save)
+}
+
+/// The encoding a save's header names; saves from before there was a choice are JSON.
+fn read_encoding(data: &[u8]) -> SaveEncoding {
+    let first_line = data.split(|b| *b == b'\n').next().unwrap_or_default();
+    serde_json::from_slice::<SaveHeader>(first_line)
+        .map_or_else(|_| Default::default(), |h| h.encoding)
+}
+
+/// A save whose header says its body is binary, or `None` for any other save.
+fn read_binary(data: &[u8]) -> Result<Option<SaveEnvelope>, String> {
+    let (first_line, body) = match data.iter().position(|b| *b == b'\n') {
+        Some(end) => (&data[..end], &data[end + 1..]),
+        None => return Ok(None),
+    };
+    let header = match serde_json::from_slice::<SaveHeader>(first_line) {
+        Ok(header) if header.encoding == SaveEncoding::Binary => header,
+        _ => return Ok(None),
+    };
+
+    let body: SaveBody = ciborium::from_reader(DeflateDecoder::new(body))
+        .map_err(|e| format!("The save is damaged: {}", e))?;
+    Ok(Some(SaveEnvelope {
+        format_version: header.format_version,
+        game_version: header.game_version,
+        metadata: header.metadata,
+        resources: body.resources,
+        components: body.components,
+    }))
+}
+
+/// A JSON save, in any of the layouts it has had.
+fn read_json(data: &[u8]) -> Result<SaveEnvelope, String> {
+    let damaged = |e: serde_json::Error| format!("The save is damaged: {}", e);
+    let mut values = Vec::new();
+    for value in serde_json::Deserializer::from_slice(data).into_iter::<Value>() {
+        values.push(value.map_err(damaged)?);
+    }
+
+    let is_header = |value: &Value| value.get("format_version").is_some();
+    if values.len() == 2 && is_header(&values[0]) {
+        let body: SaveBody = serde_json::from_value(values.pop().unwrap()).map_err(damaged)?;
+        let header: SaveHeader = serde_json::from_value(values.pop().unwrap()).map_err(damaged)?;
+        Ok(SaveEnvelope {
+            format_version: header.format_version,
+            game_version: header.game_version,
+            metadata: header.metadata,
+            resources: body.resources,
+            components: body.components,
+        })
+    } else if values.len() == 1 && is_header(&values[0]) {
+        // Format 1 was a single object
+        serde_json::from_value(values.pop().unwrap()).map_err(damaged)
+    } else {
+        // Before that, a save was just one storage after another
+        let mut components = serde_json::Map::new();
+        components.insert("legacy".to_string(), Value::Array(values));
+        Ok(SaveEnvelope {
+            format_version: 0,
+            game_version: "unknown".to_string(),
+            metadata: None,
+            resources: serde_json::Map::new(),
+            components,
+        })
+    }
+}
+
+/// Headerless saves held the storages in the order the save list was in when the format
+/// was introduced; name them.
+fn migrate_v0_to_v1(save: &mut SaveEnvelope) -> Result<(), String> {
+    const V0_COMPONENTS: &[&str] = &[
+        "Position",
+        "Renderable",
+        "Player",
+        "Viewshed",
+        "Name",
+        "BlocksTile",
+        "SufferDamage",
+        "WantsToMelee",
+        "Item",
+        "Consumable",
+        "Ranged",
+        "InflictsDamage",
+        "AreaOfEffect",
+        "Confusion",
+        "ProvidesHealing",
+        "InBackpack",
+        "WantsToPickupItem",
+        "WantsToUseItem",
+        "WantsToDropItem",
+        "SerializationHelper",
+        "Equippable",
+        "Equipped",
+        "MeleeWeapon",
+        "Wearable",
+        "WantsToRemoveItem",
+        "ParticleLifetime",
+        "HungerClock",
+        "ProvidesFood",
+        "MagicMapper",
+        "Hidden",
+        "EntryTrigger",
+        "EntityMoved",
+        "SingleActivation",
+        "Door",
+        "BlocksVisibility",
+        "Quips",
+        "Attributes",
+        "Skills",
+        "Pools",
+        "NaturalAttackDefense",
+        "LootTable",
+        "OtherLevelPosition",
+        "DMSerializationHelper",
+        "LightSource",
+        "Initiative",
+        "MyTurn",
+        "Faction",
+        "WantsToApproach",
+        "WantsToFlee",
+        "MoveMode",
+        "Chasing",
+        "EquipmentChanged",
+        "Vendor",
+        "TownPortal",
+        "TeleportTo",
+        "ApplyMove",
+        "ApplyTeleport",
+        "MagicItem",
+        "ObfuscatedName",
+        "IdentifiedItem",
+    ];
+
+    let storages = match save.components.remove("legacy") {
+        Some(Value::Array(storages)) => storages,
+        _ => return Err("no component data".to_string()),
+    };
+    if storages.len() != V0_COMPONENTS.len() {
+        return Err(format!(
+            "expected {} component lists, found {}",
+            V0_COMPONENTS.len(),
+            storages.len()
+        ));
+    }
+    for (name, storage) in V0_COMPONENTS.iter().zip(storages) {
+        save.components.insert(name.to_string(), storage);
+    }
+    Ok(())
+}
+
+/// The map and dungeon used to be saved on helper entities; they are resources now.
+fn migrate_v1_to_v2(save: &mut SaveEnvelope) -> Result<(), String> {
+    for (helper, resource) in [
+        ("SerializationHelper", "Map"),
+        ("DMSerializationHelper", "MasterDungeonMap"),
+    ] {
+        // Each helper storage holds one entity: {"marker": .., "components": [{"map": ..}]}
+        let value = save
+            .components
+            .remove(helper)
+            .and_then(|mut storage| storage.get_mut(0).map(Value::take))
+            .and_then(|mut entity| entity.pointer_mut("/components/0/map").map(Value::take))
+            .ok_or(format!("no {}", resource))?;
+        save.resources.insert(resource.to_string(), value);
+    }
+    Ok(())
+}
+
+/// Saves stopped carrying each map's visible tiles and light, which are worked out again
+/// on load.
+fn migrate_v2_to_v3(save: &mut SaveEnvelope) -> Result<(), String> {
+    let strip = |map: &mut Value| {
+        if let Some(map) = map.as_object_mut() {
+            map.remove("visible_tiles");
+            map.remove("light");
+        }
+    };
+    if let Some(map) = save.resources.get_mut("Map") {
+        strip(map);
+    }
+    if let Some(maps) = save
+        .resources
+        .get_mut("MasterDungeonMap")
+        .and_then(|dungeon| dungeon.get_mut("maps"))
+        .and_then(Value::as_object_mut)
+    {
+        maps.values_mut().for_each(strip);
+    }
+    Ok(())
+}
+
+/// Damage from items and traps became dice rather than a fixed amount; a fixed amount is
+/// a bonus with no dice.
+fn migrate_v3_to_v4(save: &mut SaveEnvelope) -> Result<(), String> {
+    let entities = save
+        .components
+        .get_mut("InflictsDamage")
+        .and_then(Value::as_array_mut);
+    for entity in entities.into_iter().flatten() {
+        if let Some(damage) = entity.pointer_mut("/components/0") {
+            if let Some(amount) = damage.get("damage").and_then(Value::as_i64) {
+                *damage = json!({ "n_dice": 0, "die_type": 0, "bonus": amount });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The ranged skill was added; everyone saved before it has the starting level in it.
+fn migrate_v4_to_v5(save: &mut SaveEnvelope) -> Result<(), String> {
+    let entities = save
+        .components
+        .get_mut("Skills")
+        .and_then(Value::as_array_mut);
+    for entity in entities.into_iter().flatten() {
+        if let Some(skills) = entity
+            .pointer_mut("/components/0/skills")
+            .and_then(Value::as_object_mut)
+        {
+            skills.entry("Ranged").or_insert(json!(1));
+        }
+    }
+    Ok(())
+}
+
+/// Spells were added; a player saved before them knows the one a new player starts with.
+fn migrate_v5_to_v6(save: &mut SaveEnvelope) -> Result<(), String> {
+    if save.components.contains_key("KnownSpells") {
+        return Ok(());
+    }
+    let players: Vec<Value> = save
+        .components
+        .get("Player")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|entity| !entity["components"][0].is_null())
+        .map(|entity| {
+            json!({
+                "marker": entity["marker"],
+                "components": [{ "spells": ["Zap"] }],
+            })
+        })
+        .collect();
+    save.components
+        .insert("KnownSpells".to_string(), Value::Array(players));
+    Ok(())
+}
+
+/// `Confusion` became one of many statuses. On something with hit points it is now a
+/// status it's under, and on a scroll or trap one the scroll or trap inflicts.
+fn migrate_v6_to_v7(save: &mut SaveEnvelope) -> Result<(), String> {
+    let Some(Value::Array(confused)) = save.components.remove("Confusion") else {
+        return Ok(());
+    };
+    let has_pools: Vec<Value> = save
+        .components
+        .get("Pools")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|entity| !entity["components"][0].is_null())
+        .map(|entity| entity["marker"].clone())
+        .collect();
+
+    let mut statuses = Vec::new();
+    let mut inflicts = Vec::new();
+    for entity in confused {
+        let Some(turns) = entity.pointer("/components/0/turns").cloned() else {
+            continue;
+        };
+        let status = json!({ "kind": "confusion", "turns": turns, "magnitude": 0 });
+        if has_pools.contains(&entity["marker"]) {
+            statuses.push(json!({
+                "marker": entity["marker"],
+                "components": [{ "effects": [status] }],
+            }));
+        } else {
+            inflicts.push(json!({
+                "marker": entity["marker"],
+                "components": [{ "statuses": [status] }],
+            }));
         }
     }
-    ecs.delete_entity(deleteme.unwrap())
-        .expect("Unable to delete helper");
-    ecs.delete_entity(deleteme2.unwrap())
-        .expect("Unable to delete helper");
+    save.components
+        .insert("StatusEffects".to_string(), Value::Array(statuses));
+    save.components
+        .insert("InflictsStatus".to_string(), Value::Array(inflicts));
+    Ok(())
 }
 
-pub fn delete_save() {
-    if does_save_exist() {
-        fs::remove_file("./savegame.json").expect("Unable to delete file");
+pub fn delete_save(slot: &str) {
+    let path = slot_path(slot);
+    if path.exists() {
+        fs::remove_file(path).expect("Unable to delete file");
     }
 }
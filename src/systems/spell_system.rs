@@ -0,0 +1,119 @@
+use crate::{
+    effects::{aoe_tiles, EffectQueue, EffectType, Targets},
+    gamelog::GameLog,
+    practice_skills,
+    raws::{spell_info, RAWS},
+    skill_bonus, Attributes, KnownSpells, Map, Name, Pools, Skill, Skills, WantsToCastSpell,
+};
+use specs::prelude::*;
+
+/// How hard a spell is to cast: a d20 plus the caster's Intelligence bonus and Magic skill
+/// has to reach this for it to work.
+pub fn spell_difficulty(mana_cost: i32) -> i32 {
+    5 + mana_cost
+}
+
+pub struct SpellCastSystem {}
+
+impl<'a> System<'a> for SpellCastSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, Map>,
+        WriteExpect<'a, GameLog>,
+        WriteStorage<'a, WantsToCastSpell>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Attributes>,
+        WriteStorage<'a, Skills>,
+        WriteStorage<'a, Pools>,
+        ReadStorage<'a, KnownSpells>,
+        WriteExpect<'a, rltk::RandomNumberGenerator>,
+        WriteExpect<'a, EffectQueue>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            player_entity,
+            map,
+            mut log,
+            mut wants_cast,
+            names,
+            attributes,
+            mut skills,
+            mut pools,
+            known_spells,
+            mut rng,
+            mut effect_queue,
+        ) = data;
+
+        let casts: Vec<(Entity, WantsToCastSpell)> = (&entities, &wants_cast)
+            .join()
+            .map(|(entity, cast)| (entity, cast.clone()))
+            .collect();
+        wants_cast.clear();
+
+        let raws = RAWS.lock().unwrap();
+        let mut practiced: Vec<(Entity, Skill)> = Vec::new();
+        for (entity, cast) in casts {
+            let knows_it = known_spells
+                .get(entity)
+                .is_some_and(|known| known.spells.contains(&cast.spell));
+            let (true, Some(info), Some(name), Some(caster_pools)) = (
+                knows_it,
+                spell_info(&raws, &cast.spell),
+                names.get(entity),
+                pools.get_mut(entity),
+            ) else {
+                continue;
+            };
+            if caster_pools.mana.current < info.mana_cost {
+                continue;
+            }
+            caster_pools.mana.current -= info.mana_cost;
+
+            let natural_roll = rng.roll_dice(1, 20);
+            let intelligence_bonus = attributes
+                .get(entity)
+                .map_or(0, |attr| attr.intelligence.bonus);
+            let magic_bonus = skills
+                .get(entity)
+                .map_or(0, |skills| skill_bonus(Skill::Magic, skills));
+            let bonus = intelligence_bonus + magic_bonus;
+            if natural_roll == 1
+                || (natural_roll != 20 && natural_roll + bonus < spell_difficulty(info.mana_cost))
+            {
+                log.entries.push(format!(
+                    "{} tries to cast {}, but the spell fizzles.",
+                    name.name, cast.spell
+                ));
+                continue;
+            }
+
+            practiced.push((entity, Skill::Magic));
+            if entity != *player_entity {
+                log.entries
+                    .push(format!("{} casts {}.", name.name, cast.spell));
+            }
+            effect_queue.add_effect(
+                Some(entity),
+                EffectType::SpellUse {
+                    spell: cast.spell.clone(),
+                    power: 0.max(bonus),
+                },
+                match (cast.target, info.radius) {
+                    (None, _) => Targets::Single { target: entity },
+                    (Some(target), Some(radius)) => Targets::Tiles {
+                        tiles: aoe_tiles(&map, target, radius),
+                    },
+                    (Some(target), None) => Targets::Tile {
+                        tile_idx: map.xy_idx(target.x, target.y) as i32,
+                    },
+                },
+            );
+        }
+
+        practice_skills(&practiced, &mut skills, &pools, *player_entity, &mut log);
+    }
+}
@@ -60,7 +60,7 @@ impl<'a> System<'a> for VisibilitySystem {
                         map.visible_tiles[idx] = true;
 
                         // Chance to reveal hidden things
-                        crate::spatial::for_each_tile_content(idx, |e| {
+                        map.spatial.for_each_tile_content(idx, |e| {
                             let maybe_hidden = hidden.get(e);
                             if let Some(_hidden) = maybe_hidden {
                                 if rng.roll_dice(1, 24) == 1 {
@@ -0,0 +1,150 @@
+//! Charged items keep working until their charges run out, and can be recharged by a scroll
+//! or a vendor at the risk of blowing up.
+
+mod common;
+
+use common::{backpack_index, give, hit_points, logged, pools, set_gold};
+use roguelike_rust::{
+    pay_for_recharge, recharge_explosion_chance, Charges, Consumable, Game, PlayerCommand,
+    ProvidesHealing, ProvidesRecharge, Stackable,
+};
+use specs::prelude::*;
+
+fn use_item(game: &mut Game, item: Entity) {
+    let item = backpack_index(game, item);
+    game.apply(PlayerCommand::UseItem { item, target: None });
+}
+
+fn charges(game: &Game, item: Entity) -> Charges {
+    game.world()
+        .read_storage::<Charges>()
+        .get(item)
+        .unwrap()
+        .clone()
+}
+
+fn gold(game: &mut Game) -> f32 {
+    let player = game.player();
+    pools(game).get(player).unwrap().gold
+}
+
+fn game() -> Game {
+    let mut game = Game::with_seed(11);
+    game.apply(PlayerCommand::Wait);
+    game
+}
+
+/// Gives the player a two-charge wand with `remaining` charges left.
+fn give_wand(game: &mut Game, remaining: i32, times_recharged: i32) -> Entity {
+    give(game, "Wand of Mending")
+        .with(Consumable {})
+        .with(ProvidesHealing { heal_amount: 1 })
+        .with(Charges {
+            remaining,
+            max: 2,
+            times_recharged,
+        })
+        .build()
+}
+
+fn give_recharging_scroll(game: &mut Game) -> Entity {
+    give(game, "Recharging Scroll")
+        .with(Consumable {})
+        .with(ProvidesRecharge {})
+        .build()
+}
+
+#[test]
+fn each_use_spends_a_charge() {
+    let mut game = game();
+    let wand = give_wand(&mut game, 2, 0);
+    use_item(&mut game, wand);
+    assert_eq!(charges(&game, wand).remaining, 1);
+    use_item(&mut game, wand);
+    assert_eq!(charges(&game, wand).remaining, 0);
+
+    // A spent wand is kept but does nothing
+    assert!(game.world().is_alive(wand));
+    use_item(&mut game, wand);
+    assert!(logged(
+        &game,
+        "You use the Wand of Mending (0 charges), but nothing happens."
+    ));
+}
+
+#[test]
+fn a_scroll_with_nothing_to_recharge_is_kept() {
+    let mut game = game();
+    let scroll = give_recharging_scroll(&mut game);
+    use_item(&mut game, scroll);
+    assert!(logged(&game, "You have nothing to recharge."));
+    assert!(game.world().is_alive(scroll));
+}
+
+#[test]
+fn the_first_recharge_is_always_safe() {
+    let mut game = game();
+    let wand = give_wand(&mut game, 0, 0);
+    let scroll = give_recharging_scroll(&mut game);
+    assert_eq!(recharge_explosion_chance(0), 0);
+    game.apply(PlayerCommand::Recharge {
+        scroll: backpack_index(&game, scroll),
+        item: backpack_index(&game, wand),
+    });
+    assert!(!game.world().is_alive(scroll));
+    assert_eq!(charges(&game, wand).remaining, 2);
+    assert_eq!(charges(&game, wand).times_recharged, 1);
+    assert!(logged(
+        &game,
+        "The Wand of Mending (2 charges) glows; it is fully charged."
+    ));
+}
+
+#[test]
+fn reading_one_scroll_of_a_stack_leaves_the_rest() {
+    let mut game = game();
+    let wand = give_wand(&mut game, 0, 0);
+    let scrolls = give_recharging_scroll(&mut game);
+    game.world_mut()
+        .write_storage::<Stackable>()
+        .insert(scrolls, Stackable { quantity: 2 })
+        .unwrap();
+    game.apply(PlayerCommand::Recharge {
+        scroll: backpack_index(&game, scrolls),
+        item: backpack_index(&game, wand),
+    });
+    assert_eq!(charges(&game, wand).remaining, 2);
+    assert!(game.world().is_alive(scrolls));
+    assert_eq!(
+        game.world()
+            .read_storage::<Stackable>()
+            .get(scrolls)
+            .unwrap()
+            .quantity,
+        1
+    );
+}
+
+#[test]
+fn a_vendor_wants_paying() {
+    let mut game = game();
+    let wand = give_wand(&mut game, 0, 1);
+    set_gold(&mut game, 50.0);
+    assert!(!pay_for_recharge(game.world_mut(), wand, 75.0));
+    assert!(logged(&game, "You can't afford to have that recharged."));
+    assert_eq!(gold(&mut game), 50.0);
+}
+
+#[test]
+fn a_wand_recharged_too_often_explodes() {
+    let mut game = game();
+    let wand = give_wand(&mut game, 0, 4);
+    set_gold(&mut game, 50.0);
+    assert_eq!(recharge_explosion_chance(4), 100);
+    let hp = hit_points(&game);
+    assert!(pay_for_recharge(game.world_mut(), wand, 20.0));
+    assert_eq!(gold(&mut game), 30.0);
+    assert!(!game.world().is_alive(wand));
+    game.apply(PlayerCommand::Wait);
+    assert!(hit_points(&game) < hp);
+}
@@ -0,0 +1,87 @@
+//! Helpers shared by the integration tests, which each build a `Game` and play it.
+#![allow(dead_code)]
+
+use roguelike_rust::{
+    backpack_items, Game, GameLog, InBackpack, Item, Name, PlayerCommand, Pools, Position,
+};
+use specs::prelude::*;
+
+pub fn logged(game: &Game, entry: &str) -> bool {
+    game.world()
+        .fetch::<GameLog>()
+        .entries
+        .iter()
+        .any(|e| e == entry)
+}
+
+pub fn player_position(game: &Game) -> (i32, i32) {
+    let positions = game.world().read_storage::<Position>();
+    let pos = positions.get(game.player()).unwrap();
+    (pos.x, pos.y)
+}
+
+pub fn hit_points(game: &Game) -> i32 {
+    game.world()
+        .read_storage::<Pools>()
+        .get(game.player())
+        .unwrap()
+        .hit_points
+        .current
+}
+
+pub fn set_hit_points(game: &mut Game, amount: i32) {
+    let player = game.player();
+    game.world_mut()
+        .write_storage::<Pools>()
+        .get_mut(player)
+        .unwrap()
+        .hit_points
+        .current = amount;
+}
+
+/// Whether any log entry starts with `prefix`, for messages that end in a roll.
+pub fn logged_starting(game: &Game, prefix: &str) -> bool {
+    game.world()
+        .fetch::<GameLog>()
+        .entries
+        .iter()
+        .any(|e| e.starts_with(prefix))
+}
+
+pub fn pools(game: &mut Game) -> WriteStorage<'_, Pools> {
+    game.world_mut().write_storage::<Pools>()
+}
+
+/// Uses the item at the end of the backpack, which is the one given last.
+pub fn use_last_item(game: &mut Game) {
+    let item = backpack_items(game.world()).len() - 1;
+    game.apply(PlayerCommand::UseItem { item, target: None });
+}
+
+/// Puts a new item in the player's backpack; add whatever else it needs and build it.
+pub fn give<'a>(game: &'a mut Game, name: &str) -> EntityBuilder<'a> {
+    let player = game.player();
+    game.world_mut()
+        .create_entity()
+        .with(Name {
+            name: name.to_string(),
+        })
+        .with(Item {
+            initiative_penalty: 0.0,
+            weight_lbs: 0.0,
+            base_value: 0.0,
+        })
+        .with(InBackpack { owner: player })
+}
+
+pub fn backpack_index(game: &Game, item: Entity) -> usize {
+    backpack_items(game.world())
+        .iter()
+        .position(|e| *e == item)
+        .unwrap()
+}
+
+pub fn set_gold(game: &mut Game, gold: f32) {
+    let player = game.player();
+    pools(game).get_mut(player).unwrap().gold = gold;
+}
@@ -0,0 +1,219 @@
+//! Containers such as chests hold items the player can take out or put in, once any lock is
+//! picked and any trap has gone off, and keep them while the level is frozen.
+
+mod common;
+
+use common::{backpack_index, hit_points, logged, player_position};
+use rltk::Point;
+use roguelike_rust::{
+    backpack_items, container_items, container_pos, freeze_level_entities, thaw_level_entities,
+    Consumable, Container, Game, InBackpack, InflictsDamage, Item, Locked, Name, PlayerCommand,
+    Pools, Position, ProvidesHealing, SingleActivation, Trapped,
+};
+use specs::prelude::*;
+
+fn chest(game: &mut Game, x: i32, y: i32) -> Entity {
+    game.world_mut()
+        .create_entity()
+        .with(Name {
+            name: "Chest".to_string(),
+        })
+        .with(Container {})
+        .with(Position { x, y })
+        .build()
+}
+
+/// A game with a chest next to the player, holding a Health Potion.
+fn game_with_chest() -> (Game, Entity, Entity) {
+    let mut game = Game::with_seed(11);
+    game.apply(PlayerCommand::Wait);
+    let (x, y) = player_position(&game);
+    let chest = chest(&mut game, x + 1, y);
+    let potion = game
+        .world_mut()
+        .create_entity()
+        .with(Name {
+            name: "Health Potion".to_string(),
+        })
+        .with(Item {
+            initiative_penalty: 0.0,
+            weight_lbs: 0.5,
+            base_value: 50.0,
+        })
+        .with(Consumable {})
+        .with(ProvidesHealing { heal_amount: 8 })
+        .with(InBackpack { owner: chest })
+        .build();
+    (game, chest, potion)
+}
+
+#[test]
+fn only_a_container_next_to_the_player_can_be_opened() {
+    let mut game = Game::with_seed(11);
+    game.apply(PlayerCommand::Wait);
+    let (x, y) = player_position(&game);
+    chest(&mut game, x + 2, y);
+    game.apply(PlayerCommand::Open);
+    assert!(logged(&game, "There is nothing here to open."));
+}
+
+#[test]
+fn items_move_between_backpack_and_container() {
+    let (mut game, chest, potion) = game_with_chest();
+    assert_eq!(container_items(game.world(), chest), vec![potion]);
+    assert!(!backpack_items(game.world()).contains(&potion));
+
+    game.apply(PlayerCommand::Open);
+    game.apply(PlayerCommand::TakeItem {
+        container: container_pos(game.world(), chest).unwrap(),
+        item: 0,
+    });
+    assert!(logged(&game, "You take the Health Potion from the Chest."));
+    assert!(container_items(game.world(), chest).is_empty());
+    let item = backpack_index(&game, potion);
+    game.apply(PlayerCommand::PutItem {
+        container: container_pos(game.world(), chest).unwrap(),
+        item,
+    });
+    assert!(logged(&game, "You put the Health Potion in the Chest."));
+    assert_eq!(container_items(game.world(), chest), vec![potion]);
+}
+
+#[test]
+fn locks_are_picked_or_keep_the_container_shut() {
+    let (mut game, chest, _) = game_with_chest();
+
+    // A lock too hard to pick keeps it shut; an easy one is picked and gone
+    game.world_mut()
+        .write_storage::<Locked>()
+        .insert(chest, Locked { difficulty: 100 })
+        .unwrap();
+    game.apply(PlayerCommand::Open);
+    assert!(logged(&game, "You fail to pick the lock on the Chest."));
+    game.world_mut()
+        .write_storage::<Locked>()
+        .insert(chest, Locked { difficulty: 1 })
+        .unwrap();
+    game.apply(PlayerCommand::Open);
+    assert!(logged(&game, "You pick the lock on the Chest."));
+    assert!(game.world().read_storage::<Locked>().get(chest).is_none());
+}
+
+#[test]
+fn items_move_to_the_container_they_were_meant_for() {
+    let (mut game, chest, potion) = game_with_chest();
+    let (x, y) = player_position(&game);
+    let other = self::chest(&mut game, x - 1, y);
+    let other_pos = container_pos(game.world(), other).unwrap();
+    game.apply(PlayerCommand::Open);
+    game.apply(PlayerCommand::TakeItem {
+        container: container_pos(game.world(), chest).unwrap(),
+        item: 0,
+    });
+    let item = backpack_index(&game, potion);
+    game.apply(PlayerCommand::PutItem {
+        container: other_pos,
+        item,
+    });
+    assert_eq!(container_items(game.world(), other), vec![potion]);
+    assert!(container_items(game.world(), chest).is_empty());
+
+    // Once out of reach, a container can't be used at all
+    game.world_mut()
+        .write_storage::<Position>()
+        .insert(other, Position { x: x - 3, y })
+        .unwrap();
+    game.apply(PlayerCommand::TakeItem {
+        container: Point::new(x - 3, y),
+        item: 0,
+    });
+    assert_eq!(container_items(game.world(), other), vec![potion]);
+}
+
+#[test]
+fn a_locked_container_keeps_its_items() {
+    let (mut game, chest, potion) = game_with_chest();
+    game.world_mut()
+        .write_storage::<Locked>()
+        .insert(chest, Locked { difficulty: 100 })
+        .unwrap();
+    game.apply(PlayerCommand::TakeItem {
+        container: container_pos(game.world(), chest).unwrap(),
+        item: 0,
+    });
+    assert!(logged(&game, "The Chest isn't open."));
+    assert_eq!(container_items(game.world(), chest), vec![potion]);
+}
+
+#[test]
+fn a_trap_goes_off_on_whoever_opens_it_once() {
+    let (mut game, chest, _) = game_with_chest();
+    let trap = game
+        .world_mut()
+        .create_entity()
+        .with(Name {
+            name: "Chest".to_string(),
+        })
+        .with(InflictsDamage {
+            n_dice: 1,
+            die_type: 1,
+            bonus: 2,
+        })
+        .with(SingleActivation {})
+        .build();
+    game.world_mut()
+        .write_storage::<Trapped>()
+        .insert(chest, Trapped { trap })
+        .unwrap();
+    let hp = hit_points(&game);
+    game.apply(PlayerCommand::Open);
+    assert!(logged(&game, "Chest triggers!"));
+    assert_eq!(hit_points(&game), hp - 3);
+
+    // The trap and its effects are gone, and the chest opens as any other
+    assert!(game.world().read_storage::<Trapped>().get(chest).is_none());
+    assert!(!game.world().is_alive(trap));
+    game.apply(PlayerCommand::Open);
+    assert_eq!(hit_points(&game), hp - 3);
+}
+
+#[test]
+fn a_destroyed_container_takes_its_trap_with_it() {
+    let (mut game, chest, _) = game_with_chest();
+    let trap = game
+        .world_mut()
+        .create_entity()
+        .with(SingleActivation {})
+        .build();
+    let player = game.player();
+    let mut pools = game
+        .world()
+        .read_storage::<Pools>()
+        .get(player)
+        .unwrap()
+        .clone();
+    pools.hit_points.current = 0;
+    {
+        let world = game.world_mut();
+        world
+            .write_storage::<Trapped>()
+            .insert(chest, Trapped { trap })
+            .unwrap();
+        world.write_storage::<Pools>().insert(chest, pools).unwrap();
+    }
+    game.apply(PlayerCommand::Wait);
+    assert!(!game.world().is_alive(chest));
+    assert!(!game.world().is_alive(trap));
+}
+
+#[test]
+fn frozen_levels_keep_what_is_inside() {
+    let (mut game, chest, potion) = game_with_chest();
+    freeze_level_entities(game.world_mut());
+    assert!(game.world().read_storage::<Position>().get(chest).is_none());
+    assert_eq!(container_pos(game.world(), chest), None);
+    assert_eq!(container_items(game.world(), chest), vec![potion]);
+    thaw_level_entities(game.world_mut());
+    assert!(game.world().read_storage::<Position>().get(chest).is_some());
+    assert_eq!(container_items(game.world(), chest), vec![potion]);
+}
@@ -0,0 +1,123 @@
+//! Cursed equipment stays on until the curse is lifted, by a scroll or at the temple.
+
+mod common;
+
+use common::{give, logged, set_gold, use_last_item};
+use roguelike_rust::{
+    equipped_items, lift_curse, Consumable, CursedItem, EquipmentSlot, Equippable, Game,
+    MasterDungeonMap, PlayerCommand, Pools, ProvidesRemoveCurse,
+};
+use specs::prelude::*;
+
+fn remove(game: &mut Game, item: Entity) {
+    let item = equipped_items(game.world())
+        .iter()
+        .position(|e| *e == item)
+        .unwrap();
+    game.apply(PlayerCommand::RemoveItem { item });
+}
+
+fn is_equipped(game: &Game, item: Entity) -> bool {
+    equipped_items(game.world()).contains(&item)
+}
+
+fn is_cursed(game: &Game, item: Entity) -> bool {
+    game.world()
+        .read_storage::<CursedItem>()
+        .get(item)
+        .is_some()
+}
+
+fn hands() -> Equippable {
+    Equippable {
+        slot: EquipmentSlot::Hands,
+    }
+}
+
+/// A game where the player has put on cursed gauntlets.
+fn cursed_game() -> (Game, Entity) {
+    let mut game = Game::with_seed(11);
+    game.apply(PlayerCommand::Wait);
+    let gauntlets = give(&mut game, "Gauntlets of Fumbling")
+        .with(hands())
+        .with(CursedItem {})
+        .build();
+    use_last_item(&mut game);
+    (game, gauntlets)
+}
+
+#[test]
+fn cursed_items_cling_to_their_wearer() {
+    let (mut game, gauntlets) = cursed_game();
+    assert!(is_equipped(&game, gauntlets));
+    assert!(logged(
+        &game,
+        "The Gauntlets of Fumbling clings to you; it's cursed!"
+    ));
+
+    // Neither taking it off nor putting something else on works
+    remove(&mut game, gauntlets);
+    assert!(is_equipped(&game, gauntlets));
+    assert!(logged(
+        &game,
+        "You can't remove the Gauntlets of Fumbling; it's cursed!"
+    ));
+    let gloves = give(&mut game, "Leather Gloves").with(hands()).build();
+    use_last_item(&mut game);
+    assert!(is_equipped(&game, gauntlets));
+    assert!(!is_equipped(&game, gloves));
+}
+
+#[test]
+fn nothing_is_learned_from_what_cursed_gear_keeps_off() {
+    let (mut game, gauntlets) = cursed_game();
+    let gloves = give(&mut game, "Troll-Hide Gloves").with(hands()).build();
+    use_last_item(&mut game);
+    assert!(is_equipped(&game, gauntlets));
+    assert!(!is_equipped(&game, gloves));
+    assert!(!game
+        .world()
+        .fetch::<MasterDungeonMap>()
+        .identified_items
+        .contains("Troll-Hide Gloves"));
+}
+
+#[test]
+fn the_temple_lifts_curses_for_a_price() {
+    let (mut game, gauntlets) = cursed_game();
+    set_gold(&mut game, 10.0);
+    lift_curse(game.world_mut(), gauntlets, 100.0);
+    assert!(logged(&game, "You can't afford the priest's blessing."));
+    assert!(is_cursed(&game, gauntlets));
+
+    set_gold(&mut game, 150.0);
+    lift_curse(game.world_mut(), gauntlets, 100.0);
+    assert!(!is_cursed(&game, gauntlets));
+    let player = game.player();
+    assert_eq!(
+        game.world()
+            .read_storage::<Pools>()
+            .get(player)
+            .unwrap()
+            .gold,
+        50.0
+    );
+}
+
+#[test]
+fn a_remove_curse_scroll_lifts_the_curse() {
+    let (mut game, gauntlets) = cursed_game();
+    give(&mut game, "Remove Curse Scroll")
+        .with(Consumable {})
+        .with(ProvidesRemoveCurse {})
+        .build();
+    use_last_item(&mut game);
+    assert!(logged(
+        &game,
+        "The curse on your Gauntlets of Fumbling lifts."
+    ));
+
+    // With the curse gone, it comes off like anything else
+    remove(&mut game, gauntlets);
+    assert!(!is_equipped(&game, gauntlets));
+}
@@ -0,0 +1,127 @@
+//! Equipment can raise its wearer's attributes and skills for as long as it's worn.
+
+mod common;
+
+use common::use_last_item;
+use roguelike_rust::{
+    equipped_items, player_hp_at_level, skill_bonus, AttributeBonus, Attributes, EquipmentSlot,
+    Equippable, Game, InBackpack, Item, Name, PlayerCommand, Pools, Skill, SkillBonus, Skills,
+};
+use specs::prelude::*;
+use std::collections::HashMap;
+
+fn attributes(game: &Game) -> Attributes {
+    game.world()
+        .read_storage::<Attributes>()
+        .get(game.player())
+        .unwrap()
+        .clone()
+}
+
+fn melee_bonus(game: &Game) -> i32 {
+    skill_bonus(
+        Skill::Melee,
+        game.world()
+            .read_storage::<Skills>()
+            .get(game.player())
+            .unwrap(),
+    )
+}
+
+fn max_hit_points(game: &Game) -> i32 {
+    game.world()
+        .read_storage::<Pools>()
+        .get(game.player())
+        .unwrap()
+        .hit_points
+        .max
+}
+
+/// Gives the player gauntlets that raise might, fitness and melee and lower quickness, and
+/// puts them on.
+fn wear_gauntlets(game: &mut Game) {
+    let player = game.player();
+    game.world_mut()
+        .create_entity()
+        .with(Name {
+            name: "Gauntlets of Testing".to_string(),
+        })
+        .with(Item {
+            initiative_penalty: 0.0,
+            weight_lbs: 0.0,
+            base_value: 0.0,
+        })
+        .with(Equippable {
+            slot: EquipmentSlot::Hands,
+        })
+        .with(AttributeBonus {
+            might: 2,
+            fitness: 2,
+            quickness: -2,
+            intelligence: 0,
+        })
+        .with(SkillBonus {
+            skills: HashMap::from([(Skill::Melee, 3)]),
+        })
+        .with(InBackpack { owner: player })
+        .build();
+    use_last_item(game);
+}
+
+#[test]
+fn wearing_equipment_applies_its_bonuses() {
+    let mut game = Game::with_seed(11);
+    game.apply(PlayerCommand::Wait);
+
+    let before = attributes(&game);
+    let melee_before = melee_bonus(&game);
+    assert_eq!(
+        max_hit_points(&game),
+        player_hp_at_level(before.fitness.base, 1)
+    );
+
+    // Wearing them changes the modifiers, bonuses and everything that follows from them
+    wear_gauntlets(&mut game);
+    let worn = attributes(&game);
+    assert_eq!(worn.might.modifiers, 2);
+    assert_eq!(worn.might.bonus, (before.might.base + 2 - 10) / 2);
+    assert_eq!(worn.quickness.modifiers, -2);
+    assert_eq!(worn.quickness.bonus, (before.quickness.base - 2 - 10) / 2);
+    assert_eq!(worn.intelligence.modifiers, 0);
+    assert_eq!(melee_bonus(&game), melee_before + 3);
+    assert_eq!(
+        max_hit_points(&game),
+        player_hp_at_level(before.fitness.base + 2, 1)
+    );
+}
+
+#[test]
+fn taking_equipment_off_puts_everything_back() {
+    let mut game = Game::with_seed(11);
+    let player = game.player();
+    game.apply(PlayerCommand::Wait);
+
+    let before = attributes(&game);
+    let melee_before = melee_bonus(&game);
+    let hp_before = max_hit_points(&game);
+    wear_gauntlets(&mut game);
+
+    let item = equipped_items(game.world())
+        .iter()
+        .position(|item| {
+            game.world()
+                .read_storage::<AttributeBonus>()
+                .get(*item)
+                .is_some()
+        })
+        .unwrap();
+    game.apply(PlayerCommand::RemoveItem { item });
+    let removed = attributes(&game);
+    assert_eq!(removed.might.modifiers, 0);
+    assert_eq!(removed.might.bonus, before.might.bonus);
+    assert_eq!(removed.quickness.bonus, before.quickness.bonus);
+    assert_eq!(melee_bonus(&game), melee_before);
+    assert_eq!(max_hit_points(&game), hp_before);
+    let pools = game.world().read_storage::<Pools>();
+    assert!(pools.get(player).unwrap().hit_points.current <= hp_before);
+}
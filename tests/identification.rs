@@ -0,0 +1,184 @@
+//! Unidentified items are learned by reading an Identify scroll on them or by seeing what
+//! they do, and collect hints along the way.
+
+mod common;
+
+use common::{backpack_index, logged};
+use roguelike_rust::{
+    annotate_item, sell_item, Consumable, Game, InBackpack, Item, ItemNote, MagicItem,
+    MagicItemClass, MasterDungeonMap, Name, ObfuscatedName, PlayerCommand, ProvidesHealing,
+    ProvidesIdentification, ProvidesRemoveCurse, Stackable,
+};
+use specs::prelude::*;
+
+/// Puts an unidentified magic item from the raws in the player's backpack, under the name
+/// this run gave it.
+fn give_unknown<'a>(game: &'a mut Game, name: &str) -> (EntityBuilder<'a>, String) {
+    let player = game.player();
+    let obfuscated = {
+        let dm = game.world().fetch::<MasterDungeonMap>();
+        dm.scroll_mappings
+            .get(name)
+            .or_else(|| dm.potion_mappings.get(name))
+            .unwrap()
+            .clone()
+    };
+    let builder = game
+        .world_mut()
+        .create_entity()
+        .with(Name {
+            name: name.to_string(),
+        })
+        .with(Item {
+            initiative_penalty: 0.0,
+            weight_lbs: 0.0,
+            base_value: 50.0,
+        })
+        .with(MagicItem {
+            class: MagicItemClass::Common,
+        })
+        .with(ObfuscatedName {
+            name: obfuscated.clone(),
+        })
+        .with(Consumable {})
+        .with(InBackpack { owner: player });
+    (builder, obfuscated)
+}
+
+fn is_identified(game: &Game, name: &str) -> bool {
+    game.world()
+        .fetch::<MasterDungeonMap>()
+        .identified_items
+        .contains(name)
+}
+
+fn note(game: &Game, name: &str) -> ItemNote {
+    game.world()
+        .fetch::<MasterDungeonMap>()
+        .item_notes
+        .get(name)
+        .cloned()
+        .unwrap_or_default()
+}
+
+fn game() -> Game {
+    let mut game = Game::with_seed(11);
+    game.apply(PlayerCommand::Wait);
+    game
+}
+
+/// Reads an Identify scroll on a Health Potion, returning them and the potion's unknown
+/// name.
+fn identify_a_potion(game: &mut Game) -> (Entity, Entity, String) {
+    let (builder, _) = give_unknown(game, "Identify Scroll");
+    let scroll = builder.with(ProvidesIdentification {}).build();
+    let (builder, potion_name) = give_unknown(game, "Health Potion");
+    let potion = builder.with(ProvidesHealing { heal_amount: 8 }).build();
+    game.apply(PlayerCommand::Identify {
+        scroll: backpack_index(game, scroll),
+        item: backpack_index(game, potion),
+    });
+    (scroll, potion, potion_name)
+}
+
+#[test]
+fn identify_needs_something_unknown() {
+    let mut game = game();
+    let (builder, _) = give_unknown(&mut game, "Identify Scroll");
+    let scroll = builder.with(ProvidesIdentification {}).build();
+    let item = backpack_index(&game, scroll);
+    game.apply(PlayerCommand::UseItem { item, target: None });
+    assert!(logged(&game, "You have nothing to identify."));
+    assert!(game.world().entities().is_alive(scroll));
+}
+
+#[test]
+fn identifying_makes_both_kinds_known() {
+    let mut game = game();
+    let (scroll, potion, potion_name) = identify_a_potion(&mut game);
+    assert!(is_identified(&game, "Health Potion"));
+    assert!(is_identified(&game, "Identify Scroll"));
+    assert!(game
+        .world()
+        .read_storage::<ObfuscatedName>()
+        .get(potion)
+        .is_none());
+    assert!(!game.world().entities().is_alive(scroll));
+    assert!(logged(
+        &game,
+        &format!(
+            "You read the Identify Scroll. The {} is a Health Potion.",
+            potion_name
+        )
+    ));
+}
+
+#[test]
+fn reading_one_scroll_of_a_stack_leaves_the_rest() {
+    let mut game = game();
+    let (builder, _) = give_unknown(&mut game, "Identify Scroll");
+    let scrolls = builder
+        .with(ProvidesIdentification {})
+        .with(Stackable { quantity: 3 })
+        .build();
+    let (builder, _) = give_unknown(&mut game, "Health Potion");
+    let potion = builder.with(ProvidesHealing { heal_amount: 8 }).build();
+    game.apply(PlayerCommand::Identify {
+        scroll: backpack_index(&game, scrolls),
+        item: backpack_index(&game, potion),
+    });
+    assert!(is_identified(&game, "Health Potion"));
+    assert!(game.world().entities().is_alive(scrolls));
+    assert_eq!(
+        game.world()
+            .read_storage::<Stackable>()
+            .get(scrolls)
+            .unwrap()
+            .quantity,
+        2
+    );
+}
+
+#[test]
+fn items_that_do_nothing_are_noted_as_tried() {
+    let mut game = game();
+    let (builder, _) = give_unknown(&mut game, "Remove Curse Scroll");
+    let remove_curse = builder.with(ProvidesRemoveCurse {}).build();
+    let item = backpack_index(&game, remove_curse);
+    game.apply(PlayerCommand::UseItem { item, target: None });
+    game.apply(PlayerCommand::Wait);
+    assert!(!game.world().entities().is_alive(remove_curse));
+    assert!(!is_identified(&game, "Remove Curse Scroll"));
+    assert!(note(&game, "Remove Curse Scroll").tried);
+}
+
+#[test]
+fn selling_and_writing_add_to_the_notes() {
+    let mut game = game();
+    let (builder, _) = give_unknown(&mut game, "Remove Curse Scroll");
+    let for_sale = builder.build();
+    let (builder, _) = give_unknown(&mut game, "Remove Curse Scroll");
+    let kept = builder.build();
+    sell_item(game.world_mut(), for_sale);
+    annotate_item(game.world_mut(), kept, "  not identify  ");
+    assert_eq!(
+        note(&game, "Remove Curse Scroll"),
+        ItemNote {
+            tried: false,
+            sold_for: Some(40.0),
+            text: "not identify".to_string(),
+        }
+    );
+    assert_eq!(
+        note(&game, "Remove Curse Scroll").describe().unwrap(),
+        "{sold for 40 gp; not identify}"
+    );
+}
+
+#[test]
+fn known_kinds_take_no_notes() {
+    let mut game = game();
+    let (_, potion, _) = identify_a_potion(&mut game);
+    annotate_item(game.world_mut(), potion, "healing");
+    assert_eq!(note(&game, "Health Potion"), ItemNote::default());
+}
@@ -0,0 +1,96 @@
+//! Using items and springing traps both go through the effects system.
+
+mod common;
+
+use common::{hit_points, logged, player_position, set_hit_points};
+use roguelike_rust::{
+    backpack_items, EntryTrigger, Game, HungerClock, HungerState, InflictsDamage, Name,
+    PlayerCommand, Position, SingleActivation,
+};
+use specs::prelude::*;
+
+fn backpack_index(game: &Game, name: &str) -> (usize, Entity) {
+    let names = game.world().read_storage::<Name>();
+    backpack_items(game.world())
+        .into_iter()
+        .enumerate()
+        .find(|(_, item)| names.get(*item).unwrap().name == name)
+        .unwrap()
+}
+
+#[test]
+fn eating_food_feeds_the_player() {
+    let mut game = Game::with_seed(11);
+    let (index, sausage) = backpack_index(&game, "Dried Sausage");
+    game.apply(PlayerCommand::UseItem {
+        item: index,
+        target: None,
+    });
+    assert!(!game.world().is_alive(sausage));
+    assert!(logged(&game, "You eat the Dried Sausage."));
+    assert!(
+        game.world()
+            .read_storage::<HungerClock>()
+            .get(game.player())
+            .unwrap()
+            .state
+            == HungerState::WellFed
+    );
+}
+
+#[test]
+fn drinking_heals_the_player() {
+    let mut game = Game::with_seed(11);
+    set_hit_points(&mut game, 1);
+    let (index, beer) = backpack_index(&game, "Beer");
+    game.apply(PlayerCommand::UseItem {
+        item: index,
+        target: None,
+    });
+    assert!(!game.world().is_alive(beer));
+    assert!(logged(&game, "You use the Beer, healing 4 hp."));
+    assert!(hit_points(&game) >= 5);
+}
+
+#[test]
+fn a_trap_goes_off_when_stepped_on() {
+    let mut game = Game::with_seed(11);
+    let start = player_position(&game);
+    let trap = game
+        .world_mut()
+        .create_entity()
+        .with(Position {
+            x: start.0,
+            y: start.1,
+        })
+        .with(Name {
+            name: "Test Trap".to_string(),
+        })
+        .with(EntryTrigger {})
+        .with(InflictsDamage {
+            n_dice: 0,
+            die_type: 0,
+            bonus: 3,
+        })
+        .with(SingleActivation {})
+        .build();
+    let (delta_x, delta_y) = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+        .into_iter()
+        .find(|(delta_x, delta_y)| {
+            game.apply(PlayerCommand::Move {
+                delta_x: *delta_x,
+                delta_y: *delta_y,
+            });
+            player_position(&game) != start
+        })
+        .expect("the player can't move");
+    let before = hit_points(&game);
+    game.apply(PlayerCommand::Move {
+        delta_x: -delta_x,
+        delta_y: -delta_y,
+    });
+    assert_eq!(player_position(&game), start);
+    assert!(logged(&game, "Test Trap triggers!"));
+    assert_eq!(hit_points(&game), before - 3);
+    assert!(!game.world().is_alive(trap));
+}
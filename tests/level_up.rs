@@ -0,0 +1,150 @@
+//! Levelling up banks points for the player to spend on attributes and skills.
+
+mod common;
+
+use common::logged;
+use roguelike_rust::{
+    effects::{add_effect, EffectType, Targets},
+    has_points_to_spend, mana_at_level, player_hp_at_level, AttributeName, Attributes, Game,
+    LevelUpPoints, PlayerCommand, Pool, Pools, RunState, Skill, Skills,
+};
+use specs::prelude::*;
+
+fn points(game: &Game) -> Option<(i32, i32)> {
+    game.world()
+        .read_storage::<LevelUpPoints>()
+        .get(game.player())
+        .map(|points| (points.attributes, points.skills))
+}
+
+fn skill(game: &Game, skill: Skill) -> i32 {
+    game.world()
+        .read_storage::<Skills>()
+        .get(game.player())
+        .unwrap()
+        .skills[&skill]
+}
+
+/// A game where the player has just made a kill worth 2500 xp, enough for two levels at
+/// once, and is on the level-up screen.
+fn levelled_game() -> Game {
+    let mut game = Game::with_seed(11);
+    let player = game.player();
+    game.apply(PlayerCommand::Wait);
+    let victim = game
+        .world_mut()
+        .create_entity()
+        .with(Pools {
+            hit_points: Pool { max: 1, current: 0 },
+            mana: Pool { max: 0, current: 0 },
+            xp: 0,
+            level: 25,
+            total_weight: 0.0,
+            total_initiative_penalty: 0.0,
+            gold: 0.0,
+            god_mode: false,
+            hp_regen_progress: 0,
+            mana_regen_progress: 0,
+        })
+        .build();
+    add_effect(
+        game.world(),
+        Some(player),
+        EffectType::EntityDeath,
+        Targets::Single { target: victim },
+    );
+    assert_eq!(game.apply(PlayerCommand::Wait), RunState::LevelUp);
+    game
+}
+
+#[test]
+fn a_big_kill_gains_several_levels() {
+    let game = levelled_game();
+    assert!(logged(&game, "Congratulations, you are now level 3"));
+    assert_eq!(
+        game.world()
+            .read_storage::<Pools>()
+            .get(game.player())
+            .unwrap()
+            .level,
+        3
+    );
+    assert_eq!(points(&game), Some((2, 4)));
+    assert!(has_points_to_spend(game.world()));
+}
+
+#[test]
+fn attribute_points_recalculate_bonuses_and_pools() {
+    let mut game = levelled_game();
+    let player = game.player();
+    let before = game
+        .world()
+        .read_storage::<Attributes>()
+        .get(player)
+        .unwrap()
+        .clone();
+    game.apply(PlayerCommand::RaiseAttribute {
+        attribute: AttributeName::Fitness,
+    });
+    game.apply(PlayerCommand::RaiseAttribute {
+        attribute: AttributeName::Intelligence,
+    });
+    assert!(logged(
+        &game,
+        &format!("Your Fitness rises to {}.", before.fitness.base + 1)
+    ));
+    {
+        let attributes = game.world().read_storage::<Attributes>();
+        let after = attributes.get(player).unwrap();
+        assert_eq!(after.fitness.base, before.fitness.base + 1);
+        assert_eq!(after.fitness.bonus, (after.fitness.base - 10) / 2);
+        assert_eq!(after.intelligence.bonus, (after.intelligence.base - 10) / 2);
+        let pools = game.world().read_storage::<Pools>();
+        let pools = pools.get(player).unwrap();
+        assert_eq!(
+            pools.hit_points.max,
+            player_hp_at_level(after.fitness.base, 3)
+        );
+        assert_eq!(pools.hit_points.current, pools.hit_points.max);
+        assert_eq!(pools.mana.max, mana_at_level(after.intelligence.base, 3));
+    }
+    assert_eq!(points(&game), Some((0, 4)));
+
+    // With no attribute points left, further raises are ignored
+    game.apply(PlayerCommand::RaiseAttribute {
+        attribute: AttributeName::Might,
+    });
+    assert_eq!(points(&game), Some((0, 4)));
+}
+
+#[test]
+fn skills_cannot_be_raised_past_the_cap() {
+    let mut game = levelled_game();
+    let magic = skill(&game, Skill::Magic);
+    for _ in 0..4 {
+        game.apply(PlayerCommand::RaiseSkill {
+            skill: Skill::Magic,
+        });
+    }
+    assert_eq!(skill(&game, Skill::Magic), 4);
+    assert_eq!(points(&game), Some((2, 4 - (4 - magic))));
+    assert!(logged(&game, "Your Magic skill rises to 4."));
+}
+
+#[test]
+fn spending_everything_leaves_the_level_up_screen() {
+    let mut game = levelled_game();
+    for attribute in [AttributeName::Fitness, AttributeName::Intelligence] {
+        game.apply(PlayerCommand::RaiseAttribute { attribute });
+    }
+    for skill in [Skill::Melee, Skill::Defense, Skill::Ranged, Skill::Magic]
+        .into_iter()
+        .cycle()
+        .take(16)
+    {
+        game.apply(PlayerCommand::RaiseSkill { skill });
+    }
+    assert_eq!(points(&game), None);
+    assert!(!has_points_to_spend(game.world()));
+    assert_eq!(game.run_state(), RunState::AwaitingInput);
+}
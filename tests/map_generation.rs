@@ -0,0 +1,248 @@
+//! Every builder, across many seeds, must produce a level the game can use: a walkable
+//! start, a reachable way down, a way up below the town, nothing spawned inside walls and
+//! a solid border to keep everything in.
+
+use rltk::RandomNumberGenerator;
+use roguelike_rust::map_builders::*;
+use roguelike_rust::{is_tile_walkable, load_raws, Map, TileType};
+use std::collections::VecDeque;
+use std::sync::{Mutex, Once};
+
+const WIDTH: i32 = 80;
+const HEIGHT: i32 = 50;
+const SEEDS: u64 = 20;
+
+static RAWS: Once = Once::new();
+// Some builders path through the global spatial map, so only one level is built at a time
+static BUILDING: Mutex<()> = Mutex::new(());
+
+fn setup() {
+    RAWS.call_once(|| {
+        load_raws();
+    });
+}
+
+/// Tiles reachable on foot from `start`.
+fn reachable(map: &Map, start: usize) -> Vec<bool> {
+    let mut seen = vec![false; map.tiles.len()];
+    let mut open = VecDeque::new();
+    seen[start] = true;
+    open.push_back(start);
+    while let Some(idx) = open.pop_front() {
+        let (x, y) = (idx as i32 % map.width, idx as i32 / map.width);
+        for (dx, dy) in [
+            (-1, 0),
+            (1, 0),
+            (0, -1),
+            (0, 1),
+            (-1, -1),
+            (1, -1),
+            (-1, 1),
+            (1, 1),
+        ] {
+            let (nx, ny) = (x + dx, y + dy);
+            if nx < 0 || nx >= map.width || ny < 0 || ny >= map.height {
+                continue;
+            }
+            let next = map.xy_idx(nx, ny);
+            if !seen[next] && is_tile_walkable(map.tiles[next]) {
+                seen[next] = true;
+                open.push_back(next);
+            }
+        }
+    }
+    seen
+}
+
+fn check_invariants(name: &str, seed: u64, chain: &BuilderChain) {
+    let build_data = &chain.build_data;
+    let map = &build_data.map;
+    let context = format!("{} (depth {}, seed {})", name, map.depth, seed);
+
+    for x in 0..map.width {
+        for y in [0, map.height - 1] {
+            assert!(
+                !is_tile_walkable(map.tiles[map.xy_idx(x, y)]),
+                "{}: border at {},{} is open",
+                context,
+                x,
+                y
+            );
+        }
+    }
+    for y in 0..map.height {
+        for x in [0, map.width - 1] {
+            assert!(
+                !is_tile_walkable(map.tiles[map.xy_idx(x, y)]),
+                "{}: border at {},{} is open",
+                context,
+                x,
+                y
+            );
+        }
+    }
+
+    let start = build_data
+        .starting_position
+        .as_ref()
+        .unwrap_or_else(|| panic!("{}: no starting position", context));
+    let start_idx = map.xy_idx(start.x, start.y);
+    assert!(
+        is_tile_walkable(map.tiles[start_idx]),
+        "{}: starting position {},{} isn't walkable",
+        context,
+        start.x,
+        start.y
+    );
+
+    let reachable = reachable(map, start_idx);
+    let down_stairs: Vec<usize> = (0..map.tiles.len())
+        .filter(|idx| map.tiles[*idx] == TileType::DownStairs)
+        .collect();
+    assert!(!down_stairs.is_empty(), "{}: no down stairs", context);
+    assert!(
+        down_stairs.iter().any(|idx| reachable[*idx]),
+        "{}: the down stairs can't be reached from the start",
+        context
+    );
+
+    if map.depth > 1 {
+        assert_eq!(
+            map.tiles[start_idx],
+            TileType::UpStairs,
+            "{}: no up stairs at the start",
+            context
+        );
+    }
+
+    for (idx, spawn) in build_data.spawn_list.iter() {
+        assert!(
+            map.tiles[*idx] != TileType::Wall,
+            "{}: {} spawned in a wall at {},{}",
+            context,
+            spawn,
+            *idx as i32 % map.width,
+            *idx as i32 / map.width
+        );
+    }
+}
+
+fn build_and_check<F>(name: &str, depth: i32, make_chain: F)
+where
+    F: Fn(&mut RandomNumberGenerator) -> BuilderChain,
+{
+    setup();
+    let _building = BUILDING.lock().unwrap_or_else(|e| e.into_inner());
+    for seed in 0..SEEDS {
+        let mut rng = RandomNumberGenerator::seeded(seed);
+        let mut chain = make_chain(&mut rng);
+        chain.build_data.record_history = false;
+        chain.build_map(&mut rng);
+        check_invariants(name, seed, &chain);
+        assert_eq!(chain.build_data.map.depth, depth);
+    }
+}
+
+/// A starting builder followed by the meta builders the game uses with it.
+fn starter_chain(starter: Box<dyn InitialMapBuilder>, rooms: bool) -> BuilderChain {
+    let mut chain = BuilderChain::new(6, WIDTH, HEIGHT, "Test Map");
+    chain.start_with(starter);
+    if rooms {
+        chain.with(RoomSorter::new(RoomSort::LeftMost));
+        chain.with(RoomDrawer::new());
+        chain.with(DoglegCorridors::new());
+        chain.with(RoomBasedStartingPosition::new());
+        chain.with(RoomBasedStairs::new());
+        chain.with(RoomBasedSpawner::new());
+    } else {
+        chain.with(AreaStartingPosition::new(XStart::Center, YStart::Center));
+        chain.with(CullUnreachable::new());
+        chain.with(VoronoiSpawning::new());
+        chain.with(DistantExit::new());
+    }
+    chain.with(UpStairsPlacement::new());
+    chain
+}
+
+macro_rules! starter_tests {
+    ($($test:ident: $starter:expr, $rooms:expr;)*) => {
+        $(
+            #[test]
+            fn $test() {
+                build_and_check(stringify!($test), 6, |_| starter_chain($starter, $rooms));
+            }
+        )*
+    };
+}
+
+starter_tests! {
+    simple_map: SimpleMapBuilder::new(), true;
+    bsp_dungeon: BspDungeonBuilder::new(), true;
+    cellular_automata: CellularAutomataBuilder::new(), false;
+    drunkard_open_area: DrunkardsWalkBuilder::open_area(), false;
+    drunkard_open_halls: DrunkardsWalkBuilder::open_halls(), false;
+    drunkard_winding_passages: DrunkardsWalkBuilder::winding_passages(), false;
+    drunkard_fat_passages: DrunkardsWalkBuilder::fat_passages(), false;
+    drunkard_fearful_symmetry: DrunkardsWalkBuilder::fearful_symmetry(), false;
+    maze: MazeBuilder::new(), false;
+    dla_walk_inwards: DLABuilder::walk_inwards(), false;
+    dla_walk_outwards: DLABuilder::walk_outwards(), false;
+    dla_central_attractor: DLABuilder::central_attractor(), false;
+    dla_insectoid: DLABuilder::insectoid(), false;
+    dla_heavy_erosion: DLABuilder::heavy_erosion(), false;
+    voronoi_pythagoras: VoronoiCellBuilder::pythagoras(), false;
+    voronoi_manhattan: VoronoiCellBuilder::manhattan(), false;
+    prefab_wfc_populated: PrefabBuilder::constant(prefab_levels::WFC_POPULATED), false;
+}
+
+#[test]
+fn bsp_interior() {
+    // BSP interior carves its own rooms and corridors
+    build_and_check("bsp_interior", 6, |_| {
+        let mut chain = BuilderChain::new(6, WIDTH, HEIGHT, "Test Map");
+        chain.start_with(BspInteriorBuilder::new());
+        chain.with(RoomBasedStartingPosition::new());
+        chain.with(RoomBasedStairs::new());
+        chain.with(RoomBasedSpawner::new());
+        chain.with(UpStairsPlacement::new());
+        chain
+    });
+}
+
+#[test]
+fn town() {
+    build_and_check("town", 1, |rng| level_builder(1, rng, WIDTH, HEIGHT));
+}
+
+#[test]
+fn forest() {
+    build_and_check("forest", 2, |rng| level_builder(2, rng, WIDTH, HEIGHT));
+}
+
+#[test]
+fn limestone_cavern() {
+    build_and_check("limestone_cavern", 3, |rng| {
+        level_builder(3, rng, WIDTH, HEIGHT)
+    });
+}
+
+#[test]
+fn limestone_deep_cavern() {
+    build_and_check("limestone_deep_cavern", 4, |rng| {
+        level_builder(4, rng, WIDTH, HEIGHT)
+    });
+}
+
+#[test]
+fn limestone_transition() {
+    build_and_check("limestone_transition", 5, |rng| {
+        level_builder(5, rng, WIDTH, HEIGHT)
+    });
+}
+
+#[test]
+fn random_levels() {
+    build_and_check("random_builder", 6, |rng| {
+        level_builder(6, rng, WIDTH, HEIGHT)
+    });
+}
@@ -0,0 +1,175 @@
+//! Firing a ranged weapon uses up ammunition from the backpack, and what isn't broken can
+//! be picked up where it landed.
+
+mod common;
+
+use common::{logged, logged_starting};
+use rltk::Point;
+use roguelike_rust::{
+    Ammunition, EquipmentSlot, Equippable, Equipped, Game, InBackpack, Item, Name, PlayerCommand,
+    Pools, Position, RangedWeapon, RunState, Stackable, Viewshed,
+};
+use specs::prelude::*;
+
+fn arrows(game: &Game) -> Vec<Entity> {
+    let world = game.world();
+    (&world.entities(), &world.read_storage::<Ammunition>())
+        .join()
+        .map(|(entity, _)| entity)
+        .collect()
+}
+
+fn arrows_carried(game: &Game) -> i32 {
+    let backpack = game.world().read_storage::<InBackpack>();
+    let stacks = game.world().read_storage::<Stackable>();
+    arrows(game)
+        .into_iter()
+        .filter(|arrow| backpack.get(*arrow).is_some())
+        .map(|arrow| stacks.get(arrow).map_or(1, |s| s.quantity))
+        .sum()
+}
+
+fn arrows_on_the_floor(game: &Game) -> usize {
+    let positions = game.world().read_storage::<Position>();
+    arrows(game)
+        .into_iter()
+        .filter(|arrow| positions.get(*arrow).is_some())
+        .count()
+}
+
+/// Someone the player can see, within `range`.
+fn visible_target(game: &Game, range: f32) -> Point {
+    let world = game.world();
+    let player = game.player();
+    let positions = world.read_storage::<Position>();
+    let player_pos = positions.get(player).unwrap();
+    let player_pos = Point::new(player_pos.x, player_pos.y);
+    let viewsheds = world.read_storage::<Viewshed>();
+    let visible = &viewsheds.get(player).unwrap().visible_tiles;
+    (
+        &world.entities(),
+        &positions,
+        &world.read_storage::<Pools>(),
+    )
+        .join()
+        .map(|(entity, pos, _)| (entity, Point::new(pos.x, pos.y)))
+        .find(|(entity, pos)| {
+            *entity != player
+                && visible.contains(pos)
+                && rltk::DistanceAlg::Pythagoras.distance2d(player_pos, *pos) <= range
+        })
+        .map(|(_, pos)| pos)
+        .expect("nobody in sight")
+}
+
+/// Equips the player with a bow and puts a stack of `count` arrows in their backpack.
+fn arm_with_bow(game: &mut Game, count: i32) {
+    let player = game.player();
+    let world = game.world_mut();
+    world
+        .create_entity()
+        .with(Name {
+            name: "Test Bow".to_string(),
+        })
+        .with(Item {
+            initiative_penalty: 0.0,
+            weight_lbs: 0.0,
+            base_value: 0.0,
+        })
+        .with(Equippable {
+            slot: EquipmentSlot::Ranged,
+        })
+        .with(Equipped {
+            owner: player,
+            slot: EquipmentSlot::Ranged,
+        })
+        .with(RangedWeapon {
+            range: 8,
+            ammo: "arrow".to_string(),
+            damage_n_dice: 1,
+            damage_die_type: 1,
+            damage_bonus: 0,
+            hit_bonus: 0,
+        })
+        .build();
+    if count > 0 {
+        world
+            .create_entity()
+            .with(Name {
+                name: "Arrow".to_string(),
+            })
+            .with(Item {
+                initiative_penalty: 0.0,
+                weight_lbs: 0.0,
+                base_value: 0.0,
+            })
+            .with(Ammunition {
+                kind: "arrow".to_string(),
+            })
+            .with(Stackable { quantity: count })
+            .with(InBackpack { owner: player })
+            .build();
+    }
+}
+
+#[test]
+fn firing_needs_a_ranged_weapon() {
+    let mut game = Game::with_seed(11);
+    game.apply(PlayerCommand::Fire { target: None });
+    assert!(logged(&game, "You have no ranged weapon equipped."));
+}
+
+#[test]
+fn firing_without_a_target_asks_for_one() {
+    let mut game = Game::with_seed(11);
+    arm_with_bow(&mut game, 3);
+    assert!(matches!(
+        game.apply(PlayerCommand::Fire { target: None }),
+        RunState::ShowFireTargeting { .. }
+    ));
+    assert_eq!(arrows_carried(&game), 3);
+}
+
+#[test]
+fn shooting_uses_up_ammunition() {
+    let mut game = Game::with_seed(11);
+    arm_with_bow(&mut game, 3);
+    let target = visible_target(&game, 8.0);
+    game.apply(PlayerCommand::Fire {
+        target: Some(target),
+    });
+    assert!(logged_starting(&game, "Player shoots"));
+    assert_eq!(arrows_carried(&game), 2);
+
+    // With this seed the arrow didn't break, so it can be picked up from the floor
+    assert_eq!(arrows_on_the_floor(&game), 1);
+}
+
+#[test]
+fn each_shot_takes_one_arrow_off_the_stack() {
+    let mut game = Game::with_seed(11);
+    arm_with_bow(&mut game, 3);
+    let target = visible_target(&game, 8.0);
+    for left in [2, 1, 0] {
+        game.apply(PlayerCommand::Fire {
+            target: Some(target),
+        });
+        assert_eq!(arrows_carried(&game), left);
+    }
+
+    // With this seed one of the three broke and the other two landed whole
+    let broken = 1;
+    assert_eq!(arrows_on_the_floor(&game) + broken, 3);
+    assert_eq!(arrows(&game).len(), 2);
+}
+
+#[test]
+fn firing_needs_ammunition() {
+    let mut game = Game::with_seed(11);
+    arm_with_bow(&mut game, 0);
+    let target = visible_target(&game, 8.0);
+    game.apply(PlayerCommand::Fire {
+        target: Some(target),
+    });
+    assert!(logged(&game, "You have no arrow ammunition left."));
+}
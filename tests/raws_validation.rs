@@ -0,0 +1,441 @@
+//! The raws are merged from several files, with mods overlaid by name, and checked as a
+//! whole before the game starts. Every problem is reported with the file and JSON path
+//! of the value at fault.
+
+use roguelike_rust::{
+    check_raw_files, check_raws_with_mods, load_raws, read_raw_dir, reload_raws, Effect, RawFile,
+};
+use serde_json::{json, Value};
+use std::path::Path;
+
+fn shipped_raws() -> Vec<RawFile> {
+    read_raw_dir(Path::new("raws")).unwrap()
+}
+
+/// Edits the JSON of the shipped raws file `name`.
+fn edit<F: FnOnce(&mut Value)>(files: &mut [RawFile], name: &str, f: F) {
+    let file = files
+        .iter_mut()
+        .find(|file| Path::new(&file.path).file_name().unwrap() == name)
+        .unwrap();
+    let mut value: Value = serde_json::from_str(&file.json).unwrap();
+    f(&mut value);
+    file.json = value.to_string();
+}
+
+fn add_file(files: &mut Vec<RawFile>, path: &str, json: Value) {
+    files.push(RawFile {
+        path: path.to_string(),
+        json: json.to_string(),
+    });
+}
+
+/// Where `name` is in `list` in the shipped file that holds the list.
+fn index_of(files: &[RawFile], list: &str, name: &str) -> usize {
+    files
+        .iter()
+        .filter_map(|file| {
+            let value: Value = serde_json::from_str(&file.json).unwrap();
+            value[list]
+                .as_array()?
+                .iter()
+                .position(|entry| entry["name"] == name)
+        })
+        .next()
+        .unwrap()
+}
+
+fn problems(files: &[RawFile]) -> Vec<String> {
+    match check_raw_files(files) {
+        Ok(_) => Vec::new(),
+        Err(errors) => errors.iter().map(|e| e.to_string()).collect(),
+    }
+}
+
+fn assert_reported(problems: &[String], expected: &str) {
+    assert!(
+        problems.iter().any(|p| p.starts_with(expected)),
+        "expected {:?} in {:#?}",
+        expected,
+        problems
+    );
+}
+
+#[test]
+fn shipped_raws_are_clean() {
+    assert_eq!(problems(&shipped_raws()), Vec::<String>::new());
+}
+
+#[test]
+fn every_problem_is_reported_at_once() {
+    let mut files = shipped_raws();
+    let potion = index_of(&files, "items", "Health Potion");
+    let barkeep = index_of(&files, "mobs", "Barkeep");
+    let trap = index_of(&files, "props", "Bear Trap");
+    let bow = index_of(&files, "items", "Shortbow");
+    let dagger = index_of(&files, "items", "Dagger");
+    let book = index_of(&files, "items", "Book of Mending");
+    let shaman = index_of(&files, "mobs", "Goblin Shaman");
+    let regeneration = index_of(&files, "items", "Regeneration Potion");
+    let rune = index_of(&files, "props", "Paralysis Rune");
+    let cabinet = index_of(&files, "props", "Cabinet");
+    let chest = index_of(&files, "props", "Ornate Chest");
+    let helm = index_of(&files, "items", "Duelist's Helm");
+
+    let mut duplicate = 0;
+    edit(&mut files, "items.json", |raws| {
+        raws["items"][potion]["consumable"]["effects"]
+            .as_array_mut()
+            .unwrap()
+            .push(json!({ "type": "provides_healing", "amount": 2 }));
+        raws["items"][potion]["renderable"]["glyph"] = json!("ж");
+        raws["items"][potion]["regeneration"] = json!({ "hp": 5 });
+        raws["items"][potion]["attributes"] = json!({ "might": 1 });
+        raws["items"][potion]["cursed"] = json!(true);
+        raws["items"][potion]["consumable"]["charges"] = json!("0");
+        raws["items"][helm]["skills"]["Stealth"] = json!(2);
+        raws["items"][helm]["stackable"] = json!(true);
+        raws["items"][bow]["weapon"]["ammo"] = json!("bolt");
+        raws["items"][dagger]["weapon"]["range"] = json!("far");
+        raws["items"][book]["consumable"]["effects"][0]["spell"] = json!("Blink");
+        let effects = &mut raws["items"][regeneration]["consumable"]["effects"];
+        effects[0]["magnitude"] = json!(0);
+        effects.as_array_mut().unwrap().push(
+            json!({ "type": "status", "status": "regeneration", "turns": 0, "magnitude": 1 }),
+        );
+        let copy = raws["items"][potion].clone();
+        let items = raws["items"].as_array_mut().unwrap();
+        items.push(copy);
+        duplicate = items.len() - 1;
+    });
+    edit(&mut files, "mobs.json", |raws| {
+        raws["mobs"][barkeep]["equipped"][0] = json!("Cudgle");
+        raws["mobs"][barkeep]["faction"] = json!("Tavern");
+        raws["mobs"][barkeep]["temple"] = json!({ "curse_price": -5.0 });
+        raws["mobs"][barkeep]["gold"] = json!("2d");
+        raws["mobs"][barkeep]["movement"] = json!("wander");
+        raws["mobs"][shaman]["spells"][0] = json!("Fireball");
+        raws["mobs"][shaman]["recharge"] = json!({ "price": -1.0 });
+    });
+    edit(&mut files, "props.json", |raws| {
+        raws["props"][trap]["entry_trigger"]["effects"][0] = json!({ "type": "food" });
+        raws["props"][trap]["entry_trigger"]["effects"]
+            .as_array_mut()
+            .unwrap()
+            .push(
+                json!({ "type": "particle", "glyph": "ж", "color": "red", "lifetime_ms": 200.0 }),
+            );
+    });
+    edit(&mut files, "props.json", |raws| {
+        raws["props"][rune]["entry_trigger"]["effects"][0]["magnitude"] = json!(2);
+        raws["props"][cabinet]["container"]["loot_table"] = json!("Cupboard");
+        raws["props"][cabinet]["container"]["lock"] = json!(0);
+        raws["props"][chest]["container"]["trap"]["effects"]
+            .as_array_mut()
+            .unwrap()
+            .push(json!({ "type": "single_activation" }));
+        raws["props"][chest]["entry_trigger"] = json!({ "effects": [] });
+    });
+    edit(&mut files, "spawn_table.json", |raws| {
+        raws["spawn_table"][0]["name"] = json!("Gobbo");
+    });
+    edit(&mut files, "spells.json", |raws| {
+        raws["spells"][0]["effects"]
+            .as_array_mut()
+            .unwrap()
+            .push(json!({ "type": "food" }));
+        raws["spells"][1]["mana_cost"] = json!(-1);
+        raws["spells"][1]["effects"]
+            .as_array_mut()
+            .unwrap()
+            .push(json!({ "type": "identify" }));
+    });
+
+    let problems = problems(&files);
+    for expected in [
+        format!(
+            "raws/items.json: items[{}].consumable.effects[1]: there is already a provides_healing effect",
+            potion
+        ),
+        format!(
+            "raws/items.json: items[{}].renderable.glyph: 'ж' isn't in code page 437",
+            potion
+        ),
+        format!(
+            "raws/items.json: items[{}].regeneration: only equipment can change how quickly its wearer regenerates",
+            potion
+        ),
+        format!(
+            "raws/items.json: items[{}].cursed: only equipment can be cursed",
+            potion
+        ),
+        format!(
+            "raws/mobs.json: mobs[{}].temple.curse_price: a temple can't charge less than 0 gold",
+            barkeep
+        ),
+        format!(
+            "raws/items.json: items[{}].consumable.charges: an item with charges needs at least 1 to begin with",
+            potion
+        ),
+        format!(
+            "raws/mobs.json: mobs[{}].recharge: only a vendor can recharge items",
+            shaman
+        ),
+        format!(
+            "raws/items.json: items[{}].stackable: an item with charges can't stack",
+            potion
+        ),
+        format!(
+            "raws/items.json: items[{}].stackable: equipment can't stack",
+            helm
+        ),
+        format!(
+            "raws/props.json: props[{}].container.loot_table: no loot table is called \"Cupboard\"",
+            cabinet
+        ),
+        format!(
+            "raws/props.json: props[{}].container.lock: a lock needs a difficulty of 1 or more",
+            cabinet
+        ),
+        format!(
+            "raws/props.json: props[{}].container.trap.effects[2]: a single_activation effect can't be used by a container's trap",
+            chest
+        ),
+        format!(
+            "raws/props.json: props[{}].entry_trigger: a container can't have an entry trigger; give it a trap instead",
+            chest
+        ),
+        format!(
+            "raws/mobs.json: mobs[{}].recharge.price: a recharge can't cost less than 0 gold",
+            shaman
+        ),
+        format!(
+            "raws/items.json: items[{}].attributes: only equipment can change its wearer's attributes",
+            potion
+        ),
+        format!(
+            "raws/items.json: items[{}].skills.Stealth: unknown skill \"Stealth\"; expected one of Melee, Defense, Magic, Ranged",
+            helm
+        ),
+        format!(
+            "raws/items.json: items[{}].weapon.ammo: no item is ammunition of kind \"bolt\"",
+            bow
+        ),
+        format!(
+            "raws/items.json: items[{}].weapon.range: \"far\" should be \"melee\" or a number of tiles",
+            dagger
+        ),
+        format!(
+            "raws/items.json: items[{}].name: \"Health Potion\" is already",
+            duplicate
+        ),
+        format!(
+            "raws/mobs.json: mobs[{}].equipped[0]: no item is called \"Cudgle\"",
+            barkeep
+        ),
+        format!(
+            "raws/mobs.json: mobs[{}].faction: no faction is called \"Tavern\"",
+            barkeep
+        ),
+        format!(
+            "raws/mobs.json: mobs[{}].gold: \"2d\" isn't a dice string",
+            barkeep
+        ),
+        format!(
+            "raws/mobs.json: mobs[{}].movement: unknown movement mode \"wander\"",
+            barkeep
+        ),
+        format!(
+            "raws/props.json: props[{}].entry_trigger.effects[0]: a food effect can't be used by a trap",
+            trap
+        ),
+        format!(
+            "raws/props.json: props[{}].entry_trigger.effects[2].glyph: 'ж' isn't in code page 437",
+            trap
+        ),
+        format!(
+            "raws/props.json: props[{}].entry_trigger.effects[2].color: \"red\" isn't a colour",
+            trap
+        ),
+        "raws/spawn_table.json: spawn_table[0].name: no item, mob or prop is called \"Gobbo\""
+            .to_string(),
+        format!(
+            "raws/items.json: items[{}].consumable.effects[0].spell: no spell is called \"Blink\"",
+            book
+        ),
+        format!(
+            "raws/mobs.json: mobs[{}].spells[0]: no spell is called \"Fireball\"",
+            shaman
+        ),
+        "raws/spells.json: spells[0].effects[3]: a food effect can't be used by a spell".to_string(),
+        "raws/spells.json: spells[1].mana_cost: a spell can't cost less than 0 mana".to_string(),
+        "raws/spells.json: spells[1].effects[2]: an identify effect can't be used by a spell"
+            .to_string(),
+        format!(
+            "raws/items.json: items[{}].consumable.effects[0].magnitude: a regeneration status needs a magnitude of 1 or more",
+            regeneration
+        ),
+        format!(
+            "raws/items.json: items[{}].consumable.effects[1].status: there is already a regeneration status",
+            regeneration
+        ),
+        format!(
+            "raws/items.json: items[{}].consumable.effects[1].turns: a status has to last 1 turn or more",
+            regeneration
+        ),
+        format!(
+            "raws/props.json: props[{}].entry_trigger.effects[0].magnitude: a paralysis status has no magnitude",
+            rune
+        ),
+    ] {
+        assert_reported(&problems, &expected);
+    }
+}
+
+#[test]
+fn malformed_files_report_where_they_went_wrong() {
+    let mut files = shipped_raws();
+    let orc = index_of(&files, "mobs", "Orc");
+    let potion = index_of(&files, "items", "Health Potion");
+    let trap = index_of(&files, "props", "Bear Trap");
+    let befuddle = index_of(&files, "spells", "Befuddle");
+    edit(&mut files, "mobs.json", |raws| {
+        raws["mobs"][orc]["vision_range"] = json!("far");
+    });
+    edit(&mut files, "items.json", |raws| {
+        raws["items"][potion]["consumable"]["effects"][0] = json!({ "type": "levitation" });
+    });
+    edit(&mut files, "props.json", |raws| {
+        raws["props"][trap]["entry_trigger"]["effects"][0]["dice"] = json!("lots");
+    });
+    edit(&mut files, "spells.json", |raws| {
+        raws["spells"][befuddle]["effects"][1]["status"] = json!("petrified");
+    });
+    add_file(&mut files, "mods/typo.json", json!({ "itmes": [] }));
+
+    let problems = problems(&files);
+    assert_reported(
+        &problems,
+        &format!("raws/mobs.json: mobs[{}].vision_range: invalid type", orc),
+    );
+    assert_reported(
+        &problems,
+        &format!(
+            "raws/items.json: items[{}].consumable.effects[0].type: unknown variant `levitation`",
+            potion
+        ),
+    );
+    assert_reported(
+        &problems,
+        &format!(
+            "raws/spells.json: spells[{}].effects[1]: unknown variant `petrified`",
+            befuddle
+        ),
+    );
+    assert_reported(
+        &problems,
+        &format!(
+            "raws/props.json: props[{}].entry_trigger.effects[0]: \"lots\" isn't a dice string",
+            trap
+        ),
+    );
+    assert_reported(&problems, "mods/typo.json: itmes: unknown field `itmes`");
+}
+
+#[test]
+fn mods_add_and_override_templates_by_name() {
+    let mut files = shipped_raws();
+    let items = check_raw_files(&files).unwrap().items.len();
+    add_file(
+        &mut files,
+        "mods/pack/potions.json",
+        json!({
+            "items": [
+                {
+                    "name": "Health Potion",
+                    "renderable": { "glyph": "!", "fg": "#FF0000", "bg": "#000000", "order": 2 },
+                    "consumable": { "effects": [ { "type": "provides_healing", "amount": 20 } ] }
+                },
+                {
+                    "name": "Mana Potion",
+                    "renderable": { "glyph": "!", "fg": "#0000FF", "bg": "#000000", "order": 2 },
+                    "consumable": { "effects": [ { "type": "provides_healing", "amount": 1 } ] }
+                }
+            ],
+            "spawn_table": [
+                { "name": "Mana Potion", "weight": 3, "min_depth": 1, "max_depth": 100 }
+            ]
+        }),
+    );
+
+    let raws = check_raw_files(&files).unwrap();
+    assert_eq!(raws.items.len(), items + 1);
+    let potion = raws
+        .items
+        .iter()
+        .find(|item| item.name == "Health Potion")
+        .unwrap();
+    assert_eq!(
+        potion.consumable.as_ref().unwrap().effects,
+        vec![Effect::ProvidesHealing { amount: 20 }]
+    );
+    assert!(raws.spawn_table.iter().any(|e| e.name == "Mana Potion"));
+}
+
+#[test]
+fn problems_in_an_override_point_at_the_mod() {
+    let mut files = shipped_raws();
+    add_file(
+        &mut files,
+        "mods/broken.json",
+        json!({
+            "loot_tables": [ { "name": "Animal", "drops": [ { "name": "Fur", "weight": 1 } ] } ]
+        }),
+    );
+
+    assert_eq!(
+        problems(&files),
+        vec!["mods/broken.json: loot_tables[0].drops[0].name: no item is called \"Fur\""]
+    );
+}
+
+#[test]
+fn a_broken_mod_is_skipped_and_the_rest_load() {
+    let mut mods = Vec::new();
+    add_file(
+        &mut mods,
+        "mods/a_broken.json",
+        json!({
+            "loot_tables": [ { "name": "Animal", "drops": [ { "name": "Fur", "weight": 1 } ] } ]
+        }),
+    );
+    add_file(&mut mods, "mods/b_garbled.json", json!("not raws"));
+    add_file(
+        &mut mods,
+        "mods/c_fine.json",
+        json!({
+            "spawn_table": [
+                { "name": "Health Potion", "weight": 77, "min_depth": 0, "max_depth": 100 }
+            ]
+        }),
+    );
+
+    let (raws, problems) = check_raws_with_mods(&shipped_raws(), mods);
+    let problems: Vec<String> = problems.iter().map(|e| e.to_string()).collect();
+    assert_reported(
+        &problems,
+        "mods/a_broken.json: loot_tables[0].drops[0].name",
+    );
+    assert_reported(&problems, "mods/b_garbled.json");
+    assert!(!problems.iter().any(|p| p.starts_with("mods/c_fine.json")));
+    assert!(raws
+        .spawn_table
+        .iter()
+        .any(|e| e.name == "Health Potion" && e.weight == 77));
+}
+
+#[test]
+fn raws_reload_from_the_files_on_disk() {
+    assert!(load_raws().is_empty());
+    reload_raws().unwrap();
+}
@@ -0,0 +1,184 @@
+//! Hit points and mana come back a little every turn, unless there's danger in sight.
+
+mod common;
+
+use common::player_position;
+use roguelike_rust::{
+    Attribute, Attributes, EquipmentSlot, Equipped, Faction, Game, HungerClock, HungerState,
+    Initiative, Name, PlayerCommand, Pool, Pools, Position, RegenerationBonus, Status,
+    StatusEffects, StatusKind,
+};
+use specs::prelude::*;
+
+/// Empties an entity's pools, and the regeneration built up towards them.
+fn drain(game: &mut Game, entity: Entity) {
+    let mut pools = game.world_mut().write_storage::<Pools>();
+    let pools = pools.get_mut(entity).unwrap();
+    pools.hit_points.current = 1;
+    pools.mana.current = 0;
+    pools.hp_regen_progress = 0;
+    pools.mana_regen_progress = 0;
+}
+
+/// Hit points and mana regained since `drain`.
+fn regained(game: &Game, entity: Entity) -> (i32, i32) {
+    let pools = game.world().read_storage::<Pools>();
+    let pools = pools.get(entity).unwrap();
+    (pools.hit_points.current - 1, pools.mana.current)
+}
+
+fn wait(game: &mut Game, turns: usize) {
+    for _ in 0..turns {
+        game.apply(PlayerCommand::Wait);
+    }
+}
+
+fn set_hunger(game: &mut Game, state: HungerState) {
+    let player = game.player();
+    game.world_mut()
+        .write_storage::<HungerClock>()
+        .insert(
+            player,
+            HungerClock {
+                state,
+                duration: 1000,
+            },
+        )
+        .unwrap();
+}
+
+fn attribute(base: i32) -> Attribute {
+    Attribute {
+        base,
+        modifiers: 0,
+        bonus: 0,
+    }
+}
+
+/// A game past the player's first turn, which comes straight away, so that each command
+/// then takes a turn, with the player neither hungry nor well fed.
+fn fed_game() -> Game {
+    let mut game = Game::with_seed(11);
+    wait(&mut game, 1);
+    set_hunger(&mut game, HungerState::Normal);
+    game
+}
+
+#[test]
+fn fitness_and_intelligence_set_the_rate() {
+    let mut game = fed_game();
+    let player = game.player();
+
+    // Fitness and Intelligence of 11 at level 1 bring back 4 tenths of a point a turn
+    drain(&mut game, player);
+    wait(&mut game, 5);
+    assert_eq!(regained(&game, player), (2, 2));
+}
+
+#[test]
+fn hunger_speeds_it_up_or_slows_it_down() {
+    let mut game = fed_game();
+    let player = game.player();
+    set_hunger(&mut game, HungerState::WellFed);
+    drain(&mut game, player);
+    wait(&mut game, 5);
+    assert_eq!(regained(&game, player), (3, 3));
+    set_hunger(&mut game, HungerState::Starving);
+    drain(&mut game, player);
+    wait(&mut game, 5);
+    assert_eq!(regained(&game, player), (0, 0));
+}
+
+#[test]
+fn poison_stops_hit_points_but_not_mana() {
+    let mut game = fed_game();
+    let player = game.player();
+    game.world_mut()
+        .write_storage::<StatusEffects>()
+        .insert(
+            player,
+            StatusEffects {
+                effects: vec![Status {
+                    kind: StatusKind::Poison,
+                    turns: 10,
+                    magnitude: 0,
+                }],
+            },
+        )
+        .unwrap();
+    drain(&mut game, player);
+    wait(&mut game, 5);
+    assert_eq!(regained(&game, player), (0, 2));
+}
+
+#[test]
+fn equipment_can_help() {
+    let mut game = fed_game();
+    let player = game.player();
+    game.world_mut()
+        .create_entity()
+        .with(RegenerationBonus { hp: 6, mana: 1 })
+        .with(Equipped {
+            owner: player,
+            slot: EquipmentSlot::Hands,
+        })
+        .build();
+    drain(&mut game, player);
+    wait(&mut game, 5);
+    assert_eq!(regained(&game, player), (5, 2));
+}
+
+#[test]
+fn nobody_regenerates_with_an_enemy_in_sight() {
+    let mut game = fed_game();
+    let player = game.player();
+    let (x, y) = player_position(&game);
+    game.world_mut()
+        .create_entity()
+        .with(Name {
+            name: "Bandit".to_string(),
+        })
+        .with(Position { x, y })
+        .with(Faction {
+            name: "Bandits".to_string(),
+        })
+        .build();
+    drain(&mut game, player);
+    wait(&mut game, 5);
+    assert_eq!(regained(&game, player), (0, 0));
+}
+
+#[test]
+fn monsters_regenerate_on_their_own_turns() {
+    let mut game = fed_game();
+    let (x, y) = player_position(&game);
+    let monster = game
+        .world_mut()
+        .create_entity()
+        .with(Position { x, y })
+        .with(Initiative { current: 0 })
+        .with(Attributes {
+            might: attribute(11),
+            fitness: attribute(15),
+            quickness: attribute(11),
+            intelligence: attribute(11),
+        })
+        .with(Pools {
+            hit_points: Pool {
+                current: 1,
+                max: 50,
+            },
+            mana: Pool { current: 0, max: 0 },
+            xp: 0,
+            level: 3,
+            total_weight: 0.0,
+            total_initiative_penalty: 0.0,
+            gold: 0.0,
+            god_mode: false,
+            hp_regen_progress: 0,
+            mana_regen_progress: 0,
+        })
+        .build();
+    wait(&mut game, 10);
+    assert!(regained(&game, monster).0 > 0);
+}
@@ -0,0 +1,366 @@
+//! Saves carry a format version; older saves are migrated on load and anything the game
+//! can't read is refused with a message rather than a panic.
+
+use roguelike_rust::{
+    known_spells, load_from_bytes, load_from_str, read_save, save_to_bytes, save_to_string, Game,
+    InflictsDamage, InflictsStatus, Map, PlayerCommand, Position, SaveEncoding, Skill, Skills,
+    Status, StatusEffects, StatusKind, SAVE_FORMAT_VERSION,
+};
+use serde_json::{json, Value};
+use specs::prelude::*;
+
+/// The order storages were written in before saves had a header.
+const V0_COMPONENTS: &[&str] = &[
+    "Position",
+    "Renderable",
+    "Player",
+    "Viewshed",
+    "Name",
+    "BlocksTile",
+    "SufferDamage",
+    "WantsToMelee",
+    "Item",
+    "Consumable",
+    "Ranged",
+    "InflictsDamage",
+    "AreaOfEffect",
+    "Confusion",
+    "ProvidesHealing",
+    "InBackpack",
+    "WantsToPickupItem",
+    "WantsToUseItem",
+    "WantsToDropItem",
+    "SerializationHelper",
+    "Equippable",
+    "Equipped",
+    "MeleeWeapon",
+    "Wearable",
+    "WantsToRemoveItem",
+    "ParticleLifetime",
+    "HungerClock",
+    "ProvidesFood",
+    "MagicMapper",
+    "Hidden",
+    "EntryTrigger",
+    "EntityMoved",
+    "SingleActivation",
+    "Door",
+    "BlocksVisibility",
+    "Quips",
+    "Attributes",
+    "Skills",
+    "Pools",
+    "NaturalAttackDefense",
+    "LootTable",
+    "OtherLevelPosition",
+    "DMSerializationHelper",
+    "LightSource",
+    "Initiative",
+    "MyTurn",
+    "Faction",
+    "WantsToApproach",
+    "WantsToFlee",
+    "MoveMode",
+    "Chasing",
+    "EquipmentChanged",
+    "Vendor",
+    "TownPortal",
+    "TeleportTo",
+    "ApplyMove",
+    "ApplyTeleport",
+    "MagicItem",
+    "ObfuscatedName",
+    "IdentifiedItem",
+];
+
+fn player_position(game: &Game) -> (i32, i32) {
+    let positions = game.world().read_storage::<Position>();
+    let pos = positions.get(game.player()).unwrap();
+    (pos.x, pos.y)
+}
+
+fn played_game() -> Game {
+    let mut game = Game::with_seed(11);
+    for _ in 0..5 {
+        game.apply(PlayerCommand::Move {
+            delta_x: 1,
+            delta_y: 0,
+        });
+        game.apply(PlayerCommand::Move {
+            delta_x: 0,
+            delta_y: 1,
+        });
+    }
+    game
+}
+
+fn assert_same_game(loaded: &Game, original: &Game) {
+    assert_eq!(player_position(loaded), player_position(original));
+    assert_eq!(loaded.seed(), original.seed());
+    let loaded_map = loaded.world().fetch::<Map>();
+    let original_map = original.world().fetch::<Map>();
+    assert_eq!(loaded_map.name, original_map.name);
+    assert_eq!(loaded_map.depth, original_map.depth);
+}
+
+#[test]
+fn save_round_trips() {
+    let mut original = played_game();
+    let data = save_to_string(original.world_mut());
+
+    let save = read_save(data.as_bytes()).unwrap();
+    assert_eq!(save.format_version, SAVE_FORMAT_VERSION);
+    assert_eq!(save.game_version, env!("CARGO_PKG_VERSION"));
+    let metadata = save.metadata.unwrap();
+    assert_eq!(metadata.character_level, 1);
+    assert_eq!(metadata.depth, 1);
+    assert_eq!(metadata.map_name, original.world().fetch::<Map>().name);
+    assert!(!metadata.consumed);
+
+    let mut loaded = Game::with_seed(12);
+    load_from_str(loaded.world_mut(), &data).unwrap();
+    assert_same_game(&loaded, &original);
+}
+
+#[test]
+fn binary_saves_round_trip() {
+    let mut original = played_game();
+    let binary = save_to_bytes(original.world_mut(), SaveEncoding::Binary);
+    let json = save_to_string(original.world_mut());
+    assert!(
+        binary.len() * 4 < json.len(),
+        "{} vs {}",
+        binary.len(),
+        json.len()
+    );
+
+    // The header stays readable, so the load menu can list binary saves
+    let header_end = binary.iter().position(|b| *b == b'\n').unwrap();
+    let header: Value = serde_json::from_slice(&binary[..header_end]).unwrap();
+    assert_eq!(header["encoding"], "binary");
+    assert_eq!(header["metadata"]["depth"], 1);
+
+    let mut loaded = Game::with_seed(12);
+    load_from_bytes(loaded.world_mut(), &binary).unwrap();
+    assert_same_game(&loaded, &original);
+}
+
+#[test]
+fn visibility_and_light_are_rebuilt_rather_than_saved() {
+    let mut original = played_game();
+    let data = save_to_string(original.world_mut());
+    let save = read_save(data.as_bytes()).unwrap();
+    assert!(save.resources["Map"].get("visible_tiles").is_none());
+    assert!(save.resources["Map"].get("light").is_none());
+
+    let mut loaded = Game::with_seed(12);
+    load_from_str(loaded.world_mut(), &data).unwrap();
+    let loaded_map = loaded.world().fetch::<Map>();
+    let original_map = original.world().fetch::<Map>();
+    assert_eq!(loaded_map.visible_tiles, original_map.visible_tiles);
+    assert_eq!(loaded_map.light.len(), original_map.light.len());
+}
+
+/// A save as format 1 wrote it: one object, with the map and dungeon on helper entities.
+fn format_1_save(game: &mut Game) -> Value {
+    let mut save = read_save(save_to_string(game.world_mut()).as_bytes()).unwrap();
+    for (helper, resource) in [
+        ("SerializationHelper", "Map"),
+        ("DMSerializationHelper", "MasterDungeonMap"),
+    ] {
+        let map = save.resources.remove(resource).unwrap();
+        save.components.insert(
+            helper.to_string(),
+            json!([{ "marker": [100_000], "components": [{ "map": map }] }]),
+        );
+    }
+    // Nothing was confused; statuses came later
+    save.components.remove("StatusEffects");
+    save.components.remove("InflictsStatus");
+    save.components.insert("Confusion".to_string(), json!([]));
+    json!({
+        "format_version": 1,
+        "game_version": "0.1.0",
+        "components": save.components,
+    })
+}
+
+#[test]
+fn format_1_saves_are_migrated() {
+    let mut original = played_game();
+    let save = format_1_save(&mut original);
+
+    let mut loaded = Game::with_seed(12);
+    load_from_str(loaded.world_mut(), &save.to_string()).unwrap();
+    assert_same_game(&loaded, &original);
+}
+
+#[test]
+fn headerless_saves_are_migrated() {
+    let mut original = played_game();
+    let mut save = format_1_save(&mut original);
+    let components = save["components"].as_object_mut().unwrap();
+    let legacy: String = V0_COMPONENTS
+        .iter()
+        .map(|name| components.remove(*name).unwrap().to_string())
+        .collect();
+
+    let mut loaded = Game::with_seed(12);
+    load_from_str(loaded.world_mut(), &legacy).unwrap();
+    assert_same_game(&loaded, &original);
+}
+
+#[test]
+fn format_3_fixed_damage_becomes_a_bonus() {
+    let mut original = played_game();
+    let data = save_to_string(original.world_mut());
+    let (header, body) = data.split_once('\n').unwrap();
+    let mut header: Value = serde_json::from_str(header).unwrap();
+    header["format_version"] = Value::from(3);
+    let mut body: Value = serde_json::from_str(body).unwrap();
+    let damaging = body["components"]["InflictsDamage"].as_array_mut().unwrap();
+    assert!(!damaging.is_empty());
+    for entity in damaging.iter_mut() {
+        entity["components"][0] = json!({ "damage": 7 });
+    }
+
+    let mut loaded = Game::with_seed(12);
+    load_from_str(loaded.world_mut(), &format!("{}\n{}", header, body)).unwrap();
+    let damage = loaded.world().read_storage::<InflictsDamage>();
+    assert!((&damage).join().count() > 0);
+    for damage in (&damage).join() {
+        assert_eq!((damage.n_dice, damage.die_type, damage.bonus), (0, 0, 7));
+    }
+}
+
+#[test]
+fn format_4_skills_learn_ranged() {
+    let mut original = played_game();
+    let data = save_to_string(original.world_mut());
+    let (header, body) = data.split_once('\n').unwrap();
+    let mut header: Value = serde_json::from_str(header).unwrap();
+    header["format_version"] = Value::from(4);
+    let mut body: Value = serde_json::from_str(body).unwrap();
+    for entity in body["components"]["Skills"].as_array_mut().unwrap() {
+        if let Some(skills) = entity
+            .pointer_mut("/components/0/skills")
+            .and_then(Value::as_object_mut)
+        {
+            skills.remove("Ranged");
+        }
+    }
+
+    let mut loaded = Game::with_seed(12);
+    load_from_str(loaded.world_mut(), &format!("{}\n{}", header, body)).unwrap();
+    let skills = loaded.world().read_storage::<Skills>();
+    assert!((&skills).join().count() > 0);
+    for skills in (&skills).join() {
+        assert_eq!(skills.skills.get(&Skill::Ranged), Some(&1));
+    }
+}
+
+#[test]
+fn format_5_players_know_zap() {
+    let mut original = played_game();
+    let data = save_to_string(original.world_mut());
+    let (header, body) = data.split_once('\n').unwrap();
+    let mut header: Value = serde_json::from_str(header).unwrap();
+    header["format_version"] = Value::from(5);
+    let mut body: Value = serde_json::from_str(body).unwrap();
+    body["components"]
+        .as_object_mut()
+        .unwrap()
+        .remove("KnownSpells");
+
+    let mut loaded = Game::with_seed(12);
+    load_from_str(loaded.world_mut(), &format!("{}\n{}", header, body)).unwrap();
+    assert_eq!(known_spells(loaded.world()), vec!["Zap".to_string()]);
+}
+
+#[test]
+fn format_6_confusion_becomes_a_status() {
+    let mut original = played_game();
+    let data = save_to_string(original.world_mut());
+    let (header, body) = data.split_once('\n').unwrap();
+    let mut header: Value = serde_json::from_str(header).unwrap();
+    header["format_version"] = Value::from(6);
+    let mut body: Value = serde_json::from_str(body).unwrap();
+    let components = body["components"].as_object_mut().unwrap();
+    components.remove("StatusEffects");
+    components.remove("InflictsStatus");
+    let marker_of = |storage: &str, components: &serde_json::Map<String, Value>| {
+        components[storage]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|entity| !entity["components"][0].is_null())
+            .unwrap()["marker"]
+            .clone()
+    };
+    let player = marker_of("Player", components);
+    let item = marker_of("Item", components);
+    components.insert(
+        "Confusion".to_string(),
+        json!([
+            { "marker": player, "components": [{ "turns": 3 }] },
+            { "marker": item, "components": [{ "turns": 4 }] },
+        ]),
+    );
+
+    let mut loaded = Game::with_seed(12);
+    load_from_str(loaded.world_mut(), &format!("{}\n{}", header, body)).unwrap();
+    let confused = |turns| Status {
+        kind: StatusKind::Confusion,
+        turns,
+        magnitude: 0,
+    };
+    let statuses = loaded.world().read_storage::<StatusEffects>();
+    assert_eq!(
+        statuses.get(loaded.player()).unwrap().effects,
+        vec![confused(3)]
+    );
+    let inflicts = loaded.world().read_storage::<InflictsStatus>();
+    let inflicts: Vec<&InflictsStatus> = (&inflicts).join().collect();
+    assert_eq!(inflicts.len(), 1);
+    assert_eq!(inflicts[0].statuses, vec![confused(4)]);
+}
+
+#[test]
+fn components_missing_from_a_save_load_empty() {
+    let mut original = played_game();
+    let data = save_to_string(original.world_mut());
+    let (header, body) = data.split_once('\n').unwrap();
+    let mut body: Value = serde_json::from_str(body).unwrap();
+    body["components"]
+        .as_object_mut()
+        .unwrap()
+        .remove("IdentifiedItem");
+
+    let mut loaded = Game::with_seed(12);
+    load_from_str(loaded.world_mut(), &format!("{}\n{}", header, body)).unwrap();
+    assert_same_game(&loaded, &original);
+}
+
+#[test]
+fn newer_saves_are_refused_without_touching_the_world() {
+    let mut original = played_game();
+    let data = save_to_string(original.world_mut());
+    let (header, body) = data.split_once('\n').unwrap();
+    let mut header: Value = serde_json::from_str(header).unwrap();
+    header["format_version"] = Value::from(SAVE_FORMAT_VERSION + 1);
+    header["game_version"] = Value::from("99.0.0");
+
+    let mut game = Game::with_seed(12);
+    let player = game.player();
+    let error = load_from_str(game.world_mut(), &format!("{}\n{}", header, body)).unwrap_err();
+    assert!(error.contains("newer version"), "{}", error);
+    assert!(error.contains("99.0.0"), "{}", error);
+    assert!(game.world().is_alive(player));
+}
+
+#[test]
+fn damaged_saves_are_refused() {
+    let mut game = Game::with_seed(12);
+    assert!(load_from_str(game.world_mut(), "{\"format_version\": 1, \"comp").is_err());
+    assert!(load_from_str(game.world_mut(), "[1, 2, 3]").is_err());
+}
@@ -0,0 +1,81 @@
+//! Skills improve with use, each level taking longer to reach, up to a cap set by the
+//! character's level.
+
+use roguelike_rust::{
+    practice_skill, practice_skills, skill_bonus, skill_cap, skill_practice_needed, Game, GameLog,
+    Pools, Skill, Skills, UNTRAINED_SKILL,
+};
+use specs::prelude::*;
+use std::collections::HashMap;
+
+#[test]
+fn skills_improve_with_practice_up_to_a_cap() {
+    assert_eq!(skill_practice_needed(0), 5);
+    assert_eq!(skill_practice_needed(1), 20);
+    assert_eq!(skill_practice_needed(2), 45);
+    assert_eq!(skill_cap(1), 2);
+
+    // Each level takes longer than the last
+    let mut skills = Skills {
+        skills: HashMap::from([(Skill::Magic, 0)]),
+        practice: HashMap::new(),
+        modifiers: HashMap::new(),
+    };
+    for _ in 0..4 {
+        assert_eq!(practice_skill(&mut skills, Skill::Magic, 1), None);
+    }
+    assert_eq!(practice_skill(&mut skills, Skill::Magic, 1), Some(1));
+    assert_eq!(skills.practice[&Skill::Magic], 0);
+    for _ in 0..19 {
+        assert_eq!(practice_skill(&mut skills, Skill::Magic, 1), None);
+    }
+    assert_eq!(practice_skill(&mut skills, Skill::Magic, 1), Some(2));
+
+    // At the cap for their level, practice teaches nothing until the character levels up
+    for _ in 0..100 {
+        assert_eq!(practice_skill(&mut skills, Skill::Magic, 1), None);
+    }
+    assert_eq!(skills.skills[&Skill::Magic], 2);
+    assert_eq!(skills.practice[&Skill::Magic], 0);
+    assert_eq!(practice_skill(&mut skills, Skill::Magic, 2), None);
+    assert_eq!(skills.practice[&Skill::Magic], 1);
+
+    // The player hears about their own improvements
+    let game = Game::with_seed(11);
+    let player = game.player();
+    let world = game.world();
+    let level = world.read_storage::<Skills>().get(player).unwrap().skills[&Skill::Melee];
+    let practiced = vec![(player, Skill::Melee); skill_practice_needed(level) as usize];
+    practice_skills(
+        &practiced,
+        &mut world.write_storage::<Skills>(),
+        &world.read_storage::<Pools>(),
+        player,
+        &mut world.write_resource::<GameLog>(),
+    );
+    assert!(world
+        .fetch::<GameLog>()
+        .entries
+        .iter()
+        .any(|e| *e == format!("Your Melee skill improves to {}.", level + 1)));
+}
+
+#[test]
+fn untrained_skills_practice_up_from_the_untrained_penalty() {
+    let mut skills = Skills {
+        skills: HashMap::new(),
+        practice: HashMap::new(),
+        modifiers: HashMap::new(),
+    };
+    assert_eq!(skill_bonus(Skill::Ranged, &skills), UNTRAINED_SKILL);
+
+    // A few uses take the edge off the penalty, rather than jumping straight past it
+    for _ in 0..4 {
+        assert_eq!(practice_skill(&mut skills, Skill::Ranged, 1), None);
+    }
+    assert_eq!(
+        practice_skill(&mut skills, Skill::Ranged, 1),
+        Some(UNTRAINED_SKILL + 1)
+    );
+    assert_eq!(skill_bonus(Skill::Ranged, &skills), UNTRAINED_SKILL + 1);
+}
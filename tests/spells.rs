@@ -0,0 +1,141 @@
+//! Spells cost mana, are learned from books and get stronger with the Magic skill.
+
+mod common;
+
+use common::{logged, pools, use_last_item};
+use rltk::Point;
+use roguelike_rust::{
+    known_spells, Consumable, Game, InBackpack, Item, Name, PlayerCommand, RunState, Skill, Skills,
+    TeachesSpell,
+};
+use specs::prelude::*;
+
+fn mana(game: &mut Game) -> i32 {
+    let player = game.player();
+    pools(game).get(player).unwrap().mana.current
+}
+
+fn set_mana(game: &mut Game, amount: i32) {
+    let player = game.player();
+    pools(game).get_mut(player).unwrap().mana.current = amount;
+}
+
+fn give_book(game: &mut Game) -> Entity {
+    let player = game.player();
+    game.world_mut()
+        .create_entity()
+        .with(Name {
+            name: "Book of Mending".to_string(),
+        })
+        .with(Item {
+            initiative_penalty: 0.0,
+            weight_lbs: 0.0,
+            base_value: 0.0,
+        })
+        .with(Consumable {})
+        .with(TeachesSpell {
+            spell: "Mend".to_string(),
+        })
+        .with(InBackpack { owner: player })
+        .build()
+}
+
+/// A game where the player has learned Mend, their second spell, from a book.
+fn game_knowing_mend() -> Game {
+    let mut game = Game::with_seed(11);
+    give_book(&mut game);
+    use_last_item(&mut game);
+    game
+}
+
+#[test]
+fn books_teach_spells_once() {
+    let mut game = Game::with_seed(11);
+    assert_eq!(known_spells(game.world()), vec!["Zap".to_string()]);
+
+    // Learning from a book uses it up, unless the spell is already known
+    let book = give_book(&mut game);
+    use_last_item(&mut game);
+    assert!(logged(
+        &game,
+        "You study the Book of Mending and learn to cast Mend."
+    ));
+    assert!(!game.world().is_alive(book));
+    assert_eq!(
+        known_spells(game.world()),
+        vec!["Zap".to_string(), "Mend".to_string()]
+    );
+    let book = give_book(&mut game);
+    use_last_item(&mut game);
+    assert!(logged(&game, "You already know how to cast Mend."));
+    assert!(game.world().is_alive(book));
+}
+
+#[test]
+fn spells_need_enough_mana() {
+    let mut game = game_knowing_mend();
+    set_mana(&mut game, 3);
+    game.apply(PlayerCommand::CastSpell {
+        spell: 1,
+        target: None,
+    });
+    assert!(logged(&game, "You don't have enough mana to cast Mend."));
+    assert_eq!(mana(&mut game), 3);
+}
+
+#[test]
+fn aimed_spells_need_a_target_in_range() {
+    let mut game = Game::with_seed(11);
+    set_mana(&mut game, 10);
+    assert!(matches!(
+        game.apply(PlayerCommand::CastSpell {
+            spell: 0,
+            target: None,
+        }),
+        RunState::ShowSpellTargeting { spell: 0, .. }
+    ));
+    assert_eq!(mana(&mut game), 10);
+    let far_away = Point::new(0, 0);
+    game.apply(PlayerCommand::CastSpell {
+        spell: 0,
+        target: Some(far_away),
+    });
+    assert!(logged(&game, "You can't cast Zap there."));
+    assert_eq!(mana(&mut game), 10);
+}
+
+#[test]
+fn magic_skill_strengthens_spells() {
+    let mut game = game_knowing_mend();
+    let player = game.player();
+
+    // A master of magic heals for far more than the spell's 8 hp, but even they fumble a
+    // natural 1; either way the mana is spent
+    game.world_mut()
+        .write_storage::<Skills>()
+        .get_mut(player)
+        .unwrap()
+        .skills
+        .insert(Skill::Magic, 20);
+    let mut cast = false;
+    for _ in 0..10 {
+        set_mana(&mut game, 4);
+        pools(&mut game).get_mut(player).unwrap().hit_points.current = 1;
+        game.apply(PlayerCommand::CastSpell {
+            spell: 1,
+            target: None,
+        });
+        assert!(mana(&mut game) <= 1);
+        if logged(&game, "You cast Mend, healing 28 hp.") {
+            cast = true;
+            break;
+        }
+        assert!(logged(
+            &game,
+            "Player tries to cast Mend, but the spell fizzles."
+        ));
+    }
+    assert!(cast);
+    let hit_points = pools(&mut game).get(player).unwrap().hit_points.clone();
+    assert_eq!(hit_points.current, hit_points.max.min(29));
+}
@@ -0,0 +1,127 @@
+//! Stackable items share one backpack entry: picking up or buying more joins the stack,
+//! and using, dropping or selling takes one off it.
+
+mod common;
+
+use common::{backpack_index, player_position, pools, set_gold};
+use roguelike_rust::{
+    backpack_items, buy_item, sell_item, Consumable, EquipmentChanged, Game, InBackpack, Item,
+    Name, PlayerCommand, Position, ProvidesHealing, Stackable,
+};
+use specs::prelude::*;
+
+/// A Health Potion, which the raws make stackable, so the game can make more of it.
+fn potion<'a>(game: &'a mut Game, quantity: i32) -> EntityBuilder<'a> {
+    game.world_mut()
+        .create_entity()
+        .with(Name {
+            name: "Health Potion".to_string(),
+        })
+        .with(Item {
+            initiative_penalty: 0.0,
+            weight_lbs: 0.5,
+            base_value: 50.0,
+        })
+        .with(Consumable {})
+        .with(ProvidesHealing { heal_amount: 8 })
+        .with(Stackable { quantity })
+}
+
+fn quantity(game: &Game, item: Entity) -> i32 {
+    game.world()
+        .read_storage::<Stackable>()
+        .get(item)
+        .unwrap()
+        .quantity
+}
+
+fn weight(game: &mut Game) -> f32 {
+    let player = game.player();
+    pools(game).get(player).unwrap().total_weight
+}
+
+/// A game where the player carries a stack of `quantity` potions.
+fn game_with_stack(quantity: i32) -> (Game, Entity) {
+    let mut game = Game::with_seed(11);
+    let player = game.player();
+    game.apply(PlayerCommand::Wait);
+    let stack = potion(&mut game, quantity)
+        .with(InBackpack { owner: player })
+        .build();
+    (game, stack)
+}
+
+#[test]
+fn picking_one_up_joins_the_stack() {
+    let (mut game, stack) = game_with_stack(2);
+    let carried = backpack_items(game.world()).len();
+    let (x, y) = player_position(&game);
+    let found = potion(&mut game, 1).with(Position { x, y }).build();
+    game.apply(PlayerCommand::PickUp);
+    assert!(!game.world().is_alive(found));
+    assert_eq!(quantity(&game, stack), 3);
+    assert_eq!(backpack_items(game.world()).len(), carried);
+}
+
+#[test]
+fn buying_one_joins_the_stack() {
+    let (mut game, stack) = game_with_stack(2);
+    let carried = backpack_items(game.world()).len();
+    set_gold(&mut game, 100.0);
+    buy_item(game.world_mut(), "Health Potion", 60.0);
+    assert_eq!(quantity(&game, stack), 3);
+    assert_eq!(backpack_items(game.world()).len(), carried);
+}
+
+#[test]
+fn the_whole_stack_weighs_in() {
+    let mut game = Game::with_seed(11);
+    let player = game.player();
+    game.apply(PlayerCommand::Wait);
+    let without_potions = weight(&mut game);
+    potion(&mut game, 3)
+        .with(InBackpack { owner: player })
+        .build();
+    game.world_mut()
+        .write_storage::<EquipmentChanged>()
+        .insert(player, EquipmentChanged {})
+        .unwrap();
+    game.apply(PlayerCommand::Wait);
+    assert_eq!(weight(&mut game), without_potions + 1.5);
+}
+
+#[test]
+fn using_dropping_and_selling_each_take_one() {
+    let (mut game, stack) = game_with_stack(4);
+    let item = backpack_index(&game, stack);
+    game.apply(PlayerCommand::UseItem { item, target: None });
+    assert_eq!(quantity(&game, stack), 3);
+
+    game.apply(PlayerCommand::DropItem { item });
+    assert_eq!(quantity(&game, stack), 2);
+    let (x, y) = player_position(&game);
+    let dropped: Vec<Entity> = {
+        let names = game.world().read_storage::<Name>();
+        let positions = game.world().read_storage::<Position>();
+        (&game.world().entities(), &names, &positions)
+            .join()
+            .filter(|(_, name, at)| name.name == "Health Potion" && at.x == x && at.y == y)
+            .map(|(entity, _, _)| entity)
+            .collect()
+    };
+    assert_eq!(dropped.len(), 1);
+    assert_eq!(quantity(&game, dropped[0]), 1);
+
+    set_gold(&mut game, 0.0);
+    sell_item(game.world_mut(), stack);
+    assert_eq!(quantity(&game, stack), 1);
+    let player = game.player();
+    assert_eq!(pools(&mut game).get(player).unwrap().gold, 40.0);
+}
+
+#[test]
+fn the_last_one_goes_like_any_other_item() {
+    let (mut game, stack) = game_with_stack(1);
+    sell_item(game.world_mut(), stack);
+    assert!(!game.world().is_alive(stack));
+}
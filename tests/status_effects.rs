@@ -0,0 +1,169 @@
+//! Timed statuses count down on their owner's turns.
+
+mod common;
+
+use common::{hit_points, logged, set_hit_points};
+use roguelike_rust::{
+    backpack_items, Attributes, Consumable, Game, HungerClock, HungerState, InBackpack,
+    InflictsStatus, Initiative, Item, Name, PlayerCommand, Status, StatusEffects, StatusKind,
+};
+use specs::prelude::*;
+
+fn status(kind: StatusKind, turns: i32, magnitude: i32) -> Status {
+    Status {
+        kind,
+        turns,
+        magnitude,
+    }
+}
+
+fn statuses(game: &Game) -> Option<StatusEffects> {
+    game.world()
+        .read_storage::<StatusEffects>()
+        .get(game.player())
+        .cloned()
+}
+
+/// Gives the player a potion that puts the statuses on them, and drinks it.
+fn drink(game: &mut Game, statuses: Vec<Status>) {
+    let player = game.player();
+    game.world_mut()
+        .create_entity()
+        .with(Name {
+            name: "Test Potion".to_string(),
+        })
+        .with(Item {
+            initiative_penalty: 0.0,
+            weight_lbs: 0.0,
+            base_value: 0.0,
+        })
+        .with(Consumable {})
+        .with(InflictsStatus { statuses })
+        .with(InBackpack { owner: player })
+        .build();
+    let item = backpack_items(game.world()).len() - 1;
+    game.apply(PlayerCommand::UseItem { item, target: None });
+}
+
+fn initiative(game: &Game) -> i32 {
+    game.world()
+        .read_storage::<Initiative>()
+        .get(game.player())
+        .unwrap()
+        .current
+}
+
+/// A game past the player's first turn, which comes straight away, so that each command
+/// then takes a turn. The player is starving, so doesn't regenerate and only statuses
+/// change their health.
+fn starving_game() -> Game {
+    let mut game = Game::with_seed(11);
+    let player = game.player();
+    game.apply(PlayerCommand::Wait);
+    let mut clocks = game.world_mut().write_storage::<HungerClock>();
+    let clock = clocks.get_mut(player).unwrap();
+    clock.state = HungerState::Starving;
+    clock.duration = 1000;
+    drop(clocks);
+    game
+}
+
+#[test]
+fn poison_stacks_and_anything_else_is_refreshed() {
+    let mut effects = StatusEffects::default();
+    effects.add(status(StatusKind::Poison, 2, 2));
+    effects.add(status(StatusKind::Poison, 3, 1));
+    effects.add(status(StatusKind::Haste, 5, 1));
+    effects.add(status(StatusKind::Haste, 2, 3));
+    assert_eq!(
+        effects.effects,
+        vec![
+            status(StatusKind::Poison, 2, 2),
+            status(StatusKind::Poison, 3, 1),
+            status(StatusKind::Haste, 5, 3),
+        ]
+    );
+}
+
+#[test]
+fn each_dose_of_poison_hurts_until_it_runs_out() {
+    let mut game = starving_game();
+    set_hit_points(&mut game, 20);
+    drink(
+        &mut game,
+        vec![
+            status(StatusKind::Poison, 2, 2),
+            status(StatusKind::Poison, 3, 1),
+        ],
+    );
+    assert!(logged(&game, "You are poisoned."));
+    assert_eq!(hit_points(&game), 17);
+    game.apply(PlayerCommand::Wait);
+    assert_eq!(hit_points(&game), 14);
+    assert!(!logged(&game, "You are no longer poisoned."));
+    game.apply(PlayerCommand::Wait);
+    assert_eq!(hit_points(&game), 13);
+    assert!(logged(&game, "You are no longer poisoned."));
+    assert!(statuses(&game).is_none());
+}
+
+#[test]
+fn regeneration_heals_each_turn() {
+    let mut game = starving_game();
+    set_hit_points(&mut game, 13);
+    drink(&mut game, vec![status(StatusKind::Regeneration, 2, 3)]);
+    assert!(logged(&game, "You are regenerating."));
+    assert_eq!(hit_points(&game), 16);
+    game.apply(PlayerCommand::Wait);
+    assert_eq!(hit_points(&game), 19);
+    assert!(statuses(&game).is_none());
+}
+
+#[test]
+fn haste_and_slow_change_the_wait_for_a_turn() {
+    let mut game = starving_game();
+    drink(&mut game, vec![status(StatusKind::Haste, 3, 20)]);
+    assert!(initiative(&game) < 0);
+    game.apply(PlayerCommand::Wait);
+    game.apply(PlayerCommand::Wait);
+    assert!(statuses(&game).is_none());
+    // The last hasted turn comes round at once
+    game.apply(PlayerCommand::Wait);
+    drink(&mut game, vec![status(StatusKind::Slow, 2, 20)]);
+    assert!(initiative(&game) > 20);
+    game.apply(PlayerCommand::Wait);
+    assert!(statuses(&game).is_none());
+}
+
+#[test]
+fn weakness_saps_might_while_it_lasts() {
+    let mut game = starving_game();
+    let might = |game: &Game| {
+        game.world()
+            .read_storage::<Attributes>()
+            .get(game.player())
+            .unwrap()
+            .might
+            .clone()
+    };
+    let before = might(&game);
+    drink(&mut game, vec![status(StatusKind::Weakness, 2, 4)]);
+    assert!(logged(&game, "You are weakened."));
+    assert_eq!(might(&game).modifiers, -4);
+    assert_eq!(might(&game).bonus, (before.base - 4 - 10) / 2);
+    game.apply(PlayerCommand::Wait);
+    game.apply(PlayerCommand::Wait);
+    assert!(logged(&game, "You are no longer weakened."));
+    assert_eq!(might(&game).modifiers, 0);
+    assert_eq!(might(&game).bonus, before.bonus);
+}
+
+#[test]
+fn the_paralyzed_lose_their_turns() {
+    let mut game = starving_game();
+    drink(&mut game, vec![status(StatusKind::Paralysis, 2, 0)]);
+    assert!(logged(&game, "You are paralyzed."));
+    assert!(logged(&game, "You are paralyzed, and lose your turn."));
+    assert!(logged(&game, "You are no longer paralyzed."));
+    assert!(statuses(&game).is_none());
+}